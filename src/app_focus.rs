@@ -0,0 +1,265 @@
+// 按前台应用自动切换配置档案
+use serde::{Deserialize, Serialize};
+use log::info;
+use std::time::{Duration, Instant};
+
+/// 当前获得焦点的应用信息
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FocusedApp {
+    pub window_class: String,
+    pub window_instance: String,
+    pub process_name: Option<String>,
+}
+
+impl FocusedApp {
+    /// 检查应用是否匹配规则中的名称（不区分大小写，匹配窗口类、实例名或进程名）
+    pub fn matches(&self, app: &str) -> bool {
+        let app = app.trim();
+        if app.is_empty() {
+            return false;
+        }
+        self.window_class.eq_ignore_ascii_case(app)
+            || self.window_instance.eq_ignore_ascii_case(app)
+            || self.process_name.as_deref().is_some_and(|name| name.eq_ignore_ascii_case(app))
+    }
+
+    /// 用于界面显示的名称
+    pub fn display_name(&self) -> String {
+        match &self.process_name {
+            Some(process) => format!("{} ({})", self.window_class, process),
+            None => self.window_class.clone(),
+        }
+    }
+}
+
+/// 焦点窗口来源
+///
+/// 真实实现读取窗口系统，测试中可以替换为返回固定结果的实现。
+pub trait FocusProvider: Send {
+    /// 获取当前获得焦点的应用，没有焦点窗口时返回 None
+    fn focused_app(&mut self) -> Option<FocusedApp>;
+}
+
+/// 应用与配置档案的对应规则
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AppProfileRule {
+    pub app: String,      // 窗口类或进程名，例如 firefox
+    pub profile: String,  // 要切换到的档案名称
+}
+
+/// 根据焦点窗口自动选择配置档案
+pub struct AppProfileSwitcher {
+    provider: Box<dyn FocusProvider>,
+    current_app: Option<FocusedApp>,
+    last_poll: Instant,
+    poll_interval: Duration,
+}
+
+impl AppProfileSwitcher {
+    pub fn new(provider: Box<dyn FocusProvider>) -> Self {
+        Self {
+            provider,
+            current_app: None,
+            last_poll: Instant::now().checked_sub(Duration::from_secs(1)).unwrap_or(Instant::now()),
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+
+    /// 为当前平台创建切换器，不支持时返回 None
+    pub fn for_current_platform() -> Option<Self> {
+        #[cfg(target_os = "linux")]
+        {
+            match X11FocusProvider::new() {
+                Ok(provider) => {
+                    info!("已连接X11，启用按应用自动切换配置档案");
+                    Some(Self::new(Box::new(provider)))
+                }
+                Err(e) => {
+                    log::warn!("无法连接X11，按应用自动切换不可用: {}", e);
+                    None
+                }
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            None
+        }
+    }
+
+    /// 当前焦点应用
+    pub fn current_app(&self) -> Option<&FocusedApp> {
+        self.current_app.as_ref()
+    }
+
+    /// 轮询焦点窗口，焦点切换到匹配规则的应用时返回目标档案名称
+    pub fn poll(&mut self, rules: &[AppProfileRule]) -> Option<String> {
+        if self.last_poll.elapsed() < self.poll_interval {
+            return None;
+        }
+        self.last_poll = Instant::now();
+
+        let app = self.provider.focused_app();
+        if app == self.current_app {
+            return None;
+        }
+        self.current_app = app;
+
+        let app = self.current_app.as_ref()?;
+        let rule = rules.iter().find(|rule| app.matches(&rule.app))?;
+        info!("焦点切换到 {}，匹配档案 {}", app.display_name(), rule.profile);
+        Some(rule.profile.clone())
+    }
+}
+
+/// 通过 X11 的 _NET_ACTIVE_WINDOW 读取焦点窗口
+#[cfg(target_os = "linux")]
+pub struct X11FocusProvider {
+    conn: x11rb::rust_connection::RustConnection,
+    root: x11rb::protocol::xproto::Window,
+    net_active_window: x11rb::protocol::xproto::Atom,
+    net_wm_pid: x11rb::protocol::xproto::Atom,
+}
+
+#[cfg(target_os = "linux")]
+impl X11FocusProvider {
+    pub fn new() -> Result<Self, String> {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::ConnectionExt;
+
+        let (conn, screen_num) = x11rb::connect(None).map_err(|e| e.to_string())?;
+        let root = conn.setup().roots[screen_num].root;
+        let intern = |name: &[u8]| -> Result<_, String> {
+            conn.intern_atom(false, name)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map(|reply| reply.atom)
+                .map_err(|e| e.to_string())
+        };
+        let net_active_window = intern(b"_NET_ACTIVE_WINDOW")?;
+        let net_wm_pid = intern(b"_NET_WM_PID")?;
+
+        Ok(Self {
+            conn,
+            root,
+            net_active_window,
+            net_wm_pid,
+        })
+    }
+
+    /// 读取窗口上单个 32 位属性
+    fn property_u32(
+        &self,
+        window: x11rb::protocol::xproto::Window,
+        property: x11rb::protocol::xproto::Atom,
+        property_type: x11rb::protocol::xproto::AtomEnum,
+    ) -> Option<u32> {
+        use x11rb::protocol::xproto::ConnectionExt;
+
+        let reply = self.conn
+            .get_property(false, window, property, property_type, 0, 1)
+            .ok()?
+            .reply()
+            .ok()?;
+        reply.value32()?.next()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl FocusProvider for X11FocusProvider {
+    fn focused_app(&mut self) -> Option<FocusedApp> {
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+        let window = self.property_u32(self.root, self.net_active_window, AtomEnum::WINDOW)?;
+        if window == 0 {
+            return None;
+        }
+
+        // WM_CLASS 由两个以 \0 结尾的字符串组成：实例名和类名
+        let wm_class = self.conn
+            .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)
+            .ok()?
+            .reply()
+            .ok()?;
+        let mut parts = wm_class.value
+            .split(|b| *b == 0)
+            .map(|part| String::from_utf8_lossy(part).to_string());
+        let window_instance = parts.next().unwrap_or_default();
+        let window_class = parts.next().unwrap_or_default();
+
+        // 通过 _NET_WM_PID 和 /proc 获取进程名
+        let process_name = self.property_u32(window, self.net_wm_pid, AtomEnum::CARDINAL)
+            .and_then(|pid| std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok())
+            .map(|comm| comm.trim().to_string());
+
+        Some(FocusedApp {
+            window_class,
+            window_instance,
+            process_name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// 返回测试设置的焦点应用
+    struct FakeProvider(Arc<Mutex<Option<FocusedApp>>>);
+
+    impl FocusProvider for FakeProvider {
+        fn focused_app(&mut self) -> Option<FocusedApp> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    fn app(class: &str, process: Option<&str>) -> Option<FocusedApp> {
+        Some(FocusedApp {
+            window_class: class.to_string(),
+            window_instance: class.to_lowercase(),
+            process_name: process.map(str::to_string),
+        })
+    }
+
+    fn switcher() -> (AppProfileSwitcher, Arc<Mutex<Option<FocusedApp>>>) {
+        let focus = Arc::new(Mutex::new(None));
+        let mut switcher = AppProfileSwitcher::new(Box::new(FakeProvider(focus.clone())));
+        switcher.poll_interval = Duration::ZERO;
+        (switcher, focus)
+    }
+
+    fn rules() -> Vec<AppProfileRule> {
+        vec![
+            AppProfileRule { app: "firefox".to_string(), profile: "浏览".to_string() },
+            AppProfileRule { app: "krita".to_string(), profile: "绘图".to_string() },
+        ]
+    }
+
+    #[test]
+    fn switches_only_when_focus_changes_to_matching_app() {
+        let (mut switcher, focus) = switcher();
+        assert_eq!(switcher.poll(&rules()), None);
+
+        *focus.lock().unwrap() = app("Firefox", None);
+        assert_eq!(switcher.poll(&rules()).as_deref(), Some("浏览"));
+        // 焦点没有变化时不重复切换，用户手动切换的档案得以保留
+        assert_eq!(switcher.poll(&rules()), None);
+
+        *focus.lock().unwrap() = app("Terminal", None);
+        assert_eq!(switcher.poll(&rules()), None);
+        assert_eq!(switcher.current_app().map(|app| app.window_class.as_str()), Some("Terminal"));
+
+        // 按进程名匹配，不区分大小写
+        *focus.lock().unwrap() = app("Unknown", Some("Krita"));
+        assert_eq!(switcher.poll(&rules()).as_deref(), Some("绘图"));
+    }
+
+    #[test]
+    fn waits_for_poll_interval() {
+        let (mut switcher, focus) = switcher();
+        switcher.poll_interval = Duration::from_secs(60);
+        switcher.last_poll = Instant::now();
+        *focus.lock().unwrap() = app("firefox", None);
+        assert_eq!(switcher.poll(&rules()), None);
+    }
+}
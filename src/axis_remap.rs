@@ -0,0 +1,211 @@
+// 按手柄设置的轴重映射
+use gilrs::Axis;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::device_manager::{PadState, TRACKED_AXES};
+
+/// 轴的取值范围转换
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AxisConversion {
+    #[default]
+    None,
+    /// 摇杆轴（-1..1）当作扳机（0..1）使用
+    StickToTrigger,
+    /// 扳机（0..1）当作摇杆轴（-1..1）使用
+    TriggerToStick,
+}
+
+impl AxisConversion {
+    pub const ALL: [AxisConversion; 3] = [
+        AxisConversion::None,
+        AxisConversion::StickToTrigger,
+        AxisConversion::TriggerToStick,
+    ];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            AxisConversion::None => "不转换",
+            AxisConversion::StickToTrigger => "摇杆当扳机",
+            AxisConversion::TriggerToStick => "扳机当摇杆",
+        }
+    }
+
+    fn apply(self, value: f32) -> f32 {
+        match self {
+            AxisConversion::None => value,
+            AxisConversion::StickToTrigger => (value + 1.0) / 2.0,
+            AxisConversion::TriggerToStick => value * 2.0 - 1.0,
+        }
+    }
+}
+
+/// 轴在界面上显示的名称
+pub fn axis_display_name(axis: Axis) -> &'static str {
+    match axis {
+        Axis::LeftStickX => "左摇杆 X",
+        Axis::LeftStickY => "左摇杆 Y",
+        Axis::RightStickX => "右摇杆 X",
+        Axis::RightStickY => "右摇杆 Y",
+        Axis::LeftZ => "左扳机",
+        Axis::RightZ => "右扳机",
+        _ => "其他轴",
+    }
+}
+
+/// 单个轴的处理，先反转再转换范围
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct AxisRule {
+    pub invert: bool,
+    pub conversion: AxisConversion,
+}
+
+impl AxisRule {
+    fn apply(self, value: f32) -> f32 {
+        let value = if self.invert { -value } else { value };
+        self.conversion.apply(value).clamp(-1.0, 1.0)
+    }
+}
+
+/// 一个手柄的轴映射表
+///
+/// 按顺序处理：先交换左右摇杆，再交换每个摇杆的 X/Y，最后按轴名称应用反转和范围转换。
+/// 规则作用于交换后的轴，例如交换摇杆后 `LeftStickX` 的规则作用于原来的右摇杆 X。
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct AxisRemap {
+    pub swap_sticks: bool,
+    pub swap_xy: bool,
+    /// 以 `Axis` 变体名为键的规则
+    pub rules: BTreeMap<String, AxisRule>,
+}
+
+impl AxisRemap {
+    /// 是否不改变任何轴
+    pub fn is_identity(&self) -> bool {
+        !self.swap_sticks && !self.swap_xy && self.rules.values().all(|rule| *rule == AxisRule::default())
+    }
+
+    pub fn rule(&self, axis: Axis) -> AxisRule {
+        self.rules.get(&format!("{:?}", axis)).copied().unwrap_or_default()
+    }
+
+    /// 设置轴的规则，默认规则会从表中移除
+    pub fn set_rule(&mut self, axis: Axis, rule: AxisRule) {
+        let name = format!("{:?}", axis);
+        if rule == AxisRule::default() {
+            self.rules.remove(&name);
+        } else {
+            self.rules.insert(name, rule);
+        }
+    }
+
+    /// 返回重映射后的手柄状态，按钮不受影响
+    pub fn apply(&self, state: &PadState) -> PadState {
+        if self.is_identity() {
+            return state.clone();
+        }
+        let mut remapped = state.clone();
+        for axis in TRACKED_AXES {
+            let value = state.value(self.source(axis));
+            remapped.set_value(axis, self.rule(axis).apply(value));
+        }
+        remapped
+    }
+
+    /// 目标轴读取的原始轴
+    fn source(&self, axis: Axis) -> Axis {
+        let axis = if self.swap_xy {
+            match axis {
+                Axis::LeftStickX => Axis::LeftStickY,
+                Axis::LeftStickY => Axis::LeftStickX,
+                Axis::RightStickX => Axis::RightStickY,
+                Axis::RightStickY => Axis::RightStickX,
+                other => other,
+            }
+        } else {
+            axis
+        };
+        if self.swap_sticks {
+            match axis {
+                Axis::LeftStickX => Axis::RightStickX,
+                Axis::LeftStickY => Axis::RightStickY,
+                Axis::RightStickX => Axis::LeftStickX,
+                Axis::RightStickY => Axis::LeftStickY,
+                other => other,
+            }
+        } else {
+            axis
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gilrs::Button;
+
+    fn state() -> PadState {
+        let mut state = PadState::default();
+        state.set_value(Axis::LeftStickX, 0.1);
+        state.set_value(Axis::LeftStickY, 0.2);
+        state.set_value(Axis::RightStickX, 0.3);
+        state.set_value(Axis::RightStickY, 0.4);
+        state.set_pressed(Button::South, true);
+        state
+    }
+
+    fn values(state: &PadState) -> [f32; 4] {
+        [Axis::LeftStickX, Axis::LeftStickY, Axis::RightStickX, Axis::RightStickY].map(|axis| state.value(axis))
+    }
+
+    #[test]
+    fn swaps_sticks_and_xy() {
+        let mut remap = AxisRemap { swap_sticks: true, ..AxisRemap::default() };
+        assert_eq!(values(&remap.apply(&state())), [0.3, 0.4, 0.1, 0.2]);
+
+        remap = AxisRemap { swap_xy: true, ..AxisRemap::default() };
+        assert_eq!(values(&remap.apply(&state())), [0.2, 0.1, 0.4, 0.3]);
+
+        remap.swap_sticks = true;
+        let remapped = remap.apply(&state());
+        assert_eq!(values(&remapped), [0.4, 0.3, 0.2, 0.1]);
+        assert!(remapped.is_pressed(Button::South));
+    }
+
+    #[test]
+    fn rules_apply_to_swapped_axes() {
+        let mut remap = AxisRemap { swap_sticks: true, ..AxisRemap::default() };
+        remap.set_rule(Axis::LeftStickX, AxisRule { invert: true, conversion: AxisConversion::None });
+        assert_eq!(values(&remap.apply(&state())), [-0.3, 0.4, 0.1, 0.2]);
+    }
+
+    #[test]
+    fn converts_between_stick_and_trigger_ranges() {
+        let mut remap = AxisRemap::default();
+        remap.set_rule(Axis::LeftZ, AxisRule { invert: false, conversion: AxisConversion::StickToTrigger });
+        remap.set_rule(Axis::RightZ, AxisRule { invert: false, conversion: AxisConversion::TriggerToStick });
+        let mut state = state();
+        for (left, right, expected) in [(-1.0, 0.0, (0.0, -1.0)), (0.0, 0.75, (0.5, 0.5)), (1.0, 1.0, (1.0, 1.0))] {
+            state.set_value(Axis::LeftZ, left);
+            state.set_value(Axis::RightZ, right);
+            let remapped = remap.apply(&state);
+            assert_eq!((remapped.value(Axis::LeftZ), remapped.value(Axis::RightZ)), expected);
+        }
+
+        // 先反转再转换范围
+        remap.set_rule(Axis::LeftZ, AxisRule { invert: true, conversion: AxisConversion::StickToTrigger });
+        state.set_value(Axis::LeftZ, 0.5);
+        assert_eq!(remap.apply(&state).value(Axis::LeftZ), 0.25);
+    }
+
+    #[test]
+    fn default_rules_are_removed() {
+        let mut remap = AxisRemap::default();
+        remap.set_rule(Axis::LeftStickY, AxisRule { invert: true, conversion: AxisConversion::None });
+        assert!(!remap.is_identity());
+        remap.set_rule(Axis::LeftStickY, AxisRule::default());
+        assert!(remap.rules.is_empty());
+        assert!(remap.is_identity());
+        assert_eq!(values(&remap.apply(&state())), values(&state()));
+    }
+}
@@ -0,0 +1,142 @@
+// 手柄电量
+use gilrs::PowerInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::device_manager::PadId;
+
+// 电量回升超过阈值该值后才会再次警告，避免电量在阈值附近波动时反复提示
+const WARNING_HYSTERESIS: u8 = 5;
+
+/// 手柄的电池状态
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BatteryLevel {
+    #[default]
+    Unknown,
+    /// 有线连接，没有电池
+    Wired,
+    Discharging(u8),
+    Charging(u8),
+    Charged,
+}
+
+impl From<PowerInfo> for BatteryLevel {
+    fn from(info: PowerInfo) -> Self {
+        match info {
+            PowerInfo::Unknown => BatteryLevel::Unknown,
+            PowerInfo::Wired => BatteryLevel::Wired,
+            PowerInfo::Discharging(level) => BatteryLevel::Discharging(level),
+            PowerInfo::Charging(level) => BatteryLevel::Charging(level),
+            PowerInfo::Charged => BatteryLevel::Charged,
+        }
+    }
+}
+
+impl BatteryLevel {
+    /// 界面上显示的电量
+    pub fn describe(self) -> String {
+        match self {
+            BatteryLevel::Unknown => "电量未知".to_string(),
+            BatteryLevel::Wired => "有线".to_string(),
+            BatteryLevel::Discharging(level) => format!("🔋 {}%", level),
+            BatteryLevel::Charging(level) => format!("⚡ 充电中 {}%", level),
+            BatteryLevel::Charged => "⚡ 已充满".to_string(),
+        }
+    }
+
+    /// 电池供电时是否低于阈值
+    pub fn is_low(self, threshold: u8) -> bool {
+        matches!(self, BatteryLevel::Discharging(level) if level <= threshold)
+    }
+}
+
+/// 低电量警告设置
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BatterySettings {
+    pub warn_enabled: bool,
+    /// 低于该百分比时警告
+    pub threshold: u8,
+}
+
+impl Default for BatterySettings {
+    fn default() -> Self {
+        Self {
+            warn_enabled: true,
+            threshold: 20,
+        }
+    }
+}
+
+/// 跟踪每个手柄是否已经警告过
+///
+/// 电量降到阈值以下时警告一次；开始充电或电量回升后重新计算。
+#[derive(Default)]
+pub struct BatteryMonitor {
+    warned: HashSet<PadId>,
+}
+
+impl BatteryMonitor {
+    /// 检查手柄电量，需要警告时返回当前电量
+    pub fn check(&mut self, id: PadId, level: BatteryLevel, threshold: u8) -> Option<u8> {
+        match level {
+            BatteryLevel::Discharging(percent) if percent <= threshold => {
+                self.warned.insert(id).then_some(percent)
+            }
+            BatteryLevel::Discharging(percent) if percent <= threshold.saturating_add(WARNING_HYSTERESIS) => None,
+            BatteryLevel::Unknown => None,
+            _ => {
+                self.warned.remove(&id);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAD: PadId = PadId(0);
+
+    #[test]
+    fn is_low_only_when_discharging_at_or_below_threshold() {
+        assert!(BatteryLevel::Discharging(20).is_low(20));
+        assert!(!BatteryLevel::Discharging(21).is_low(20));
+        assert!(!BatteryLevel::Charging(5).is_low(20));
+        assert!(!BatteryLevel::Unknown.is_low(20));
+    }
+
+    #[test]
+    fn warns_once_below_threshold() {
+        let mut monitor = BatteryMonitor::default();
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(21), 20), None);
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(20), 20), Some(20));
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(15), 20), None);
+        // 电量未知时保持警告状态
+        assert_eq!(monitor.check(PAD, BatteryLevel::Unknown, 20), None);
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(10), 20), None);
+        // 每个手柄分别警告
+        assert_eq!(monitor.check(PadId(1), BatteryLevel::Discharging(10), 20), Some(10));
+    }
+
+    #[test]
+    fn rearms_after_recovering_past_hysteresis() {
+        let mut monitor = BatteryMonitor::default();
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(19), 20), Some(19));
+        // 在阈值附近波动不会重复警告
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(25), 20), None);
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(18), 20), None);
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(26), 20), None);
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(18), 20), Some(18));
+    }
+
+    #[test]
+    fn charging_rearms_warning() {
+        let mut monitor = BatteryMonitor::default();
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(10), 20), Some(10));
+        assert_eq!(monitor.check(PAD, BatteryLevel::Charging(12), 20), None);
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(12), 20), Some(12));
+        assert_eq!(monitor.check(PAD, BatteryLevel::Charged, 20), None);
+        assert_eq!(monitor.check(PAD, BatteryLevel::Discharging(12), 20), Some(12));
+    }
+}
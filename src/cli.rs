@@ -0,0 +1,155 @@
+// 命令行参数
+use log::LevelFilter;
+use std::path::PathBuf;
+
+use crate::session::SessionArgs;
+
+/// 命令行帮助
+pub const USAGE: &str = "\
+用法: gamepad-mouse-control [选项]
+
+选项:
+  --config <路径>        使用指定的配置文件，扩展名为 .toml 时按 TOML 读写
+  --profile <名称>       启动时激活的配置档案
+  --log-level <级别>     日志级别: off、error、warn、info、debug、trace，默认 info
+  --log-dir <目录>       日志文件所在目录
+  --no-gui               不显示窗口，在后台运行
+  --device <UUID|名称>   只使用 UUID 或名称匹配的手柄
+  --record <路径>        启动后录制输入，退出时保存到该文件
+  --replay <路径>        启动后回放录制文件
+  --events-json          把控制器事件以 JSON 行写到标准输出
+  -h, --help             显示本帮助
+";
+
+/// 解析后的命令行参数
+#[derive(Clone, Debug, Default)]
+pub struct CliArgs {
+    pub config: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub log_level: Option<LevelFilter>,
+    pub log_dir: Option<PathBuf>,
+    pub no_gui: bool,
+    pub device: Option<String>,
+    pub session: SessionArgs,
+    /// 把控制器事件写到标准输出
+    pub events_json: bool,
+}
+
+/// 命令行要求的操作
+pub enum CliCommand {
+    Run(CliArgs),
+    Help,
+}
+
+impl CliArgs {
+    /// 解析命令行参数（不含程序名），选项值可以写作 `--config 路径` 或 `--config=路径`
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<CliCommand, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => (option.to_string(), Some(value.to_string())),
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline_value.clone()
+                    .or_else(|| args.next())
+                    .filter(|value| !value.is_empty())
+                    .ok_or_else(|| format!("选项 {} 缺少参数", option))
+            };
+            match option.as_str() {
+                "-h" | "--help" => return Ok(CliCommand::Help),
+                "--no-gui" | "--events-json" if inline_value.is_some() => {
+                    return Err(format!("选项 {} 不接受参数", option));
+                }
+                "--config" => parsed.config = Some(PathBuf::from(value()?)),
+                "--profile" => parsed.profile = Some(value()?),
+                "--log-level" => {
+                    let level = value()?;
+                    parsed.log_level = Some(level.parse().map_err(|_| format!("无效的日志级别: {}", level))?);
+                }
+                "--log-dir" => parsed.log_dir = Some(PathBuf::from(value()?)),
+                "--no-gui" => parsed.no_gui = true,
+                "--device" => parsed.device = Some(value()?),
+                "--record" => parsed.session.record = Some(PathBuf::from(value()?)),
+                "--replay" => parsed.session.replay = Some(PathBuf::from(value()?)),
+                "--events-json" => parsed.events_json = true,
+                other => return Err(format!("未知的选项: {}", other)),
+            }
+        }
+        Ok(CliCommand::Run(parsed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliCommand, String> {
+        CliArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn run(args: &[&str]) -> CliArgs {
+        match parse(args) {
+            Ok(CliCommand::Run(cli)) => cli,
+            Ok(CliCommand::Help) => panic!("{:?} 不应显示帮助", args),
+            Err(e) => panic!("{:?}: {}", args, e),
+        }
+    }
+
+    #[test]
+    fn no_arguments_use_defaults() {
+        let cli = run(&[]);
+        assert!(cli.config.is_none() && cli.profile.is_none() && cli.log_level.is_none());
+        assert!(!cli.no_gui && !cli.events_json);
+    }
+
+    #[test]
+    fn option_values_may_be_separate_or_inline() {
+        let cli = run(&["--config", "pads/left.toml", "--profile=精确 模式", "--device", "Xbox=Wireless"]);
+        assert_eq!(cli.config, Some(PathBuf::from("pads/left.toml")));
+        assert_eq!(cli.profile.as_deref(), Some("精确 模式"));
+        assert_eq!(cli.device.as_deref(), Some("Xbox=Wireless"));
+
+        // 值中的等号保留
+        let cli = run(&["--device=name=pad", "--record=a.gms", "--replay", "b.gms"]);
+        assert_eq!(cli.device.as_deref(), Some("name=pad"));
+        assert_eq!(cli.session.record, Some(PathBuf::from("a.gms")));
+        assert_eq!(cli.session.replay, Some(PathBuf::from("b.gms")));
+    }
+
+    #[test]
+    fn flags_and_log_options() {
+        let cli = run(&["--no-gui", "--log-level", "debug", "--log-dir=/tmp/logs", "--events-json"]);
+        assert!(cli.no_gui && cli.events_json);
+        assert_eq!(cli.log_level, Some(LevelFilter::Debug));
+        assert_eq!(cli.log_dir, Some(PathBuf::from("/tmp/logs")));
+        assert_eq!(run(&["--log-level=WARN"]).log_level, Some(LevelFilter::Warn));
+        assert_eq!(parse(&["--log-level", "loud"]).err().unwrap(), "无效的日志级别: loud");
+    }
+
+    #[test]
+    fn flags_reject_values() {
+        assert_eq!(parse(&["--no-gui=yes"]).err().unwrap(), "选项 --no-gui 不接受参数");
+        assert_eq!(parse(&["--events-json="]).err().unwrap(), "选项 --events-json 不接受参数");
+    }
+
+    #[test]
+    fn missing_or_empty_values_are_errors() {
+        assert_eq!(parse(&["--config"]).err().unwrap(), "选项 --config 缺少参数");
+        assert_eq!(parse(&["--profile="]).err().unwrap(), "选项 --profile 缺少参数");
+        assert_eq!(parse(&["--device", ""]).err().unwrap(), "选项 --device 缺少参数");
+    }
+
+    #[test]
+    fn unknown_options_are_errors() {
+        assert_eq!(parse(&["--fast"]).err().unwrap(), "未知的选项: --fast");
+        assert_eq!(parse(&["config.json"]).err().unwrap(), "未知的选项: config.json");
+    }
+
+    #[test]
+    fn help_wins_over_other_arguments() {
+        assert!(matches!(parse(&["-h"]), Ok(CliCommand::Help)));
+        assert!(matches!(parse(&["--no-gui", "--help", "--unknown"]), Ok(CliCommand::Help)));
+    }
+}
@@ -1,10 +1,20 @@
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::Path;
-use log::{info, error};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// 命令行 --config 指定的配置文件路径
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// 按钮绑定可以使用的名称，与 gilrs `Button` 的变体名一致，空字符串表示未绑定
+pub const BUTTON_NAMES: [&str; 19] = [
+    "South", "East", "North", "West", "C", "Z",
+    "LeftTrigger", "LeftTrigger2", "RightTrigger", "RightTrigger2",
+    "Select", "Start", "Mode", "LeftThumb", "RightThumb",
+    "DPadUp", "DPadDown", "DPadLeft", "DPadRight",
+];
 
 /// 应用配置结构体
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Config {
     // 鼠标控制设置
     pub mouse_sensitivity: f32,
@@ -17,6 +27,25 @@ pub struct Config {
     pub right_click_button: String,
     pub middle_click_button: String,
     pub double_click_button: String,   // 双击按钮
+    // 以下按钮是后来新增的，旧配置文件中没有这些字段，读取时视为未绑定
+    #[serde(default)]
+    pub triple_click_button: String,   // 三击按钮
+    #[serde(default)]
+    pub back_button: String,           // 鼠标侧键：后退 (X1)
+    #[serde(default)]
+    pub forward_button: String,        // 鼠标侧键：前进 (X2)
+    #[serde(default)]
+    pub scroll_left_button: String,    // 滚轮向左倾斜
+    #[serde(default)]
+    pub scroll_right_button: String,   // 滚轮向右倾斜
+    #[serde(default)]
+    pub save_position_button: String,  // 记录当前光标位置
+    #[serde(default)]
+    pub saved_position_click_button: String, // 在记录的位置点击
+    #[serde(default)]
+    pub saved_click_position: Option<(i32, i32)>, // 记录的屏幕坐标
+    #[serde(default)]
+    pub profile_cycle_button: String,  // 切换到下一个配置档案
     
     // 摇杆配置
     pub invert_x_axis: bool,           // 是否反转X轴
@@ -45,6 +74,16 @@ impl Default for Config {    fn default() -> Self {
             right_click_button: "East".to_string(),  // B按钮
             middle_click_button: "West".to_string(), // X按钮
             double_click_button: "North".to_string(), // Y按钮
+            // 新增的功能默认不绑定，以免占用用户已有的按钮
+            triple_click_button: String::new(),
+            back_button: String::new(),
+            forward_button: String::new(),
+            scroll_left_button: String::new(),
+            scroll_right_button: String::new(),
+            save_position_button: String::new(),
+            saved_position_click_button: String::new(),
+            saved_click_position: None,
+            profile_cycle_button: "Select".to_string(),
               // 摇杆配置
             invert_x_axis: false, 
             invert_y_axis: false,
@@ -63,62 +102,31 @@ impl Default for Config {    fn default() -> Self {
 }
 
 impl Config {
-    /// 尝试从文件中加载配置，如果失败则使用默认配置
-    pub fn load() -> Self {
-        let config_path = Config::get_config_path();
-        
-        if let Ok(config_str) = fs::read_to_string(&config_path) {
-            match serde_json::from_str(&config_str) {
-                Ok(config) => {
-                    info!("配置已从 {:?} 成功加载", config_path);
-                    return config;
-                }
-                Err(e) => {
-                    error!("解析配置文件失败: {}", e);
-                }
-            }
-        }
-        
-        // 如果加载失败，则使用默认配置
-        let default_config = Config::default();
-        info!("使用默认配置");
-        default_config
+    /// 获取配置目录
+    pub fn get_config_dir() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| std::env::current_dir().unwrap())
+            .join("gamepad-mouse-control")
     }
     
-    /// 保存配置到文件
-    pub fn save(&self) -> Result<(), String> {
-        let config_path = Config::get_config_path();
-        
-        // 确保存在父目录
-        if let Some(parent) = Path::new(&config_path).parent() {
-            if !parent.exists() {
-                if let Err(e) = fs::create_dir_all(parent) {
-                    return Err(format!("无法创建配置目录: {}", e));
-                }
-            }
-        }
-        
-        // 将配置序列化为JSON并写入文件
-        match serde_json::to_string_pretty(self) {
-            Ok(json) => {
-                if let Err(e) = fs::write(&config_path, json) {
-                    return Err(format!("无法写入配置文件: {}", e));
-                }
-                info!("配置已保存到 {:?}", config_path);
-                Ok(())
-            }
-            Err(e) => Err(format!("配置序列化失败: {}", e)),
+    /// 使用指定的配置文件代替默认路径，只能在加载配置前设置一次
+    pub fn set_config_path(path: PathBuf) {
+        if CONFIG_PATH_OVERRIDE.set(path).is_err() {
+            log::warn!("配置文件路径已设置，忽略新的路径");
         }
     }
     
     /// 获取配置文件路径
-    fn get_config_path() -> String {
-        let mut path = dirs::config_dir()
-            .unwrap_or_else(|| std::env::current_dir().unwrap())
-            .to_string_lossy()
-            .to_string();
-        
-        path.push_str("/gamepad-mouse-control/config.json");
-        path
+    ///
+    /// 命令行指定了配置文件时使用该文件；否则配置目录中存在 `config.toml` 时使用 TOML 格式，
+    /// 不存在时使用 `config.json`。
+    pub fn get_config_path() -> String {
+        if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+            return path.to_string_lossy().to_string();
+        }
+        let config_dir = Self::get_config_dir();
+        let toml_path = config_dir.join("config.toml");
+        let path = if toml_path.exists() { toml_path } else { config_dir.join("config.json") };
+        path.to_string_lossy().to_string()
     }
 }
@@ -0,0 +1,236 @@
+// 配置文件的安全写入和历史备份
+use chrono::NaiveDateTime;
+use log::{info, warn};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// 默认保留的自动备份数量
+pub const DEFAULT_BACKUP_COUNT: usize = 10;
+
+// 自动备份的原因，只有这类备份会按数量清理
+const SAVE_REASON: &str = "save";
+// 连续保存（例如拖动数值）时，两次自动备份的最短间隔
+const SAVE_BACKUP_INTERVAL: Duration = Duration::from_secs(60);
+/// 备份和导出文件名中的时间戳格式
+pub const STAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// 备份所在目录，位于配置文件旁的 backups 文件夹
+pub fn backup_dir() -> PathBuf {
+    let config_path = PathBuf::from(Config::get_config_path());
+    config_path.parent()
+        .map(|parent| parent.join("backups"))
+        .unwrap_or_else(|| Config::get_config_dir().join("backups"))
+}
+
+/// 先写入同目录下的临时文件再重命名，写入中途崩溃或磁盘已满时原文件保持完整
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), String> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+    let result = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("无法写入配置文件: {}", e));
+    }
+    Ok(())
+}
+
+/// 把配置文件复制到备份目录，文件名带有原因和时间戳，返回备份路径
+///
+/// 备份保留原扩展名，可以直接按格式导入。
+pub fn backup_file(path: &Path, reason: &str) -> Result<PathBuf, String> {
+    backup_file_in(&backup_dir(), path, reason, chrono::Local::now().naive_local())
+}
+
+fn backup_file_in(dir: &Path, path: &Path, reason: &str, now: NaiveDateTime) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("无法创建备份目录: {}", e))?;
+    let extension = path.extension().map(|extension| extension.to_string_lossy().to_string()).unwrap_or_default();
+    let backup = dir.join(format!("{}.{}-{}.{}", file_stem(path), reason, now.format(STAMP_FORMAT), extension));
+    fs::copy(path, &backup).map_err(|e| format!("无法备份配置文件: {}", e))?;
+    info!("配置文件已备份到 {:?}", backup);
+    Ok(backup)
+}
+
+/// 保存前备份当前的配置文件，只保留最近 `keep` 个自动备份
+///
+/// 距上一次自动备份不足一分钟时跳过，避免连续保存把较早的版本全部挤掉。
+pub fn backup_before_save(path: &Path, keep: usize) {
+    backup_before_save_in(&backup_dir(), path, keep, chrono::Local::now().naive_local());
+}
+
+fn backup_before_save_in(dir: &Path, path: &Path, keep: usize, now: NaiveDateTime) {
+    if keep == 0 || !path.exists() {
+        return;
+    }
+    let saves: Vec<BackupEntry> = list_backups_in(dir, &file_stem(path))
+        .into_iter()
+        .filter(|backup| backup.reason == SAVE_REASON)
+        .collect();
+    let recent = saves.first().is_some_and(|latest| {
+        let age = now - latest.time;
+        age.to_std().is_ok_and(|age| age < SAVE_BACKUP_INTERVAL)
+    });
+    if recent {
+        return;
+    }
+    if let Err(e) = backup_file_in(dir, path, SAVE_REASON, now) {
+        warn!("{}", e);
+        return;
+    }
+    // 新备份排在最前，因此已有的备份只保留 keep - 1 个
+    for old in saves.iter().skip(keep - 1) {
+        if let Err(e) = fs::remove_file(&old.path) {
+            warn!("无法删除旧备份 {:?}: {}", old.path, e);
+        }
+    }
+}
+
+/// 一个配置备份
+pub struct BackupEntry {
+    pub path: PathBuf,
+    pub reason: String,
+    pub time: NaiveDateTime,
+}
+
+impl BackupEntry {
+    /// 从备份文件名 `<名称>.<原因>-<时间>.<扩展名>` 解析，`name` 为配置文件名（不含扩展名）
+    ///
+    /// 配置文件名本身可能含有点号，因此从右侧分出原因和时间。
+    fn from_path(path: PathBuf, name: &str) -> Option<Self> {
+        let stem = path.file_stem()?.to_str()?;
+        let (backup_name, tag) = stem.rsplit_once('.')?;
+        if backup_name != name {
+            return None;
+        }
+        let (reason, stamp) = tag.split_once('-')?;
+        let time = NaiveDateTime::parse_from_str(stamp, STAMP_FORMAT).ok()?;
+        Some(Self { reason: reason.to_string(), time, path })
+    }
+
+    /// 界面上显示的备份原因
+    pub fn reason_name(&self) -> String {
+        match self.reason.as_str() {
+            SAVE_REASON => "自动备份".to_string(),
+            "broken" => "无法解析的文件".to_string(),
+            "import" => "导入前".to_string(),
+            "restore" => "恢复前".to_string(),
+            version if version.starts_with('v') => format!("升级前（版本 {}）", &version[1..]),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// 当前配置文件的所有备份，最新的在前
+///
+/// 多个配置文件放在同一目录时共用备份目录，按文件名区分。
+pub fn list_backups() -> Vec<BackupEntry> {
+    list_backups_in(&backup_dir(), &file_stem(Path::new(&Config::get_config_path())))
+}
+
+fn list_backups_in(dir: &Path, name: &str) -> Vec<BackupEntry> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<BackupEntry> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| BackupEntry::from_path(entry.path(), name))
+        .collect();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.time));
+    backups
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    /// 每个测试使用单独的临时目录
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gamepad-mouse-backup-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn time(hour: u32, min: u32, sec: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 14).unwrap().and_hms_opt(hour, min, sec).unwrap()
+    }
+
+    #[test]
+    fn write_atomic_replaces_file_without_leaving_temp_file() {
+        let dir = temp_dir("atomic");
+        let path = dir.join("config.json");
+        write_atomic(&path, "{\"old\": true}").unwrap();
+        write_atomic(&path, "{\"new\": true}").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"new\": true}");
+        let names: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(names, vec!["config.json"]);
+
+        // 目录不存在时报错，不留下临时文件
+        assert!(write_atomic(&dir.join("missing").join("config.json"), "{}").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn parses_backup_names() {
+        let parse = |file: &str, name: &str| BackupEntry::from_path(PathBuf::from(file), name);
+
+        let entry = parse("/b/config.save-20260314-101500.json", "config").unwrap();
+        assert_eq!(entry.reason, "save");
+        assert_eq!(entry.time, time(10, 15, 0));
+        assert_eq!(entry.reason_name(), "自动备份");
+
+        // 文件名中含有点号
+        let entry = parse("/b/left.pad.v2-20260314-101500.toml", "left.pad").unwrap();
+        assert_eq!(entry.reason, "v2");
+        assert_eq!(entry.reason_name(), "升级前（版本 2）");
+
+        assert!(parse("/b/other.save-20260314-101500.json", "config").is_none());
+        assert!(parse("/b/left.pad.save-20260314-101500.toml", "left").is_none());
+        assert!(parse("/b/config.save-yesterday.json", "config").is_none());
+        assert!(parse("/b/config.json", "config").is_none());
+    }
+
+    #[test]
+    fn save_backups_are_throttled_and_pruned() {
+        let dir = temp_dir("prune");
+        let backups = dir.join("backups");
+        let path = dir.join("my.config.json");
+        fs::write(&path, "{}").unwrap();
+        // 其他原因的备份和其他配置文件的备份不参与清理
+        backup_file_in(&backups, &path, "import", time(9, 0, 0)).unwrap();
+        fs::write(backups.join("other.save-20260314-090000.json"), "{}").unwrap();
+
+        for minute in 0..5 {
+            backup_before_save_in(&backups, &path, 3, time(10, minute, 0));
+        }
+        // 距上一次不足一分钟，跳过
+        backup_before_save_in(&backups, &path, 3, time(10, 4, 30));
+
+        let listed = list_backups_in(&backups, "my.config");
+        let saves: Vec<NaiveDateTime> = listed.iter()
+            .filter(|backup| backup.reason == SAVE_REASON)
+            .map(|backup| backup.time)
+            .collect();
+        assert_eq!(saves, vec![time(10, 4, 0), time(10, 3, 0), time(10, 2, 0)]);
+        assert_eq!(listed.last().unwrap().reason, "import");
+        assert!(backups.join("other.save-20260314-090000.json").exists());
+
+        // 保留数量为 0 时不备份
+        backup_before_save_in(&backups, &path, 0, time(11, 0, 0));
+        assert_eq!(list_backups_in(&backups, "my.config").len(), 4);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
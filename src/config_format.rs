@@ -0,0 +1,276 @@
+// 配置文件格式：JSON 和 TOML
+//
+// 两种格式使用相同的结构，按文件扩展名选择。读取时都先转换为 JSON 值再交给迁移和默认值补齐，
+// 因此 TOML 文件同样支持旧版本迁移。TOML 文件保存时会在原文件基础上修改，保留手写的注释和顺序。
+use chrono::NaiveDateTime;
+use log::info;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::visit_mut::{self, VisitMut};
+use toml_edit::{DocumentMut, Formatted, Item, Table};
+
+use crate::config::Config;
+use crate::config_backup;
+use crate::migration::{self, Migrated};
+use crate::profile::ProfileStore;
+
+// 新建 TOML 文件时写在开头的说明
+const TOML_HEADER: &str = "# 手柄鼠标控制配置文件\n# 可以添加注释，程序保存设置时会保留注释和字段顺序\n\n";
+
+/// 配置文件格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    pub const ALL: [ConfigFormat; 2] = [ConfigFormat::Json, ConfigFormat::Toml];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "JSON",
+            ConfigFormat::Toml => "TOML",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Toml => "toml",
+        }
+    }
+
+    /// 按扩展名判断格式，`.toml` 以外的文件都按 JSON 处理
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    /// 把配置文本解析为 JSON 值
+    pub fn parse(self, text: &str) -> Result<Value, String> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(text).map_err(|e| format!("配置文件不是有效的 JSON: {}", e)),
+            ConfigFormat::Toml => toml_edit::de::from_str(text).map_err(|e| format!("配置文件不是有效的 TOML: {}", e)),
+        }
+    }
+
+    /// 序列化档案库，`existing` 为 TOML 原文件内容时在其基础上修改以保留注释
+    pub fn serialize(self, store: &ProfileStore, existing: Option<&str>) -> Result<String, String> {
+        match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(store).map_err(|e| format!("配置序列化失败: {}", e)),
+            ConfigFormat::Toml => {
+                let fresh = toml_edit::ser::to_string_pretty(store).map_err(|e| format!("配置序列化失败: {}", e))?;
+                let mut fresh: DocumentMut = fresh.parse().map_err(|e| format!("配置序列化失败: {}", e))?;
+                TidyFloats.visit_document_mut(&mut fresh);
+
+                match existing.and_then(|text| text.parse::<DocumentMut>().ok()) {
+                    Some(mut document) => {
+                        update_table(document.as_table_mut(), fresh.as_table().clone());
+                        Ok(document.to_string())
+                    }
+                    None => Ok(format!("{}{}", TOML_HEADER, fresh)),
+                }
+            }
+        }
+    }
+}
+
+/// 配置中的小数都是 f32，TOML 序列化时按 f64 输出会得到 0.029999999329447746 这样的值，
+/// 这里换成 f32 的最短表示，读回时数值不变
+struct TidyFloats;
+
+impl VisitMut for TidyFloats {
+    fn visit_value_mut(&mut self, node: &mut toml_edit::Value) {
+        if let toml_edit::Value::Float(float) = node {
+            let value = *float.value();
+            if let Ok(tidy) = (value as f32).to_string().parse::<f64>() {
+                let decor = float.decor().clone();
+                *float = Formatted::new(tidy);
+                *float.decor_mut() = decor;
+            }
+        }
+        visit_mut::visit_value_mut(self, node);
+    }
+}
+
+/// 用新表更新原表：删除不再存在的键，已有的键保留注释和位置，新键追加到末尾
+fn update_table(old: &mut Table, new: Table) {
+    let stale: Vec<String> = old.iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !new.contains_key(key))
+        .collect();
+    for key in stale {
+        old.remove(&key);
+    }
+    for (key, item) in new {
+        match old.get_mut(&key) {
+            Some(existing) => update_item(existing, item),
+            None => {
+                old.insert(&key, item);
+            }
+        }
+    }
+}
+
+fn update_item(old: &mut Item, new: Item) {
+    match (old, new) {
+        (Item::Table(old), Item::Table(new)) => update_table(old, new),
+        (Item::ArrayOfTables(old), Item::ArrayOfTables(new)) if old.len() == new.len() => {
+            for (old, new) in old.iter_mut().zip(new) {
+                update_table(old, new);
+            }
+        }
+        (Item::Value(old), Item::Value(new)) => {
+            // 值没有变化时保留原来的写法
+            if old.to_string().trim() != new.to_string().trim() {
+                let decor = old.decor().clone();
+                *old = new;
+                *old.decor_mut() = decor;
+            }
+        }
+        (old, new) => *old = new,
+    }
+}
+
+/// 读取配置文件，按扩展名选择格式，并迁移到当前版本
+pub fn read_store(path: &Path) -> Result<Migrated, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("无法读取配置文件: {}", e))?;
+    let value = ConfigFormat::from_path(path).parse(&text)?;
+    migration::load_store(value)
+}
+
+/// 把档案库写入配置文件，TOML 文件保留原有注释
+///
+/// 写入前按设置备份原文件，写入通过临时文件和重命名完成。
+pub fn write_store(path: &Path, store: &ProfileStore) -> Result<(), String> {
+    let format = ConfigFormat::from_path(path);
+    let existing = match format {
+        ConfigFormat::Toml => fs::read_to_string(path).ok(),
+        ConfigFormat::Json => None,
+    };
+    let text = format.serialize(store, existing.as_deref())?;
+    config_backup::backup_before_save(path, store.backup_count);
+    config_backup::write_atomic(path, &text)
+}
+
+/// 把整个档案库导出到当前配置文件所在的目录，返回导出文件路径
+///
+/// 文件名带有时间戳，不会覆盖之前的导出。
+pub fn export_store(store: &ProfileStore, format: ConfigFormat) -> Result<PathBuf, String> {
+    let path = export_path(Path::new(&Config::get_config_path()), format, chrono::Local::now().naive_local());
+    let text = format.serialize(store, None)?;
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("无法创建配置目录: {}", e))?;
+    }
+    config_backup::write_atomic(&path, &text)?;
+    info!("配置档案已导出为 {}: {:?}", format.display_name(), path);
+    Ok(path)
+}
+
+/// 导出文件的路径，例如配置文件 `config.json` 旁的 `config-export-20240102-030405.toml`
+fn export_path(config_path: &Path, format: ConfigFormat, now: NaiveDateTime) -> PathBuf {
+    let stem = config_path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_else(|| "config".to_string());
+    let name = format!("{}-export-{}.{}", stem, now.format(config_backup::STAMP_FORMAT), format.extension());
+    config_path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller_model::ControllerModel;
+    use crate::haptics::HapticCue;
+
+    /// 按格式序列化后再读回
+    fn round_trip(format: ConfigFormat, store: &ProfileStore, existing: Option<&str>) -> (String, ProfileStore) {
+        let text = format.serialize(store, existing).unwrap();
+        let loaded = migration::load_store(format.parse(&text).unwrap()).unwrap();
+        assert!(loaded.issues.is_empty());
+        (text, loaded.store)
+    }
+
+    fn assert_same(left: &ProfileStore, right: &ProfileStore) {
+        assert_eq!(serde_json::to_value(left).unwrap(), serde_json::to_value(right).unwrap());
+    }
+
+    fn sample_store() -> ProfileStore {
+        let mut store = ProfileStore::default();
+        store.profiles[0].config.saved_click_position = Some((640, -20));
+        store.profiles[0].config.dead_zone = 0.03;
+        store.model_profiles.insert(ControllerModel::PlayStation, "默认".to_string());
+        store.model_profiles.insert(ControllerModel::Xbox, "默认".to_string());
+        if let Some(cue) = store.haptics.cues.get_mut(&HapticCue::LowBattery) {
+            cue.enabled = false;
+            cue.pattern.strong = 0.7;
+        }
+        store
+    }
+
+    #[test]
+    fn json_and_toml_round_trip() {
+        let store = sample_store();
+        let (json, from_json) = round_trip(ConfigFormat::Json, &store, None);
+        assert_same(&from_json, &store);
+        let (toml, from_toml) = round_trip(ConfigFormat::Toml, &from_json, None);
+        assert_same(&from_toml, &store);
+        assert!(toml.starts_with(TOML_HEADER));
+        assert!(toml.contains("dead_zone = 0.03"), "{}", toml);
+
+        // 清除的坐标和新增的档案在两种格式中都能保存
+        let mut changed = from_toml;
+        changed.profiles[0].config.saved_click_position = None;
+        changed.add("第二", Config::default()).unwrap();
+        let (toml, from_toml) = round_trip(ConfigFormat::Toml, &changed, Some(&toml));
+        assert!(!toml.contains("saved_click_position"), "{}", toml);
+        assert_same(&from_toml, &changed);
+        let (_, from_json) = round_trip(ConfigFormat::Json, &from_toml, Some(&json));
+        assert_same(&from_json, &changed);
+        assert_eq!(from_json.profiles.len(), 2);
+        assert_eq!(from_json.profiles[0].config.saved_click_position, None);
+    }
+
+    #[test]
+    fn maps_serialize_in_stable_order() {
+        let store = sample_store();
+        let mut reversed = sample_store();
+        reversed.model_profiles.clear();
+        reversed.model_profiles.insert(ControllerModel::Xbox, "默认".to_string());
+        reversed.model_profiles.insert(ControllerModel::PlayStation, "默认".to_string());
+        for format in ConfigFormat::ALL {
+            assert_eq!(format.serialize(&store, None).unwrap(), format.serialize(&reversed, None).unwrap());
+        }
+        let json = ConfigFormat::Json.serialize(&store, None).unwrap();
+        assert!(json.find("\"Xbox\"") < json.find("\"PlayStation\""));
+    }
+
+    #[test]
+    fn toml_update_keeps_comments() {
+        let store = ProfileStore::default();
+        let original = ConfigFormat::Toml.serialize(&store, None).unwrap()
+            .replace("active_profile =", "# 启动时使用的档案\nactive_profile =")
+            .replace("mouse_sensitivity = 60.0", "mouse_sensitivity = 60.0 # 灵敏度");
+
+        let mut changed = store.clone();
+        changed.profiles[0].config.mouse_sensitivity = 30.0;
+        changed.auto_switch_enabled = true;
+        let updated = ConfigFormat::Toml.serialize(&changed, Some(&original)).unwrap();
+        assert!(updated.starts_with(TOML_HEADER));
+        assert!(updated.contains("# 启动时使用的档案\nactive_profile ="), "{}", updated);
+        assert!(updated.contains("mouse_sensitivity = 30.0 # 灵敏度"), "{}", updated);
+        assert!(updated.contains("auto_switch_enabled = true"));
+        // 没有变化时原样保留
+        assert_eq!(ConfigFormat::Toml.serialize(&store, Some(&original)).unwrap(), original);
+    }
+
+    #[test]
+    fn exports_next_to_config_file_with_timestamp() {
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap().and_hms_opt(3, 4, 5).unwrap();
+        assert_eq!(
+            export_path(Path::new("/home/user/.config/pad/config.json"), ConfigFormat::Toml, now),
+            PathBuf::from("/home/user/.config/pad/config-export-20240102-030405.toml"),
+        );
+    }
+}
@@ -0,0 +1,78 @@
+// 配置文件热重载
+use log::{error, info};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::Config;
+use crate::config_backup;
+use crate::config_format;
+use crate::profile::ProfileStore;
+use crate::validation::ValidationIssue;
+
+// 检查配置文件修改时间的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 定期检查配置文件的修改时间，文件在程序外被修改时重新读取
+///
+/// 读取结果与当前档案库相同时（例如程序自己保存的文件）不会报告变化。
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_poll: Instant,
+}
+
+impl ConfigWatcher {
+    pub fn new() -> Self {
+        let path = PathBuf::from(Config::get_config_path());
+        let modified = modified_time(&path);
+        Self {
+            path,
+            modified,
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// 轮询配置文件，内容变化时返回重新读取的档案库，无法解析时返回错误
+    ///
+    /// 解析失败的文件会先备份，之后程序保存配置时覆盖它也不会丢失这次修改。
+    pub fn poll(&mut self, current: &ProfileStore) -> Option<Result<(ProfileStore, Vec<ValidationIssue>), String>> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+
+        // 新建 config.toml 后配置文件会切换到它
+        let path = PathBuf::from(Config::get_config_path());
+        let modified = modified_time(&path);
+        if path == self.path && modified == self.modified {
+            return None;
+        }
+        self.path = path;
+        self.modified = modified;
+        // 文件被删除时保留当前配置
+        self.modified?;
+
+        let migrated = match config_format::read_store(&self.path) {
+            Ok(migrated) => migrated,
+            Err(e) => {
+                error!("重新加载配置文件失败: {}", e);
+                let message = match config_backup::backup_file(&self.path, "broken") {
+                    Ok(backup) => format!("{}，修改后的文件已备份到 {:?}", e, backup),
+                    Err(_) => e,
+                };
+                return Some(Err(message));
+            }
+        };
+        let (store, issues) = ProfileStore::from_migrated(migrated);
+        if issues.is_empty() && serde_json::to_value(&store).ok() == serde_json::to_value(current.persisted()).ok() {
+            return None;
+        }
+        info!("配置文件 {:?} 已在外部修改，重新加载", self.path);
+        Some(Ok((store, issues)))
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
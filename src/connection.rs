@@ -0,0 +1,499 @@
+// 手柄连接状态机
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+// 重试等待时间的上限，手写的配置中过大的值按此处理
+const MAX_DELAY_SECS: f32 = 24.0 * 3600.0;
+
+/// 连接状态
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    Idle,          // 用户停止，不自动连接
+    Scanning,      // 等待可用的手柄
+    Connecting,    // 正在启动控制器
+    Active,        // 至少一个手柄在运行
+    Suspended,     // 控制器保留但暂停输出
+    Reconnecting,  // 运行中的手柄全部断开，等待重新连接
+    Failed,        // 超过重试次数，需要手动启动
+}
+
+impl ConnectionState {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            ConnectionState::Idle => "空闲",
+            ConnectionState::Scanning => "查找手柄",
+            ConnectionState::Connecting => "连接中",
+            ConnectionState::Active => "运行中",
+            ConnectionState::Suspended => "已暂停",
+            ConnectionState::Reconnecting => "重新连接中",
+            ConnectionState::Failed => "失败",
+        }
+    }
+}
+
+/// 重试的退避设置
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Backoff {
+    pub initial_secs: f32,
+    pub max_secs: f32,
+    pub factor: f32,
+    // 最多重试次数，0 表示不限
+    pub max_attempts: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial_secs: 1.0,
+            max_secs: 30.0,
+            factor: 2.0,
+            max_attempts: 0,
+        }
+    }
+}
+
+impl Backoff {
+    /// 第 `attempt` 次失败后的等待时间（从 1 开始计数）
+    ///
+    /// 手写的配置文件可能含有负数或 NaN，这里按最接近的有效值计算，不会 panic。
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let finite_or = |value: f32, fallback: f32| if value.is_finite() { value } else { fallback };
+        let initial = finite_or(self.initial_secs, 0.0).clamp(0.0, MAX_DELAY_SECS);
+        let max = finite_or(self.max_secs, MAX_DELAY_SECS).clamp(initial, MAX_DELAY_SECS);
+        let factor = finite_or(self.factor, 1.0).max(1.0);
+        let exponent = attempt.saturating_sub(1).min(32) as i32;
+        Duration::from_secs_f32((initial * factor.powi(exponent)).min(max))
+    }
+
+    fn exhausted(&self, attempts: u32) -> bool {
+        self.max_attempts > 0 && attempts >= self.max_attempts
+    }
+}
+
+/// 状态机的输入
+#[derive(Clone, Debug)]
+pub enum ConnectionInput {
+    /// 用户启动或自动连接开始
+    Start,
+    /// 用户停止
+    Stop,
+    /// 控制器运行中，附带运行的手柄数量
+    Connected(usize),
+    /// 启动控制器失败
+    ConnectFailed(String),
+    /// 运行中的手柄全部断开
+    ConnectionLost,
+    /// 断开的手柄已重新连接
+    Restored,
+    /// 本轮重新连接未成功
+    RetryFailed,
+    Suspend,
+    Resume,
+    /// 输入源切换后重新查找手柄
+    Scan,
+}
+
+/// 推送给界面的状态变化
+#[derive(Clone, Debug)]
+pub struct Transition {
+    pub from: ConnectionState,
+    pub to: ConnectionState,
+    pub message: String,
+}
+
+/// 连接状态机
+///
+/// 不直接操作手柄，只根据输入计算状态和下一次重试的时间，
+/// 每次状态或提示变化时通过通道推送 `Transition`。
+/// 运行中的控制器各自登记，手柄断开和重新连接由控制线程报告。
+pub struct ConnectionMachine {
+    state: ConnectionState,
+    message: String,
+    backoff: Backoff,
+    attempts: u32,
+    retry_at: Option<Instant>,
+    transitions: Sender<Transition>,
+    // 登记的控制器，以及其中手柄已断开的控制器
+    controllers: HashSet<u64>,
+    lost: HashSet<u64>,
+    next_controller: u64,
+}
+
+impl ConnectionMachine {
+    /// 创建状态机，初始处于查找手柄状态
+    pub fn new(backoff: Backoff) -> (Self, Receiver<Transition>) {
+        let (transitions, receiver) = mpsc::channel();
+        let machine = Self {
+            state: ConnectionState::Scanning,
+            message: "正在查找手柄".to_string(),
+            backoff,
+            attempts: 0,
+            retry_at: None,
+            transitions,
+            controllers: HashSet::new(),
+            lost: HashSet::new(),
+            next_controller: 0,
+        };
+        (machine, receiver)
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn set_backoff(&mut self, backoff: Backoff) {
+        self.backoff = backoff;
+    }
+
+    /// 是否到了下一次重试的时间
+    pub fn retry_due(&self, now: Instant) -> bool {
+        matches!(self.state, ConnectionState::Scanning | ConnectionState::Reconnecting)
+            && self.retry_at.is_none_or(|at| now >= at)
+    }
+
+    /// 有手柄连接时立即重试，不再等待退避间隔
+    pub fn retry_now(&mut self) {
+        self.retry_at = None;
+    }
+
+    /// 处理输入并在需要时推送状态变化
+    pub fn handle(&mut self, input: ConnectionInput, now: Instant) {
+        use ConnectionState::*;
+
+        match (self.state, input) {
+            (_, ConnectionInput::Stop) => {
+                self.reset();
+                self.transition(Idle, "已停止".to_string());
+            }
+            (_, ConnectionInput::Scan) => {
+                self.reset();
+                self.transition(Scanning, "正在查找手柄".to_string());
+            }
+            (Idle | Scanning | Reconnecting | Failed, ConnectionInput::Start) => {
+                self.transition(Connecting, "正在连接手柄".to_string());
+            }
+            (Suspended, ConnectionInput::Connected(_)) => {
+                self.transition(Suspended, "已暂停".to_string());
+            }
+            (_, ConnectionInput::Connected(pads)) => {
+                self.reset();
+                let message = if pads > 1 {
+                    format!("已连接，{} 个控制器运行中", pads)
+                } else {
+                    "已连接，控制器运行中".to_string()
+                };
+                self.transition(Active, message);
+            }
+            (Connecting | Scanning, ConnectionInput::ConnectFailed(error)) => {
+                self.attempts += 1;
+                if self.backoff.exhausted(self.attempts) {
+                    self.retry_at = None;
+                    self.transition(Failed, format!("连接失败: {}", error));
+                } else {
+                    let delay = self.backoff.delay(self.attempts);
+                    self.retry_at = Some(now + delay);
+                    self.transition(Scanning, format!("{}，{:.0} 秒后重试", error, delay.as_secs_f32().ceil()));
+                }
+            }
+            // 暂停时手柄断开不影响状态，恢复后再处理
+            (Active, ConnectionInput::ConnectionLost) => {
+                self.attempts = 0;
+                self.retry_at = None;
+                self.transition(Reconnecting, "手柄已断开，等待重新连接".to_string());
+            }
+            (Reconnecting, ConnectionInput::RetryFailed) => {
+                self.attempts += 1;
+                if self.backoff.exhausted(self.attempts) {
+                    self.retry_at = None;
+                    self.transition(Failed, format!("重新连接失败，已尝试 {} 次", self.attempts));
+                } else {
+                    let delay = self.backoff.delay(self.attempts);
+                    self.retry_at = Some(now + delay);
+                    self.transition(Reconnecting, format!("手柄已断开，等待重新连接（第 {} 次）", self.attempts));
+                }
+            }
+            (Reconnecting, ConnectionInput::Restored) => {
+                self.reset();
+                self.transition(Active, "手柄已重新连接".to_string());
+            }
+            (Active, ConnectionInput::Suspend) => {
+                self.transition(Suspended, "已暂停".to_string());
+            }
+            // 暂停期间手柄全部断开时，恢复后直接等待重新连接
+            (Suspended, ConnectionInput::Resume) if self.all_lost() => {
+                self.reset();
+                self.transition(Reconnecting, "手柄已断开，等待重新连接".to_string());
+            }
+            (Suspended, ConnectionInput::Resume) => {
+                self.transition(Active, "已连接，控制器运行中".to_string());
+            }
+            // 其他组合不改变状态
+            _ => {}
+        }
+    }
+
+    /// 登记一个运行中的控制器，返回其编号
+    fn register(&mut self) -> u64 {
+        self.next_controller += 1;
+        self.controllers.insert(self.next_controller);
+        self.next_controller
+    }
+
+    /// 注销停止的控制器，剩下的控制器都已断开时进入重新连接状态
+    fn unregister(&mut self, controller: u64) {
+        self.controllers.remove(&controller);
+        self.lost.remove(&controller);
+        if self.all_lost() {
+            self.handle(ConnectionInput::ConnectionLost, Instant::now());
+        }
+    }
+
+    /// 控制器的手柄断开，所有控制器的手柄都断开时进入重新连接状态
+    pub fn controller_lost(&mut self, controller: u64, now: Instant) {
+        if self.controllers.contains(&controller) && self.lost.insert(controller) && self.all_lost() {
+            self.handle(ConnectionInput::ConnectionLost, now);
+        }
+    }
+
+    /// 控制器的手柄重新连接
+    pub fn controller_restored(&mut self, controller: u64, now: Instant) {
+        if self.lost.remove(&controller) {
+            self.handle(ConnectionInput::Restored, now);
+        }
+    }
+
+    fn all_lost(&self) -> bool {
+        !self.controllers.is_empty() && self.controllers.iter().all(|controller| self.lost.contains(controller))
+    }
+
+    fn reset(&mut self) {
+        self.attempts = 0;
+        self.retry_at = None;
+    }
+
+    fn transition(&mut self, to: ConnectionState, message: String) {
+        if to == self.state && message == self.message {
+            return;
+        }
+        let from = self.state;
+        if from != to {
+            info!("连接状态: {} -> {} ({})", from.display_name(), to.display_name(), message);
+        }
+        self.state = to;
+        self.message = message.clone();
+        // 界面已关闭时忽略发送失败
+        let _ = self.transitions.send(Transition { from, to, message });
+    }
+}
+
+/// 界面和控制线程共享的连接状态机
+///
+/// 界面发送启动、停止等命令，控制线程通过 `ConnectionReporter` 报告手柄断开和重新连接，
+/// 状态变化在发生的线程中立即推送给界面。
+#[derive(Clone)]
+pub struct SharedConnection(Arc<Mutex<ConnectionMachine>>);
+
+impl SharedConnection {
+    pub fn new(backoff: Backoff) -> (Self, Receiver<Transition>) {
+        let (machine, transitions) = ConnectionMachine::new(backoff);
+        (Self(Arc::new(Mutex::new(machine))), transitions)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ConnectionMachine> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.lock().state()
+    }
+
+    pub fn message(&self) -> String {
+        self.lock().message().to_string()
+    }
+
+    pub fn set_backoff(&self, backoff: Backoff) {
+        self.lock().set_backoff(backoff);
+    }
+
+    pub fn retry_due(&self, now: Instant) -> bool {
+        self.lock().retry_due(now)
+    }
+
+    pub fn handle(&self, input: ConnectionInput, now: Instant) {
+        self.lock().handle(input, now);
+    }
+
+    pub fn retry_now(&self) {
+        self.lock().retry_now();
+    }
+
+    /// 为新启动的控制器登记，返回交给控制线程的报告端
+    pub fn reporter(&self) -> ConnectionReporter {
+        let controller = self.lock().register();
+        ConnectionReporter { connection: self.clone(), controller }
+    }
+}
+
+/// 控制线程报告所绑定手柄的连接情况，控制器停止后释放时自动注销
+pub struct ConnectionReporter {
+    connection: SharedConnection,
+    controller: u64,
+}
+
+impl ConnectionReporter {
+    pub fn lost(&self) {
+        self.connection.lock().controller_lost(self.controller, Instant::now());
+    }
+
+    pub fn restored(&self) {
+        self.connection.lock().controller_restored(self.controller, Instant::now());
+    }
+}
+
+impl Drop for ConnectionReporter {
+    fn drop(&mut self) {
+        self.connection.lock().unregister(self.controller);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ConnectionState::*;
+
+    fn machine(backoff: Backoff) -> (ConnectionMachine, Receiver<Transition>) {
+        ConnectionMachine::new(backoff)
+    }
+
+    fn states(transitions: &Receiver<Transition>) -> Vec<(ConnectionState, ConnectionState)> {
+        transitions.try_iter().map(|transition| (transition.from, transition.to)).collect()
+    }
+
+    #[test]
+    fn backoff_grows_and_caps() {
+        let backoff = Backoff { initial_secs: 1.0, max_secs: 10.0, factor: 2.0, max_attempts: 0 };
+        let delays: Vec<f32> = (1..=6).map(|attempt| backoff.delay(attempt).as_secs_f32()).collect();
+        assert_eq!(delays, vec![1.0, 2.0, 4.0, 8.0, 10.0, 10.0]);
+        assert_eq!(backoff.delay(0), Duration::from_secs(1));
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn backoff_tolerates_invalid_values() {
+        let cases = [
+            Backoff { initial_secs: -5.0, max_secs: -1.0, factor: -2.0, max_attempts: 0 },
+            Backoff { initial_secs: f32::NAN, max_secs: f32::NAN, factor: f32::NAN, max_attempts: 0 },
+            Backoff { initial_secs: f32::INFINITY, max_secs: 1e30, factor: 1e30, max_attempts: 0 },
+            Backoff { initial_secs: 5.0, max_secs: 1.0, factor: 0.5, max_attempts: 0 },
+        ];
+        for backoff in cases {
+            for attempt in [1, 2, 40] {
+                assert!(backoff.delay(attempt) <= Duration::from_secs_f32(MAX_DELAY_SECS), "{:?}", backoff);
+            }
+        }
+        // 最长间隔小于初始间隔时按初始间隔等待
+        assert_eq!(cases[3].delay(3), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn connect_failure_retries_after_backoff_then_fails() {
+        let backoff = Backoff { initial_secs: 2.0, max_secs: 30.0, factor: 2.0, max_attempts: 2 };
+        let (mut machine, transitions) = machine(backoff);
+        let now = Instant::now();
+        assert!(machine.retry_due(now));
+
+        machine.handle(ConnectionInput::Start, now);
+        machine.handle(ConnectionInput::ConnectFailed("没有手柄".to_string()), now);
+        assert_eq!(machine.state(), Scanning);
+        assert!(!machine.retry_due(now + Duration::from_secs(1)));
+        assert!(machine.retry_due(now + Duration::from_secs(2)));
+        machine.retry_now();
+        assert!(machine.retry_due(now));
+
+        machine.handle(ConnectionInput::Start, now);
+        machine.handle(ConnectionInput::ConnectFailed("没有手柄".to_string()), now);
+        assert_eq!(machine.state(), Failed);
+        assert!(!machine.retry_due(now + Duration::from_secs(60)));
+        assert_eq!(
+            states(&transitions),
+            vec![(Scanning, Connecting), (Connecting, Scanning), (Scanning, Connecting), (Connecting, Failed)]
+        );
+    }
+
+    #[test]
+    fn transition_table() {
+        let (mut machine, transitions) = machine(Backoff::default());
+        let now = Instant::now();
+        machine.handle(ConnectionInput::Start, now);
+        machine.handle(ConnectionInput::Connected(1), now);
+        machine.handle(ConnectionInput::Suspend, now);
+        // 暂停时新启动的控制器不会恢复输出
+        machine.handle(ConnectionInput::Connected(2), now);
+        assert_eq!(machine.state(), Suspended);
+        machine.handle(ConnectionInput::Resume, now);
+        machine.handle(ConnectionInput::ConnectionLost, now);
+        machine.handle(ConnectionInput::RetryFailed, now);
+        machine.handle(ConnectionInput::Restored, now);
+        machine.handle(ConnectionInput::Stop, now);
+        // 空闲时断开和恢复不改变状态
+        machine.handle(ConnectionInput::ConnectionLost, now);
+        machine.handle(ConnectionInput::Scan, now);
+        assert_eq!(
+            states(&transitions),
+            vec![
+                (Scanning, Connecting),
+                (Connecting, Active),
+                (Active, Suspended),
+                (Suspended, Active),
+                (Active, Reconnecting),
+                (Reconnecting, Reconnecting),
+                (Reconnecting, Active),
+                (Active, Idle),
+                (Idle, Scanning),
+            ]
+        );
+    }
+
+    #[test]
+    fn reconnects_only_when_every_controller_lost_its_pad() {
+        let (connection, transitions) = SharedConnection::new(Backoff::default());
+        let first = connection.reporter();
+        let second = connection.reporter();
+        connection.handle(ConnectionInput::Connected(2), Instant::now());
+
+        first.lost();
+        assert_eq!(connection.state(), Active);
+        second.lost();
+        assert_eq!(connection.state(), Reconnecting);
+        second.restored();
+        assert_eq!(connection.state(), Active);
+
+        // 停止的控制器不再计入，剩下的手柄仍处于断开状态
+        drop(second);
+        assert_eq!(connection.state(), Reconnecting);
+        assert_eq!(
+            states(&transitions),
+            vec![(Scanning, Active), (Active, Reconnecting), (Reconnecting, Active), (Active, Reconnecting)]
+        );
+    }
+
+    #[test]
+    fn pads_lost_while_suspended_reconnect_on_resume() {
+        let (connection, _transitions) = SharedConnection::new(Backoff::default());
+        let reporter = connection.reporter();
+        connection.handle(ConnectionInput::Connected(1), Instant::now());
+        connection.handle(ConnectionInput::Suspend, Instant::now());
+        reporter.lost();
+        assert_eq!(connection.state(), Suspended);
+        connection.handle(ConnectionInput::Resume, Instant::now());
+        assert_eq!(connection.state(), Reconnecting);
+    }
+}
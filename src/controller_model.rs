@@ -0,0 +1,219 @@
+// 手柄型号识别
+use gilrs::MappingSource;
+use serde::{Deserialize, Serialize};
+use log::info;
+
+use crate::config::Config;
+
+/// 手柄型号
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ControllerModel {
+    Xbox,
+    PlayStation,
+    SwitchPro,
+    EightBitDo,
+    Generic,
+}
+
+// USB 厂商 ID
+const VENDOR_MICROSOFT: u16 = 0x045e;
+const VENDOR_SONY: u16 = 0x054c;
+const VENDOR_NINTENDO: u16 = 0x057e;
+const VENDOR_8BITDO: u16 = 0x2dc8;
+
+// 任天堂 Switch 手柄的产品 ID：Joy-Con (L/R)、Pro 手柄和充电握把
+const NINTENDO_SWITCH_PRODUCTS: [u16; 4] = [0x2006, 0x2007, 0x2009, 0x200e];
+
+/// 识别结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DetectedController {
+    pub model: ControllerModel,
+    pub mapping_source: MappingSource,
+    /// SDL 映射库对任天堂布局的手柄按标签而非位置映射（A 在东侧），
+    /// 需要交换南/东、西/北按钮才能让配置中的按钮名称保持按位置的含义
+    pub swap_face_buttons: bool,
+}
+
+impl ControllerModel {
+    /// 所有型号
+    pub const ALL: [ControllerModel; 5] = [
+        ControllerModel::Xbox,
+        ControllerModel::PlayStation,
+        ControllerModel::SwitchPro,
+        ControllerModel::EightBitDo,
+        ControllerModel::Generic,
+    ];
+
+    /// 根据名称、厂商/产品 ID 判断手柄型号
+    pub fn classify(name: &str, vendor_id: Option<u16>, product_id: Option<u16>) -> Self {
+        let name = name.to_lowercase();
+
+        // 8BitDo 手柄在 XInput 模式下会使用微软的厂商 ID，所以先按名称判断
+        if name.contains("8bitdo") || vendor_id == Some(VENDOR_8BITDO) {
+            return ControllerModel::EightBitDo;
+        }
+        let is_switch_product = vendor_id == Some(VENDOR_NINTENDO)
+            && product_id.is_some_and(|pid| NINTENDO_SWITCH_PRODUCTS.contains(&pid));
+        if name.contains("pro controller") || name.contains("switch") || is_switch_product {
+            return ControllerModel::SwitchPro;
+        }
+        if name.contains("dualsense")
+            || name.contains("dualshock")
+            || name.contains("playstation")
+            || name.contains("ps4")
+            || name.contains("ps5")
+            || vendor_id == Some(VENDOR_SONY)
+        {
+            return ControllerModel::PlayStation;
+        }
+        if name.contains("xbox") || name.contains("x-box") || name.contains("xinput") || vendor_id == Some(VENDOR_MICROSOFT) {
+            return ControllerModel::Xbox;
+        }
+
+        ControllerModel::Generic
+    }
+
+    /// 识别已连接的手柄
+    pub fn detect(gamepad: &gilrs::Gamepad) -> DetectedController {
+        let model = Self::classify(gamepad.name(), gamepad.vendor_id(), gamepad.product_id());
+        let mapping_source = gamepad.mapping_source();
+        let swap_face_buttons = model.has_nintendo_layout() && mapping_source == MappingSource::SdlMappings;
+        info!(
+            "手柄型号识别: {} -> {} (厂商: {:04x?}, 产品: {:04x?}, 映射来源: {:?}, 交换面键: {})",
+            gamepad.name(),
+            model.display_name(),
+            gamepad.vendor_id(),
+            gamepad.product_id(),
+            mapping_source,
+            swap_face_buttons
+        );
+        DetectedController {
+            model,
+            mapping_source,
+            swap_face_buttons,
+        }
+    }
+
+    /// 型号显示名称
+    pub fn display_name(self) -> &'static str {
+        match self {
+            ControllerModel::Xbox => "Xbox",
+            ControllerModel::PlayStation => "DualShock/DualSense",
+            ControllerModel::SwitchPro => "Switch Pro",
+            ControllerModel::EightBitDo => "8BitDo",
+            ControllerModel::Generic => "通用手柄",
+        }
+    }
+
+    /// 面键是否为任天堂布局（A 在东侧，B 在南侧）
+    pub fn has_nintendo_layout(self) -> bool {
+        matches!(self, ControllerModel::SwitchPro | ControllerModel::EightBitDo)
+    }
+
+    /// 面键在该型号上的印刷标签
+    pub fn face_button_label(self, button_name: &str) -> Option<&'static str> {
+        let labels = match self {
+            ControllerModel::Xbox => ["A", "B", "X", "Y"],
+            ControllerModel::PlayStation => ["✕", "○", "□", "△"],
+            ControllerModel::SwitchPro | ControllerModel::EightBitDo => ["B", "A", "Y", "X"],
+            ControllerModel::Generic => return None,
+        };
+        match button_name {
+            "South" => Some(labels[0]),
+            "East" => Some(labels[1]),
+            "West" => Some(labels[2]),
+            "North" => Some(labels[3]),
+            _ => None,
+        }
+    }
+
+    /// 该型号的默认配置
+    ///
+    /// 任天堂布局的手柄习惯用东侧的 A 键确认，因此左右键互换。
+    pub fn default_config(self) -> Config {
+        let mut config = Config::default();
+        if self.has_nintendo_layout() {
+            config.left_click_button = "East".to_string();
+            config.right_click_button = "South".to_string();
+            config.middle_click_button = "North".to_string();
+            config.double_click_button = "West".to_string();
+        }
+        config
+    }
+}
+
+/// 交换南/东、西/北按钮名称，其他按钮保持不变
+pub fn swap_face_button(button_name: &str) -> &str {
+    match button_name {
+        "South" => "East",
+        "East" => "South",
+        "West" => "North",
+        "North" => "West",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_by_name_and_ids() {
+        let cases = [
+            ("Xbox Wireless Controller", None, None, ControllerModel::Xbox),
+            ("Microsoft X-Box 360 pad", None, None, ControllerModel::Xbox),
+            ("Unnamed XInput pad", None, None, ControllerModel::Xbox),
+            ("USB Gamepad", Some(VENDOR_MICROSOFT), Some(0x02ea), ControllerModel::Xbox),
+            ("DualSense Wireless Controller", None, None, ControllerModel::PlayStation),
+            ("Sony Interactive Entertainment Wireless Controller", Some(VENDOR_SONY), Some(0x09cc), ControllerModel::PlayStation),
+            ("PS4 Controller", None, None, ControllerModel::PlayStation),
+            ("Nintendo Switch Pro Controller", None, None, ControllerModel::SwitchPro),
+            ("Joy-Con (L)", Some(VENDOR_NINTENDO), Some(0x2006), ControllerModel::SwitchPro),
+            ("Charging Grip", Some(VENDOR_NINTENDO), Some(0x200e), ControllerModel::SwitchPro),
+            // 任天堂的其他产品不是 Switch 手柄
+            ("Wii Remote", Some(VENDOR_NINTENDO), Some(0x0306), ControllerModel::Generic),
+            ("8BitDo Pro 2", None, None, ControllerModel::EightBitDo),
+            // XInput 模式下的 8BitDo 使用微软的厂商 ID，仍按名称识别
+            ("8BitDo Ultimate Wireless", Some(VENDOR_MICROSOFT), Some(0x028e), ControllerModel::EightBitDo),
+            ("Gamepad", Some(VENDOR_8BITDO), Some(0x6012), ControllerModel::EightBitDo),
+            ("USB Gamepad", Some(0x0079), Some(0x0006), ControllerModel::Generic),
+            ("", None, None, ControllerModel::Generic),
+        ];
+        for (name, vendor, product, expected) in cases {
+            assert_eq!(ControllerModel::classify(name, vendor, product), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn swap_face_button_is_an_involution() {
+        for name in ["South", "East", "West", "North", "Start", "LeftTrigger", ""] {
+            assert_eq!(swap_face_button(swap_face_button(name)), name);
+        }
+        assert_eq!(swap_face_button("South"), "East");
+        assert_eq!(swap_face_button("West"), "North");
+        assert_eq!(swap_face_button("Select"), "Select");
+    }
+
+    #[test]
+    fn default_config_swaps_only_for_nintendo_layouts() {
+        let standard = Config::default();
+        for model in ControllerModel::ALL {
+            let config = model.default_config();
+            if model.has_nintendo_layout() {
+                // 东侧的 A 键为左键
+                assert_eq!(config.left_click_button, "East");
+                assert_eq!(config.right_click_button, "South");
+                assert_eq!(config.middle_click_button, swap_face_button(&standard.middle_click_button));
+                assert_eq!(config.double_click_button, swap_face_button(&standard.double_click_button));
+                assert_eq!(model.face_button_label(&config.left_click_button), Some("A"));
+            } else {
+                assert_eq!(serde_json::to_value(&config).unwrap(), serde_json::to_value(&standard).unwrap(), "{:?}", model);
+            }
+        }
+        assert!(ControllerModel::SwitchPro.has_nintendo_layout());
+        assert!(ControllerModel::EightBitDo.has_nintendo_layout());
+        assert!(!ControllerModel::Xbox.has_nintendo_layout());
+        assert_eq!(ControllerModel::Xbox.face_button_label("South"), Some("A"));
+        assert_eq!(ControllerModel::Generic.face_button_label("South"), None);
+    }
+}
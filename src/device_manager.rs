@@ -0,0 +1,480 @@
+// 手柄设备管理服务
+use gilrs::ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks};
+use gilrs::{Axis, Button, EventType, Gilrs, GilrsBuilder, GamepadId};
+use log::{info, error, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::battery::BatteryLevel;
+use crate::controller_model::{ControllerModel, DetectedController};
+use crate::input::{DeviceRegistry, InputSource};
+use crate::haptics::RumblePattern;
+use crate::sdl_mapping::{self, MappedInput, MappingTarget, RawInput};
+
+// 控制器读取的摇杆和扳机轴
+pub const TRACKED_AXES: [Axis; 6] = [
+    Axis::LeftStickX,
+    Axis::LeftStickY,
+    Axis::RightStickX,
+    Axis::RightStickY,
+    Axis::LeftZ,
+    Axis::RightZ,
+];
+
+// 控制器读取的按钮
+pub const TRACKED_BUTTONS: [Button; 19] = [
+    Button::South,
+    Button::East,
+    Button::North,
+    Button::West,
+    Button::C,
+    Button::Z,
+    Button::LeftTrigger,
+    Button::LeftTrigger2,
+    Button::RightTrigger,
+    Button::RightTrigger2,
+    Button::Select,
+    Button::Start,
+    Button::Mode,
+    Button::LeftThumb,
+    Button::RightThumb,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+];
+
+/// 按名称查找按钮，名称与 `Button` 的变体名相同
+pub fn button_from_name(name: &str) -> Option<Button> {
+    TRACKED_BUTTONS.into_iter().find(|button| format!("{:?}", button) == name)
+}
+
+/// 按名称查找摇杆轴，名称与 `Axis` 的变体名相同
+pub fn axis_from_name(name: &str) -> Option<Axis> {
+    TRACKED_AXES.into_iter().find(|axis| format!("{:?}", axis) == name)
+}
+
+/// 输入源内的手柄编号
+///
+/// gilrs 的 `GamepadId` 无法在 gilrs 之外构造，脚本和回放的输入源使用同样的编号类型。
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PadId(pub usize);
+
+impl From<GamepadId> for PadId {
+    fn from(id: GamepadId) -> Self {
+        PadId(id.into())
+    }
+}
+
+impl fmt::Display for PadId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// 手柄的稳定标识
+///
+/// gilrs 不保证手柄重新连接后 `GamepadId` 不变，因此用 UUID 加名称识别同一个实体手柄。
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DeviceKey {
+    pub uuid: [u8; 16],
+    pub name: String,
+}
+
+impl DeviceKey {
+    /// 配置文件中按手柄保存设置时使用的键，格式为 `名称#UUID`
+    pub fn storage_key(&self) -> String {
+        format!("{}#{}", self.name, uuid_hex(&self.uuid))
+    }
+}
+
+fn uuid_hex(uuid: &[u8; 16]) -> String {
+    uuid.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 原手柄断开后的处理方式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReconnectPolicy {
+    WaitForOriginal,    // 一直等待原手柄重新连接
+    SwitchToAvailable,  // 超时后切换到其他空闲的手柄
+}
+
+impl ReconnectPolicy {
+    pub const ALL: [ReconnectPolicy; 2] = [
+        ReconnectPolicy::WaitForOriginal,
+        ReconnectPolicy::SwitchToAvailable,
+    ];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            ReconnectPolicy::WaitForOriginal => "等待原手柄",
+            ReconnectPolicy::SwitchToAvailable => "切换到其他手柄",
+        }
+    }
+}
+
+/// 已连接的手柄信息
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    pub id: PadId,
+    pub name: String,
+    pub uuid: [u8; 16],
+    pub detected: DetectedController,
+}
+
+impl DeviceInfo {
+    pub fn key(&self) -> DeviceKey {
+        DeviceKey {
+            uuid: self.uuid,
+            name: self.name.clone(),
+        }
+    }
+
+    /// UUID 的十六进制表示，日志和命令行 `--device` 中使用
+    pub fn uuid_string(&self) -> String {
+        uuid_hex(&self.uuid)
+    }
+
+    /// 命令行 `--device` 的匹配规则：与 UUID 相同（忽略大小写和连字符），或名称中包含该文本（忽略大小写）
+    pub fn matches(&self, selector: &str) -> bool {
+        let uuid: String = selector.chars().filter(|c| *c != '-').collect();
+        uuid.eq_ignore_ascii_case(&self.uuid_string())
+            || self.name.to_lowercase().contains(&selector.to_lowercase())
+    }
+}
+
+/// 手柄当前的按钮和摇杆状态
+#[derive(Clone, Default)]
+pub struct PadState {
+    pub connected: bool,
+    pub battery: BatteryLevel,
+    axes: HashMap<Axis, f32>,
+    pressed: HashSet<Button>,
+}
+
+impl PadState {
+    /// 轴的当前值，未知的轴返回 0
+    pub fn value(&self, axis: Axis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn set_value(&mut self, axis: Axis, value: f32) {
+        self.axes.insert(axis, value);
+    }
+
+    pub fn set_pressed(&mut self, button: Button, pressed: bool) {
+        if pressed {
+            self.pressed.insert(button);
+        } else {
+            self.pressed.remove(&button);
+        }
+    }
+}
+
+// 查询电量的间隔
+const BATTERY_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 广播给订阅者的设备事件
+#[derive(Clone, Debug)]
+pub enum DeviceEvent {
+    /// 手柄列表发生变化
+    DevicesChanged(Vec<DeviceInfo>),
+    Connected(PadId),
+    Disconnected(PadId),
+    ButtonPressed(PadId, Button),
+    ButtonReleased(PadId, Button),
+    /// 原始键码输入，只在开启原始输入捕获时广播
+    RawInput(PadId, RawInput),
+}
+
+/// 发送给设备管理线程的命令
+enum DeviceCommand {
+    /// 为手柄设置映射，回复 SDL 映射字符串
+    SetMapping {
+        id: PadId,
+        mapping: Vec<(MappingTarget, MappedInput)>,
+        name: String,
+        reply: Sender<Result<String, String>>,
+    },
+    /// 让手柄震动
+    Rumble {
+        id: PadId,
+        pattern: RumblePattern,
+        reply: Sender<Result<(), String>>,
+    },
+}
+
+/// 手柄设备管理服务
+///
+/// 在后台线程中持有唯一的 gilrs 上下文，跟踪手柄的连接和断开，
+/// 并把设备列表和输入事件广播给所有订阅者。界面和控制线程共享同一个实例，
+/// 因此所有地方看到的 `GamepadId` 都是一致的。
+pub struct DeviceManager {
+    running: Arc<AtomicBool>,
+    thread_handle: Mutex<Option<JoinHandle<()>>>,
+    registry: DeviceRegistry,
+    // 是否广播原始键码
+    capture_raw: Arc<AtomicBool>,
+    commands: Sender<DeviceCommand>,
+}
+
+impl DeviceManager {
+    /// 初始化 gilrs 并启动设备管理线程
+    pub fn start() -> Result<Arc<Self>, String> {
+        let running = Arc::new(AtomicBool::new(true));
+        let registry = DeviceRegistry::default();
+        let capture_raw = Arc::new(AtomicBool::new(false));
+        let (commands, command_rx) = mpsc::channel();
+
+        let running_thread = running.clone();
+        let registry_thread = registry.clone();
+        let capture_thread = capture_raw.clone();
+        let (init_tx, init_rx) = mpsc::channel();
+
+        let thread_handle = thread::spawn(move || {
+            // 用户映射先加入，内置数据库中已有的手柄仍以内置映射为准
+            let user_mappings = sdl_mapping::load_user_mappings();
+            let mut gilrs = match GilrsBuilder::new().add_mappings(&user_mappings).build() {
+                Ok(gilrs) => {
+                    let _ = init_tx.send(Ok(()));
+                    gilrs
+                }
+                Err(err) => {
+                    let _ = init_tx.send(Err(format!("无法初始化手柄系统: {}", err)));
+                    return;
+                }
+            };
+            info!("手柄设备管理服务已启动");
+
+            refresh_states(&gilrs, &registry_thread);
+            registry_thread.set_devices(enumerate(&gilrs));
+            // 正在播放的震动，释放 Effect 会停止震动，因此保留到播放结束
+            let mut effects: Vec<(Effect, Instant)> = Vec::new();
+            // 读取电量需要访问系统文件，按间隔查询
+            refresh_battery(&gilrs, &registry_thread);
+            let mut last_battery_check = Instant::now();
+
+            while running_thread.load(Ordering::Relaxed) {
+                while let Ok(command) = command_rx.try_recv() {
+                    handle_command(&mut gilrs, &registry_thread, &mut effects, command);
+                }
+                let now = Instant::now();
+                effects.retain(|(_, until)| *until > now);
+                if now.duration_since(last_battery_check) >= BATTERY_CHECK_INTERVAL {
+                    last_battery_check = now;
+                    refresh_battery(&gilrs, &registry_thread);
+                }
+
+                // 等待事件，超时后检查是否需要退出
+                let Some(first) = gilrs.next_event_blocking(Some(Duration::from_millis(20))) else {
+                    continue;
+                };
+                let capture = capture_thread.load(Ordering::Relaxed);
+
+                let mut list_changed = false;
+                let mut event = Some(first);
+                while let Some(gilrs::Event { id, event: ev, time: _ }) = event {
+                    match ev {
+                        EventType::ButtonPressed(button, code) => {
+                            registry_thread.broadcast(DeviceEvent::ButtonPressed(id.into(), button));
+                            if capture {
+                                registry_thread.broadcast(DeviceEvent::RawInput(id.into(), RawInput::Button(code)));
+                            }
+                        }
+                        EventType::AxisChanged(_, value, code) if capture => {
+                            registry_thread.broadcast(DeviceEvent::RawInput(id.into(), RawInput::Axis(code, value)));
+                        }
+                        EventType::ButtonReleased(button, _) => registry_thread.broadcast(DeviceEvent::ButtonReleased(id.into(), button)),
+                        EventType::Connected => {
+                            info!("手柄已连接: {} (id: {:?})", gilrs.gamepad(id).name(), id);
+                            list_changed = true;
+                            registry_thread.broadcast(DeviceEvent::Connected(id.into()));
+                        }
+                        EventType::Disconnected => {
+                            warn!("手柄已断开: id {:?}", id);
+                            list_changed = true;
+                            registry_thread.broadcast(DeviceEvent::Disconnected(id.into()));
+                        }
+                        _ => {}
+                    }
+                    event = gilrs.next_event();
+                }
+
+                // 事件处理完后再发布状态，订阅者读到的状态与事件一致
+                refresh_states(&gilrs, &registry_thread);
+
+                if list_changed {
+                    let list = enumerate(&gilrs);
+                    registry_thread.set_devices(list.clone());
+                    registry_thread.broadcast(DeviceEvent::DevicesChanged(list));
+                }
+            }
+
+            info!("手柄设备管理服务已停止");
+        });
+
+        match init_rx.recv() {
+            Ok(Ok(())) => Ok(Arc::new(Self {
+                running,
+                thread_handle: Mutex::new(Some(thread_handle)),
+                registry,
+                capture_raw,
+                commands,
+            })),
+            Ok(Err(message)) => {
+                error!("{}", message);
+                Err(message)
+            }
+            Err(_) => Err("手柄设备管理线程意外退出".to_string()),
+        }
+    }
+}
+
+impl InputSource for DeviceManager {
+    fn registry(&self) -> &DeviceRegistry {
+        &self.registry
+    }
+
+    fn set_raw_capture(&self, enabled: bool) {
+        self.capture_raw.store(enabled, Ordering::Relaxed);
+    }
+
+    fn rumble(&self, id: PadId, pattern: RumblePattern) -> Result<(), String> {
+        let (reply, reply_rx) = mpsc::channel();
+        self.commands
+            .send(DeviceCommand::Rumble { id, pattern, reply })
+            .map_err(|_| "手柄设备管理服务未运行".to_string())?;
+        reply_rx.recv().map_err(|_| "手柄设备管理服务未运行".to_string())?
+    }
+
+    fn apply_mapping(&self, id: PadId, mapping: &[(MappingTarget, MappedInput)], name: &str) -> Result<String, String> {
+        let (reply, reply_rx) = mpsc::channel();
+        self.commands
+            .send(DeviceCommand::SetMapping { id, mapping: mapping.to_vec(), name: name.to_string(), reply })
+            .map_err(|_| "手柄设备管理服务未运行".to_string())?;
+        reply_rx.recv().map_err(|_| "手柄设备管理服务未运行".to_string())?
+    }
+
+    /// 停止设备管理线程
+    fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.thread_handle.lock().ok().and_then(|mut handle| handle.take())
+            && let Err(e) = handle.join()
+        {
+            error!("停止手柄设备管理服务时发生错误: {:?}", e);
+        }
+    }
+}
+
+impl Drop for DeviceManager {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 在设备管理线程中执行命令
+fn handle_command(gilrs: &mut Gilrs, registry: &DeviceRegistry, effects: &mut Vec<(Effect, Instant)>, command: DeviceCommand) {
+    match command {
+        DeviceCommand::SetMapping { id, mapping, name, reply } => {
+            let data = sdl_mapping::mapping_data(&mapping);
+            // SDL 映射用逗号分隔字段，名称中不能包含逗号
+            let name = name.replace(',', " ");
+            let result = gilrs.set_mapping(id.0, &data, name.as_str())
+                .map(|text| sdl_mapping::complete_mapping(&text, &mapping))
+                .map_err(|e| format!("设置手柄映射失败: {}", e));
+            if result.is_ok() {
+                info!("已为手柄 {} 设置新的映射", id);
+                let list = enumerate(gilrs);
+                registry.set_devices(list.clone());
+                registry.broadcast(DeviceEvent::DevicesChanged(list));
+            }
+            let _ = reply.send(result);
+        }
+        DeviceCommand::Rumble { id, pattern, reply } => {
+            let result = play_rumble(gilrs, id, pattern).map(|effect| {
+                effects.push((effect, Instant::now() + pattern.total_duration()));
+            });
+            let _ = reply.send(result);
+        }
+    }
+}
+
+/// 创建并播放震动效果
+fn play_rumble(gilrs: &mut Gilrs, id: PadId, pattern: RumblePattern) -> Result<Effect, String> {
+    let gamepad_id = gilrs.gamepads()
+        .find(|(gamepad_id, _)| PadId::from(*gamepad_id) == id)
+        .filter(|(_, gamepad)| gamepad.is_ff_supported())
+        .map(|(gamepad_id, _)| gamepad_id)
+        .ok_or_else(|| format!("手柄 {} 不支持震动", id))?;
+
+    let duration = Ticks::from_ms(pattern.duration_ms);
+    let scheduling = Replay { after: Ticks::from_ms(0), play_for: duration, with_delay: duration };
+    let magnitude = |strength: f32| (strength.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+    EffectBuilder::new()
+        .add_effect(BaseEffect {
+            kind: BaseEffectType::Strong { magnitude: magnitude(pattern.strong) },
+            scheduling,
+            envelope: Default::default(),
+        })
+        .add_effect(BaseEffect {
+            kind: BaseEffectType::Weak { magnitude: magnitude(pattern.weak) },
+            scheduling,
+            envelope: Default::default(),
+        })
+        .repeat(Repeat::For(Ticks::from_ms(pattern.total_duration().as_millis() as u32)))
+        .gamepads(&[gamepad_id])
+        .finish(gilrs)
+        .and_then(|effect| effect.play().map(|()| effect))
+        .map_err(|e| format!("播放震动失败: {}", e))
+}
+
+/// 枚举已连接的手柄
+fn enumerate(gilrs: &Gilrs) -> Vec<DeviceInfo> {
+    gilrs.gamepads()
+        .map(|(id, gamepad)| DeviceInfo {
+            id: id.into(),
+            name: gamepad.name().to_string(),
+            uuid: gamepad.uuid(),
+            detected: ControllerModel::detect(&gamepad),
+        })
+        .collect()
+}
+
+/// 读取所有已连接手柄的电量
+fn refresh_battery(gilrs: &Gilrs, registry: &DeviceRegistry) {
+    registry.update_states(|states| {
+        for (id, gamepad) in gilrs.gamepads() {
+            states.entry(id.into()).or_default().battery = gamepad.power_info().into();
+        }
+    });
+}
+
+/// 从 gilrs 读取所有已知手柄的状态
+fn refresh_states(gilrs: &Gilrs, registry: &DeviceRegistry) {
+    registry.update_states(|states| {
+        for state in states.values_mut() {
+            state.connected = false;
+        }
+        for (id, gamepad) in gilrs.gamepads() {
+            let state = states.entry(id.into()).or_default();
+            state.connected = true;
+            for axis in TRACKED_AXES {
+                state.axes.insert(axis, gamepad.value(axis));
+            }
+            state.pressed = TRACKED_BUTTONS.iter()
+                .copied()
+                .filter(|button| gamepad.is_pressed(*button))
+                .collect();
+        }
+    });
+}
@@ -0,0 +1,687 @@
+// Linux evdev 输入源
+use gilrs::{Axis, Button, MappingSource};
+use log::{info, error, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read};
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::controller_model::{ControllerModel, DetectedController};
+use crate::device_manager::{axis_from_name, button_from_name, DeviceInfo, PadId};
+use crate::input::{DeviceRegistry, InputSource};
+
+// linux/input-event-codes.h
+const EV_KEY: u16 = 0x01;
+const EV_ABS: u16 = 0x03;
+const BTN_JOYSTICK: u16 = 0x120;
+const BTN_GAMEPAD: u16 = 0x130;
+const KEY_BITS_LEN: usize = 96;
+
+// 重新扫描 /dev/input 的间隔
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+// 没有新事件时的等待时间
+const IDLE_SLEEP: Duration = Duration::from_millis(4);
+
+const fn ioc_read(nr: u8, size: usize) -> libc::c_ulong {
+    (2 << 30) | ((size as libc::c_ulong) << 16) | (0x45 << 8) | nr as libc::c_ulong
+}
+
+const EVIOCGID: libc::c_ulong = ioc_read(0x02, 8);
+const EVIOCGNAME_LEN: usize = 256;
+const EVIOCGNAME: libc::c_ulong = ioc_read(0x06, EVIOCGNAME_LEN);
+const EVIOCGBIT_KEY: libc::c_ulong = ioc_read(0x20 + EV_KEY as u8, KEY_BITS_LEN);
+
+const fn eviocgabs(code: u16) -> libc::c_ulong {
+    ioc_read(0x40 + code as u8, 24)
+}
+
+/// 一个原始的 evdev 事件
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawEvent {
+    /// 内核记录的时间
+    pub time: Duration,
+    pub kind: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+/// 从任意数据源按 `input_event` 的布局读取事件
+///
+/// 数据源可以是 `/dev/input/event*` 设备，也可以是 `cat /dev/input/eventN > dump` 得到的转储文件。
+pub struct EventReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> EventReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: vec![0; std::mem::size_of::<libc::input_event>()],
+        }
+    }
+
+    /// 读取下一个事件，数据结束时返回 None
+    pub fn next_event(&mut self) -> io::Result<Option<RawEvent>> {
+        match self.reader.read_exact(&mut self.buffer) {
+            Ok(()) => Ok(Some(decode_event(&self.buffer))),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn decode_event(buffer: &[u8]) -> RawEvent {
+    const LONG: usize = std::mem::size_of::<libc::c_long>();
+    let long = |offset: usize| {
+        let mut bytes = [0u8; LONG];
+        bytes.copy_from_slice(&buffer[offset..offset + LONG]);
+        libc::c_long::from_ne_bytes(bytes)
+    };
+    let base = std::mem::size_of::<libc::timeval>();
+    let secs = long(0).max(0) as u64;
+    let micros = long(LONG).clamp(0, 999_999) as u32;
+    RawEvent {
+        time: Duration::new(secs, micros * 1000),
+        kind: u16::from_ne_bytes([buffer[base], buffer[base + 1]]),
+        code: u16::from_ne_bytes([buffer[base + 2], buffer[base + 3]]),
+        value: i32::from_ne_bytes([buffer[base + 4], buffer[base + 5], buffer[base + 6], buffer[base + 7]]),
+    }
+}
+
+/// 绝对轴（EV_ABS）映射到的目标
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum AbsTarget {
+    /// 映射为摇杆或扳机轴，`axis` 为 `Axis` 的变体名
+    Axis { axis: String, invert: bool },
+    /// 方向键帽（HAT），负值为左/上，正值为右/下
+    DPad { horizontal: bool },
+}
+
+/// 一个设备的原始键码映射
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EvdevMapping {
+    /// 设备名称，`*` 匹配所有没有单独映射的设备
+    pub device_name: String,
+    /// EV_KEY 键码到按钮名称
+    pub buttons: BTreeMap<u16, String>,
+    /// EV_ABS 轴码到目标
+    pub axes: BTreeMap<u16, AbsTarget>,
+}
+
+impl EvdevMapping {
+    /// Linux 手柄驱动的标准键码
+    pub fn standard() -> Self {
+        let buttons = [
+            (0x130, "South"), (0x131, "East"), (0x132, "C"), (0x133, "North"), (0x134, "West"), (0x135, "Z"),
+            (0x136, "LeftTrigger"), (0x137, "RightTrigger"), (0x138, "LeftTrigger2"), (0x139, "RightTrigger2"),
+            (0x13a, "Select"), (0x13b, "Start"), (0x13c, "Mode"), (0x13d, "LeftThumb"), (0x13e, "RightThumb"),
+            (0x220, "DPadUp"), (0x221, "DPadDown"), (0x222, "DPadLeft"), (0x223, "DPadRight"),
+        ];
+        let axis = |name: &str, invert: bool| AbsTarget::Axis { axis: name.to_string(), invert };
+        Self {
+            device_name: "*".to_string(),
+            buttons: buttons.into_iter().map(|(code, name)| (code, name.to_string())).collect(),
+            axes: BTreeMap::from([
+                (0x00, axis("LeftStickX", false)),
+                (0x01, axis("LeftStickY", true)),
+                (0x02, axis("LeftZ", false)),
+                (0x03, axis("RightStickX", false)),
+                (0x04, axis("RightStickY", true)),
+                (0x05, axis("RightZ", false)),
+                (0x10, AbsTarget::DPad { horizontal: true }),
+                (0x11, AbsTarget::DPad { horizontal: false }),
+            ]),
+        }
+    }
+
+    /// 映射文件路径
+    pub fn mappings_path() -> PathBuf {
+        Config::get_config_dir().join("evdev_mappings.json")
+    }
+
+    /// 加载映射文件，文件不存在时写入标准映射供用户修改
+    pub fn load_all() -> Vec<EvdevMapping> {
+        let path = Self::mappings_path();
+        match fs::read_to_string(&path) {
+            Ok(text) => match serde_json::from_str(&text) {
+                Ok(mappings) => return mappings,
+                Err(e) => error!("解析 evdev 映射文件失败: {}，使用标准映射", e),
+            },
+            Err(_) => {
+                let mappings = vec![Self::standard()];
+                if let Err(e) = Self::save_all(&mappings) {
+                    warn!("{}", e);
+                }
+                return mappings;
+            }
+        }
+        vec![Self::standard()]
+    }
+
+    pub fn save_all(mappings: &[EvdevMapping]) -> Result<(), String> {
+        let path = Self::mappings_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("无法创建配置目录: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(mappings).map_err(|e| format!("序列化 evdev 映射失败: {}", e))?;
+        fs::write(&path, json).map_err(|e| format!("无法写入 evdev 映射文件: {}", e))
+    }
+
+    /// 按设备名称选择映射
+    pub fn find<'a>(mappings: &'a [EvdevMapping], name: &str) -> Option<&'a EvdevMapping> {
+        mappings.iter()
+            .find(|mapping| mapping.device_name == name)
+            .or_else(|| mappings.iter().find(|mapping| mapping.device_name == "*"))
+    }
+}
+
+/// 映射后的输入
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Translated {
+    Button(Button, bool),
+    Axis(Axis, f32),
+}
+
+/// 把一个设备的原始事件按映射转换为按钮和摇杆输入
+pub struct Translator {
+    buttons: HashMap<u16, Button>,
+    axes: HashMap<u16, (Axis, bool)>,
+    hats: HashMap<u16, bool>,
+    // 轴的取值范围
+    ranges: HashMap<u16, (i32, i32)>,
+    // 方向键帽上一次按下的按钮
+    hat_pressed: HashMap<u16, Button>,
+}
+
+impl Translator {
+    /// `ranges` 为设备报告的轴范围，没有报告的轴按 -32768..32767 处理
+    pub fn new(mapping: &EvdevMapping, ranges: HashMap<u16, (i32, i32)>) -> Self {
+        let mut buttons = HashMap::new();
+        for (code, name) in &mapping.buttons {
+            match button_from_name(name) {
+                Some(button) => {
+                    buttons.insert(*code, button);
+                }
+                None => warn!("evdev 映射 {}: 未知按钮 {}", mapping.device_name, name),
+            }
+        }
+        let mut axes = HashMap::new();
+        let mut hats = HashMap::new();
+        for (code, target) in &mapping.axes {
+            match target {
+                AbsTarget::Axis { axis, invert } => match axis_from_name(axis) {
+                    Some(found) => {
+                        axes.insert(*code, (found, *invert));
+                    }
+                    None => warn!("evdev 映射 {}: 未知摇杆轴 {}", mapping.device_name, axis),
+                },
+                AbsTarget::DPad { horizontal } => {
+                    hats.insert(*code, *horizontal);
+                }
+            }
+        }
+        Self {
+            buttons,
+            axes,
+            hats,
+            ranges,
+            hat_pressed: HashMap::new(),
+        }
+    }
+
+    pub fn translate(&mut self, event: RawEvent) -> Vec<Translated> {
+        match event.kind {
+            // 值 2 为按键自动重复，忽略
+            EV_KEY if event.value != 2 => self.buttons.get(&event.code)
+                .map(|button| vec![Translated::Button(*button, event.value != 0)])
+                .unwrap_or_default(),
+            EV_ABS => {
+                if let Some((axis, invert)) = self.axes.get(&event.code) {
+                    let (min, max) = self.ranges.get(&event.code).copied().unwrap_or((-32768, 32767));
+                    let span = (max - min).max(1) as f32;
+                    let unit = ((event.value - min) as f32 / span).clamp(0.0, 1.0);
+                    // 扳机轴的范围是 0..1，摇杆是 -1..1
+                    let mut value = if matches!(axis, Axis::LeftZ | Axis::RightZ) { unit } else { unit * 2.0 - 1.0 };
+                    if *invert {
+                        value = -value;
+                    }
+                    return vec![Translated::Axis(*axis, value)];
+                }
+                if let Some(horizontal) = self.hats.get(&event.code) {
+                    let mut output = Vec::new();
+                    if let Some(previous) = self.hat_pressed.remove(&event.code) {
+                        output.push(Translated::Button(previous, false));
+                    }
+                    let pressed = match (horizontal, event.value.signum()) {
+                        (true, -1) => Some(Button::DPadLeft),
+                        (true, 1) => Some(Button::DPadRight),
+                        (false, -1) => Some(Button::DPadUp),
+                        (false, 1) => Some(Button::DPadDown),
+                        _ => None,
+                    };
+                    if let Some(button) = pressed {
+                        self.hat_pressed.insert(event.code, button);
+                        output.push(Translated::Button(button, true));
+                    }
+                    return output;
+                }
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// 通过 evdev 读取手柄的输入源
+///
+/// 适用于 gilrs 识别为 `Button::Unknown` 或轴错位的街机摇杆和廉价手柄。
+/// 每个设备在单独的线程中读取，按映射文件中该设备（或 `*`）的映射转换为按钮和摇杆输入。
+pub struct EvdevSource {
+    registry: DeviceRegistry,
+    running: Arc<AtomicBool>,
+    threads: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    // 每个设备最近的原始事件，用于编写映射
+    last_raw: Arc<Mutex<HashMap<PadId, RawEvent>>>,
+    // 转储回放完毕
+    finished: Arc<AtomicBool>,
+}
+
+impl EvdevSource {
+    /// 扫描 `/dev/input` 并持续监视新连接的设备
+    pub fn start(mappings: Vec<EvdevMapping>) -> Result<Arc<Self>, String> {
+        fs::read_dir("/dev/input").map_err(|e| format!("无法读取 /dev/input: {}", e))?;
+
+        let source = Arc::new(Self::empty());
+        let registry = source.registry.clone();
+        let running = source.running.clone();
+        let threads = source.threads.clone();
+        let last_raw = source.last_raw.clone();
+
+        let monitor = thread::spawn(move || {
+            info!("evdev 输入源已启动");
+            let open: Arc<Mutex<HashSet<PathBuf>>> = Arc::new(Mutex::new(HashSet::new()));
+            let next_id = AtomicUsize::new(0);
+            let mut last_scan: Option<Instant> = None;
+
+            while running.load(Ordering::Relaxed) {
+                if last_scan.is_none_or(|at| at.elapsed() >= RESCAN_INTERVAL) {
+                    last_scan = Some(Instant::now());
+                    for path in event_devices() {
+                        if open.lock().is_ok_and(|open| open.contains(&path)) {
+                            continue;
+                        }
+                        let Some((file, device)) = open_device(&path, &mappings, &next_id) else {
+                            continue;
+                        };
+                        let Some(mapping) = EvdevMapping::find(&mappings, &device.name) else {
+                            continue;
+                        };
+                        let translator = Translator::new(mapping, axis_ranges(file.as_raw_fd(), mapping));
+                        if let Ok(mut open) = open.lock() {
+                            open.insert(path.clone());
+                        }
+
+                        let registry = registry.clone();
+                        let running = running.clone();
+                        let last_raw = last_raw.clone();
+                        let open = open.clone();
+                        let handle = thread::spawn(move || {
+                            pump(&registry, &running, &last_raw, device, translator, file, false);
+                            if let Ok(mut open) = open.lock() {
+                                open.remove(&path);
+                            }
+                        });
+                        if let Ok(mut threads) = threads.lock() {
+                            threads.push(handle);
+                        }
+                    }
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+            info!("evdev 输入源已停止");
+        });
+        if let Ok(mut threads) = source.threads.lock() {
+            threads.push(monitor);
+        }
+        Ok(source)
+    }
+
+    /// 把事件转储作为一个手柄回放，按转储中的时间间隔产生输入
+    pub fn from_dump(name: &str, reader: impl Read + Send + 'static, mapping: &EvdevMapping) -> Arc<Self> {
+        let source = Arc::new(Self::empty());
+        let device = evdev_device(0, name, [0; 4]);
+        let translator = Translator::new(mapping, HashMap::new());
+        let registry = source.registry.clone();
+        let running = source.running.clone();
+        let last_raw = source.last_raw.clone();
+        let finished = source.finished.clone();
+        let handle = thread::spawn(move || {
+            pump(&registry, &running, &last_raw, device, translator, reader, true);
+            finished.store(true, Ordering::Relaxed);
+        });
+        if let Ok(mut threads) = source.threads.lock() {
+            threads.push(handle);
+        }
+        source
+    }
+
+    /// 打开转储文件回放，按文件名选择映射
+    pub fn load_dump(path: &Path) -> Result<Arc<Self>, String> {
+        let file = File::open(path).map_err(|e| format!("无法打开转储文件 {:?}: {}", path, e))?;
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let mappings = EvdevMapping::load_all();
+        let mapping = EvdevMapping::find(&mappings, &name).cloned().unwrap_or_else(EvdevMapping::standard);
+        Ok(Self::from_dump(&name, io::BufReader::new(file), &mapping))
+    }
+
+    fn empty() -> Self {
+        Self {
+            registry: DeviceRegistry::default(),
+            running: Arc::new(AtomicBool::new(true)),
+            threads: Arc::new(Mutex::new(Vec::new())),
+            last_raw: Arc::new(Mutex::new(HashMap::new())),
+            finished: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl InputSource for EvdevSource {
+    fn registry(&self) -> &DeviceRegistry {
+        &self.registry
+    }
+
+    fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+        // 监视线程退出前可能还会启动新的读取线程，直到列表为空
+        loop {
+            let handles: Vec<JoinHandle<()>> = self.threads.lock()
+                .map(|mut threads| threads.drain(..).collect())
+                .unwrap_or_default();
+            if handles.is_empty() {
+                break;
+            }
+            for handle in handles {
+                if let Err(e) = handle.join() {
+                    error!("停止 evdev 输入源时发生错误: {:?}", e);
+                }
+            }
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+
+    fn last_raw_input(&self, id: PadId) -> Option<String> {
+        let event = self.last_raw.lock().ok().and_then(|last| last.get(&id).copied())?;
+        let kind = if event.kind == EV_KEY { "按键" } else { "轴" };
+        Some(format!("{} 0x{:03x} = {}", kind, event.code, event.value))
+    }
+}
+
+impl Drop for EvdevSource {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 读取一个设备的事件直到断开或停止
+///
+/// `paced` 为 true 时按事件时间戳等待，用于回放转储。
+fn pump(
+    registry: &DeviceRegistry,
+    running: &AtomicBool,
+    last_raw: &Mutex<HashMap<PadId, RawEvent>>,
+    device: DeviceInfo,
+    mut translator: Translator,
+    reader: impl Read,
+    paced: bool,
+) {
+    let id = device.id;
+    info!("evdev 手柄已连接: {} (id: {})", device.name, id);
+    registry.add_device(device);
+
+    let mut events = EventReader::new(reader);
+    let started = Instant::now();
+    let mut first_time: Option<Duration> = None;
+
+    while running.load(Ordering::Relaxed) {
+        let event = match events.next_event() {
+            Ok(Some(event)) => event,
+            Ok(None) => break,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(IDLE_SLEEP);
+                continue;
+            }
+            Err(e) => {
+                warn!("读取 evdev 设备失败: {}", e);
+                break;
+            }
+        };
+
+        if paced {
+            let offset = event.time.saturating_sub(*first_time.get_or_insert(event.time));
+            while running.load(Ordering::Relaxed) && started.elapsed() < offset {
+                thread::sleep((offset - started.elapsed()).min(Duration::from_millis(20)));
+            }
+        }
+
+        if matches!(event.kind, EV_KEY | EV_ABS)
+            && let Ok(mut last) = last_raw.lock()
+        {
+            last.insert(id, event);
+        }
+        for input in translator.translate(event) {
+            match input {
+                Translated::Button(button, pressed) => registry.set_button(id, button, pressed),
+                Translated::Axis(axis, value) => registry.set_axis(id, axis, value),
+            }
+        }
+    }
+
+    // 转储回放结束后保留手柄，与脚本输入源一致
+    if !paced || !running.load(Ordering::Relaxed) {
+        info!("evdev 手柄已断开: id {}", id);
+        registry.remove_device(id);
+    }
+}
+
+/// `/dev/input` 下的事件设备
+fn event_devices() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir("/dev/input") else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("event")))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// 打开事件设备，不是手柄且没有单独映射的设备返回 None
+fn open_device(path: &Path, mappings: &[EvdevMapping], next_id: &AtomicUsize) -> Option<(File, DeviceInfo)> {
+    let file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)
+        .ok()?;
+    let fd = file.as_raw_fd();
+
+    let mut name = [0u8; EVIOCGNAME_LEN];
+    // SAFETY: 缓冲区长度与请求中的长度一致
+    let length = unsafe { libc::ioctl(fd, EVIOCGNAME, name.as_mut_ptr()) };
+    let name = if length > 0 {
+        let end = name.iter().position(|byte| *byte == 0).unwrap_or(name.len());
+        String::from_utf8_lossy(&name[..end]).to_string()
+    } else {
+        path.display().to_string()
+    };
+
+    let has_own_mapping = mappings.iter().any(|mapping| mapping.device_name == name);
+    if !has_own_mapping && !is_gamepad(fd) {
+        return None;
+    }
+
+    let mut id = [0u16; 4];
+    // SAFETY: input_id 为 4 个 u16
+    unsafe {
+        libc::ioctl(fd, EVIOCGID, id.as_mut_ptr());
+    }
+    let device = evdev_device(next_id.fetch_add(1, Ordering::Relaxed), &name, id);
+    Some((file, device))
+}
+
+/// 设备是否报告手柄或摇杆按键
+fn is_gamepad(fd: RawFd) -> bool {
+    let mut bits = [0u8; KEY_BITS_LEN];
+    // SAFETY: 缓冲区长度与请求中的长度一致
+    if unsafe { libc::ioctl(fd, EVIOCGBIT_KEY, bits.as_mut_ptr()) } < 0 {
+        return false;
+    }
+    let has = |code: u16| bits[code as usize / 8] & (1 << (code % 8)) != 0;
+    (BTN_JOYSTICK..BTN_GAMEPAD + 16).any(has)
+}
+
+/// 读取映射中各轴的取值范围
+fn axis_ranges(fd: RawFd, mapping: &EvdevMapping) -> HashMap<u16, (i32, i32)> {
+    let mut ranges = HashMap::new();
+    for code in mapping.axes.keys() {
+        // value, minimum, maximum, fuzz, flat, resolution
+        let mut info = [0i32; 6];
+        // SAFETY: input_absinfo 为 6 个 i32
+        if unsafe { libc::ioctl(fd, eviocgabs(*code), info.as_mut_ptr()) } >= 0 && info[2] > info[1] {
+            ranges.insert(*code, (info[1], info[2]));
+        }
+    }
+    ranges
+}
+
+/// 按 SDL 的规则由总线、厂商、产品和版本号生成 UUID，与 gilrs 一致
+fn evdev_device(index: usize, name: &str, id: [u16; 4]) -> DeviceInfo {
+    let [bus, vendor, product, version] = id;
+    let mut uuid = [0u8; 16];
+    for (slot, value) in [bus, vendor, product, version].into_iter().enumerate() {
+        uuid[slot * 4..slot * 4 + 2].copy_from_slice(&value.to_le_bytes());
+    }
+    let known = |value: u16| (value != 0).then_some(value);
+    DeviceInfo {
+        id: PadId(index),
+        name: name.to_string(),
+        uuid,
+        detected: DetectedController {
+            model: ControllerModel::classify(name, known(vendor), known(product)),
+            mapping_source: MappingSource::None,
+            swap_face_buttons: false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在 x86_64 上录制的街机摇杆转储：按下南键、推动摇杆和扳机、拨动方向键帽后松开
+    #[cfg(all(target_pointer_width = "64", target_endian = "little"))]
+    const DUMP: &[u8] = include_bytes!("../tests/fixtures/arcade_stick.evdump");
+
+    #[cfg(all(target_pointer_width = "64", target_endian = "little"))]
+    fn read_dump() -> Vec<RawEvent> {
+        let mut reader = EventReader::new(DUMP);
+        let mut events = Vec::new();
+        while let Some(event) = reader.next_event().unwrap() {
+            events.push(event);
+        }
+        events
+    }
+
+    fn raw(kind: u16, code: u16, value: i32) -> RawEvent {
+        RawEvent { time: Duration::ZERO, kind, code, value }
+    }
+
+    #[test]
+    #[cfg(all(target_pointer_width = "64", target_endian = "little"))]
+    fn reads_dump_events() {
+        let events = read_dump();
+        assert_eq!(events.len(), 16);
+        assert_eq!(events[0], RawEvent { time: Duration::from_secs(1), kind: EV_KEY, code: 0x130, value: 1 });
+        assert_eq!(events[6], RawEvent { time: Duration::from_millis(1100), kind: EV_ABS, code: 0x10, value: -1 });
+        assert_eq!(events[15].time, Duration::from_millis(1300));
+
+        // 不完整的最后一个事件视为数据结束
+        let mut reader = EventReader::new(&DUMP[..std::mem::size_of::<libc::input_event>() + 5]);
+        assert!(reader.next_event().unwrap().is_some());
+        assert!(reader.next_event().unwrap().is_none());
+    }
+
+    #[test]
+    #[cfg(all(target_pointer_width = "64", target_endian = "little"))]
+    fn translates_dump_with_reported_ranges() {
+        let ranges = HashMap::from([(0x00, (0, 255)), (0x01, (0, 255)), (0x02, (0, 255))]);
+        let mut translator = Translator::new(&EvdevMapping::standard(), ranges);
+        let translated: Vec<Translated> = read_dump().into_iter().flat_map(|event| translator.translate(event)).collect();
+        assert_eq!(
+            translated,
+            vec![
+                Translated::Button(Button::South, true),
+                Translated::Axis(Axis::LeftStickX, 1.0),
+                // Y 轴向下为正，映射中取反
+                Translated::Axis(Axis::LeftStickY, 1.0),
+                Translated::Axis(Axis::LeftZ, 128.0 / 255.0),
+                Translated::Button(Button::DPadLeft, true),
+                Translated::Button(Button::DPadLeft, false),
+                Translated::Button(Button::DPadRight, true),
+                Translated::Button(Button::DPadRight, false),
+                // 自动重复和未映射的键码被忽略
+                Translated::Button(Button::South, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn normalizes_axis_ranges() {
+        let mapping = EvdevMapping::standard();
+        let mut translator = Translator::new(&mapping, HashMap::from([(0x03, (-128, 127)), (0x05, (0, 1023))]));
+        let axis = |translator: &mut Translator, code: u16, value: i32| match translator.translate(raw(EV_ABS, code, value))[..] {
+            [Translated::Axis(_, value)] => value,
+            ref other => panic!("{:?}", other),
+        };
+        assert_eq!(axis(&mut translator, 0x03, -128), -1.0);
+        assert_eq!(axis(&mut translator, 0x03, 127), 1.0);
+        // 超出报告范围的值被限制
+        assert_eq!(axis(&mut translator, 0x03, 500), 1.0);
+        assert_eq!(axis(&mut translator, 0x05, 0), 0.0);
+        assert_eq!(axis(&mut translator, 0x05, 1023), 1.0);
+        // 没有报告范围的轴按 -32768..32767 处理
+        assert_eq!(axis(&mut translator, 0x00, -32768), -1.0);
+        assert_eq!(axis(&mut translator, 0x00, 32767), 1.0);
+        assert_eq!(axis(&mut translator, 0x04, -32768), 1.0);
+    }
+
+    #[test]
+    fn custom_mapping_overrides_codes() {
+        let mapping = EvdevMapping {
+            device_name: "Arcade Stick".to_string(),
+            buttons: BTreeMap::from([(0x120, "Start".to_string()), (0x121, "NotAButton".to_string())]),
+            axes: BTreeMap::from([(0x00, AbsTarget::Axis { axis: "RightStickX".to_string(), invert: true })]),
+        };
+        let mut translator = Translator::new(&mapping, HashMap::new());
+        assert_eq!(translator.translate(raw(EV_KEY, 0x120, 1)), vec![Translated::Button(Button::Start, true)]);
+        assert_eq!(translator.translate(raw(EV_KEY, 0x121, 1)), Vec::new());
+        assert_eq!(translator.translate(raw(EV_KEY, 0x130, 1)), Vec::new());
+        assert_eq!(translator.translate(raw(EV_ABS, 0x00, 32767)), vec![Translated::Axis(Axis::RightStickX, -1.0)]);
+
+        let mappings = [EvdevMapping::standard(), mapping];
+        assert_eq!(EvdevMapping::find(&mappings, "Arcade Stick").unwrap().device_name, "Arcade Stick");
+        assert_eq!(EvdevMapping::find(&mappings, "Other Pad").unwrap().device_name, "*");
+    }
+}
@@ -0,0 +1,298 @@
+// 控制器事件总线
+use log::{debug, warn};
+use serde::Serialize;
+use std::io::Write;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+use crate::device_manager::PadId;
+
+/// 每个订阅者最多积压的消息数，超出后新消息对该订阅者丢弃
+const SUBSCRIBER_CAPACITY: usize = 1024;
+
+/// 鼠标按键
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseAction {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+}
+
+impl MouseAction {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            MouseAction::Left => "左键",
+            MouseAction::Right => "右键",
+            MouseAction::Middle => "中键",
+            MouseAction::Back => "后退键",
+            MouseAction::Forward => "前进键",
+        }
+    }
+}
+
+/// 调整光标速度的模式
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpeedMode {
+    Precision,
+    Turbo,
+}
+
+impl SpeedMode {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            SpeedMode::Precision => "精确模式",
+            SpeedMode::Turbo => "加速模式",
+        }
+    }
+}
+
+/// 控制线程产生的事件
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub enum ControllerEvent {
+    Connected,
+    Disconnected,
+    MouseMove { dx: i32, dy: i32 },
+    MouseDown(MouseAction),
+    MouseUp(MouseAction),
+    /// 一次完整的点击，`count` 为连击次数
+    Click { button: MouseAction, count: u8 },
+    Scroll { horizontal: bool, amount: i32 },
+    KeyDown(String),
+    KeyUp(String),
+    KeyTap(String),
+    ModeChanged { mode: SpeedMode, active: bool },
+    PositionSaved { x: i32, y: i32 },
+    ProfileCycleRequested,
+    /// 界面切换了手柄使用的配置档案
+    ProfileSwitched(String),
+    Paused,
+    Resumed,
+    /// 光标被屏幕边缘挡住
+    ScreenEdge,
+    /// 电池电量降到警告阈值以下
+    BatteryLow(u8),
+}
+
+impl ControllerEvent {
+    /// 活动记录中显示的描述
+    pub fn describe(&self) -> String {
+        match self {
+            ControllerEvent::Connected => "手柄已连接".to_string(),
+            ControllerEvent::Disconnected => "手柄已断开".to_string(),
+            ControllerEvent::MouseMove { dx, dy } => format!("移动光标 ({}, {})", dx, dy),
+            ControllerEvent::MouseDown(button) => format!("按下{}", button.display_name()),
+            ControllerEvent::MouseUp(button) => format!("松开{}", button.display_name()),
+            ControllerEvent::Click { button, count: 1 } => format!("{}单击", button.display_name()),
+            ControllerEvent::Click { button, count } => format!("{}{}连击", button.display_name(), count),
+            ControllerEvent::Scroll { horizontal: false, amount } => format!("垂直滚动 {}", amount),
+            ControllerEvent::Scroll { horizontal: true, amount } => format!("水平滚动 {}", amount),
+            ControllerEvent::KeyDown(key) => format!("按下按键 {}", key),
+            ControllerEvent::KeyUp(key) => format!("松开按键 {}", key),
+            ControllerEvent::KeyTap(key) => format!("按键 {}", key),
+            ControllerEvent::ModeChanged { mode, active: true } => format!("进入{}", mode.display_name()),
+            ControllerEvent::ModeChanged { mode, active: false } => format!("退出{}", mode.display_name()),
+            ControllerEvent::PositionSaved { x, y } => format!("记录位置 ({}, {})", x, y),
+            ControllerEvent::ProfileCycleRequested => "请求切换配置档案".to_string(),
+            ControllerEvent::ProfileSwitched(name) => format!("切换到档案 {}", name),
+            ControllerEvent::Paused => "暂停输出".to_string(),
+            ControllerEvent::Resumed => "恢复输出".to_string(),
+            ControllerEvent::ScreenEdge => "光标到达屏幕边缘".to_string(),
+            ControllerEvent::BatteryLow(level) => format!("电量不足 {}%", level),
+        }
+    }
+
+    /// 高频事件，活动记录和日志中省略
+    pub fn is_high_frequency(&self) -> bool {
+        matches!(self, ControllerEvent::MouseMove { .. } | ControllerEvent::Scroll { .. })
+    }
+}
+
+/// 总线上传递的消息
+#[derive(Serialize, Clone, Debug)]
+pub struct BusMessage {
+    pub time: SystemTime,
+    /// 产生事件的手柄名称
+    pub pad: String,
+    pub pad_id: PadId,
+    pub event: ControllerEvent,
+}
+
+/// 控制器事件总线
+///
+/// 控制线程发布事件，界面、日志和外部集成各自订阅。
+/// 订阅者的接收端被丢弃后自动取消订阅；订阅者处理不过来时丢弃新消息，
+/// 不会阻塞控制线程，也不会无限占用内存。
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<SyncSender<BusMessage>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// 订阅所有事件
+    pub fn subscribe(&self) -> Receiver<BusMessage> {
+        let (tx, rx) = mpsc::sync_channel(SUBSCRIBER_CAPACITY);
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(tx);
+        }
+        rx
+    }
+
+    /// 发布事件
+    pub fn publish(&self, pad_id: PadId, pad: &str, event: ControllerEvent) {
+        let Ok(mut subscribers) = self.subscribers.lock() else {
+            return;
+        };
+        if subscribers.is_empty() {
+            return;
+        }
+        let message = BusMessage {
+            time: SystemTime::now(),
+            pad: pad.to_string(),
+            pad_id,
+            event,
+        };
+        subscribers.retain(|subscriber| match subscriber.try_send(message.clone()) {
+            Ok(()) | Err(TrySendError::Full(_)) => true,
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    }
+}
+
+/// 启动把事件写入日志的订阅线程，总线释放后线程自动退出
+pub fn spawn_log_subscriber(bus: &EventBus) {
+    let events = bus.subscribe();
+    thread::spawn(move || {
+        while let Ok(message) = events.recv() {
+            if !message.event.is_high_frequency() {
+                debug!("[{}] {}", message.pad, message.event.describe());
+            }
+        }
+    });
+}
+
+/// 启动把事件以 JSON 行写到标准输出的订阅线程，供外部程序通过管道读取
+pub fn spawn_json_subscriber(bus: &EventBus) {
+    let events = bus.subscribe();
+    thread::spawn(move || write_json_events(events, std::io::stdout()));
+}
+
+/// 每个事件写一行 JSON，总线释放或输出关闭时返回
+fn write_json_events(events: Receiver<BusMessage>, mut out: impl Write) {
+    while let Ok(message) = events.recv() {
+        let written = serde_json::to_writer(&mut out, &message)
+            .map_err(|e| e.to_string())
+            .and_then(|_| writeln!(out).and_then(|_| out.flush()).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("事件输出已关闭: {}", e);
+            return;
+        }
+    }
+}
+
+/// 活动统计
+#[derive(Default, Clone)]
+pub struct ActivityStats {
+    pub clicks: u64,
+    pub scroll_steps: u64,
+    pub key_presses: u64,
+    pub mode_changes: u64,
+    pub disconnects: u64,
+    /// 光标移动的累计距离（像素）
+    pub distance: f64,
+}
+
+impl ActivityStats {
+    pub fn record(&mut self, event: &ControllerEvent) {
+        match event {
+            ControllerEvent::MouseMove { dx, dy } => {
+                self.distance += ((*dx as f64).powi(2) + (*dy as f64).powi(2)).sqrt();
+            }
+            ControllerEvent::MouseDown(_) => self.clicks += 1,
+            ControllerEvent::Click { count, .. } => self.clicks += *count as u64,
+            ControllerEvent::Scroll { amount, .. } => self.scroll_steps += amount.unsigned_abs() as u64,
+            ControllerEvent::KeyDown(_) | ControllerEvent::KeyTap(_) => self.key_presses += 1,
+            ControllerEvent::ModeChanged { active: true, .. } => self.mode_changes += 1,
+            ControllerEvent::Disconnected => self.disconnects += 1,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn publish_fans_out_to_every_subscriber() {
+        let bus = EventBus::new();
+        let first = bus.subscribe();
+        let second = bus.subscribe();
+        bus.publish(PadId(3), "手柄", ControllerEvent::Click { button: MouseAction::Left, count: 2 });
+
+        for events in [&first, &second] {
+            let message = events.try_recv().unwrap();
+            assert_eq!(message.pad, "手柄");
+            assert_eq!(message.pad_id, PadId(3));
+            assert_eq!(message.event, ControllerEvent::Click { button: MouseAction::Left, count: 2 });
+            assert!(events.try_recv().is_err());
+        }
+    }
+
+    #[test]
+    fn dropped_subscribers_are_removed() {
+        let bus = EventBus::new();
+        let kept = bus.subscribe();
+        drop(bus.subscribe());
+        bus.publish(PadId(0), "手柄", ControllerEvent::Paused);
+        assert_eq!(bus.subscribers.lock().unwrap().len(), 1);
+        assert_eq!(kept.try_recv().unwrap().event, ControllerEvent::Paused);
+    }
+
+    #[test]
+    fn full_subscriber_drops_messages_without_blocking_others() {
+        let bus = EventBus::new();
+        let slow = bus.subscribe();
+        let fast = bus.subscribe();
+        for amount in 0..SUBSCRIBER_CAPACITY as i32 + 10 {
+            bus.publish(PadId(0), "手柄", ControllerEvent::Scroll { horizontal: false, amount });
+            assert_eq!(
+                fast.try_recv().unwrap().event,
+                ControllerEvent::Scroll { horizontal: false, amount }
+            );
+        }
+        assert_eq!(slow.try_iter().count(), SUBSCRIBER_CAPACITY);
+
+        // 积压清空后继续接收
+        bus.publish(PadId(0), "手柄", ControllerEvent::Resumed);
+        assert_eq!(slow.try_recv().unwrap().event, ControllerEvent::Resumed);
+    }
+
+    #[test]
+    fn json_subscriber_writes_one_line_per_event() {
+        let bus = EventBus::new();
+        let events = bus.subscribe();
+        bus.publish(PadId(1), "手柄", ControllerEvent::MouseMove { dx: 3, dy: -4 });
+        bus.publish(PadId(1), "手柄", ControllerEvent::KeyTap("Space".to_string()));
+        drop(bus);
+
+        let mut out = Vec::new();
+        write_json_events(events, &mut out);
+        let lines: Vec<serde_json::Value> = String::from_utf8(out).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["pad"], "手柄");
+        assert_eq!(lines[0]["pad_id"], 1);
+        assert_eq!(lines[0]["event"]["MouseMove"]["dy"], -4);
+        assert_eq!(lines[1]["event"]["KeyTap"], "Space");
+    }
+}
@@ -1,6 +1,13 @@
+use crate::axis_remap::AxisRemap;
 use crate::config::Config;
-use enigo::{Enigo, MouseControllable};
-use gilrs::{Axis, Button, Event, EventType, Gilrs, GamepadId};
+use crate::connection::ConnectionReporter;
+use crate::controller_model::swap_face_button;
+use crate::event_bus::{ControllerEvent, EventBus, MouseAction, SpeedMode};
+use crate::device_manager::{DeviceEvent, DeviceInfo, PadState, ReconnectPolicy};
+use crate::input::InputSource;
+use crate::output::{OutputSink, SinkFactory};
+use crate::multi_pad::{keyboard_key_for_button, keyboard_key_for_stick, PadCoordination, PadRole};
+use gilrs::{Axis, Button};
 use log::{info, error, warn, debug};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -44,19 +51,40 @@ thread_local! {
 pub struct GamepadController {
     thread_handle: Option<JoinHandle<()>>,
     running: Arc<AtomicBool>,
-    gamepad_id: GamepadId,
+    // 当前绑定的手柄，重新连接或切换手柄后会改变
+    binding: Arc<Mutex<DeviceInfo>>,
+    // 共享的手柄设备管理服务
+    devices: Arc<dyn InputSource>,
     config: Arc<Mutex<Config>>,
     // 增加连接状态跟踪
     last_activity: Arc<Mutex<Instant>>,
     is_connected: Arc<AtomicBool>,
     // 新增错误恢复和重试机制的字段
     connection_lost_time: Arc<Mutex<Option<Instant>>>,
+    // 手柄上请求切换到下一个配置档案
+    profile_cycle_requested: Arc<AtomicBool>,
+    // 是否交换南/东、西/北按钮（任天堂布局的面键校正）
+    swap_face_buttons: Arc<AtomicBool>,
+    // 多手柄模式下的角色和控制权
+    coordination: Arc<Mutex<PadCoordination>>,
+    // 暂停时保留控制器但不输出
+    paused: Arc<AtomicBool>,
+    // 该手柄的轴重映射，在所有处理之前应用
+    axis_remap: Arc<Mutex<AxisRemap>>,
+    // 向界面的连接状态机报告手柄断开和重新连接
+    connection_reporter: Arc<Mutex<Option<ConnectionReporter>>>,
 }
 
 impl GamepadController {
-    /// 创建新的手柄控制器
-    pub fn new(gilrs: Gilrs, gamepad_id: GamepadId, config: Config) -> Self {
-        info!("创建控制器: gamepad_id={:?}", gamepad_id);
+    /// 创建使用指定输出端的手柄控制器
+    pub fn with_output(
+        devices: Arc<dyn InputSource>,
+        bus: Arc<EventBus>,
+        device: DeviceInfo,
+        config: Config,
+        make_output: SinkFactory,
+    ) -> Self {
+        info!("创建控制器: gamepad_id={:?}", device.id);
         let running = Arc::new(AtomicBool::new(true));
         let running_clone = running.clone();
         let config = Arc::new(Mutex::new(config));
@@ -69,63 +97,107 @@ impl GamepadController {
         let is_connected_clone = is_connected.clone();
         let connection_lost_time = Arc::new(Mutex::new(None));
         let connection_lost_time_clone = connection_lost_time.clone();
+        let profile_cycle_requested = Arc::new(AtomicBool::new(false));
+        let profile_cycle_requested_clone = profile_cycle_requested.clone();
+        let swap_face_buttons = Arc::new(AtomicBool::new(device.detected.swap_face_buttons));
+        let swap_face_buttons_clone = swap_face_buttons.clone();
+        let coordination = Arc::new(Mutex::new(PadCoordination::default()));
+        let coordination_thread = coordination.clone();
+        let paused = Arc::new(AtomicBool::new(false));
+        let paused_thread = paused.clone();
+        let axis_remap = Arc::new(Mutex::new(AxisRemap::default()));
+        let axis_remap_thread = axis_remap.clone();
+        let connection_reporter: Arc<Mutex<Option<ConnectionReporter>>> = Arc::new(Mutex::new(None));
+        let reporter_thread = connection_reporter.clone();
+        // 在线程启动前订阅，避免错过事件
+        let events = devices.subscribe();
+        let devices_thread = devices.clone();
+        if !devices.claim(device.id) {
+            warn!("手柄 {} 已被其他控制器占用", device.name);
+        }
+        let binding = Arc::new(Mutex::new(device));
+        let binding_thread = binding.clone();
 
         // 创建控制线程
         let thread_handle = thread::spawn(move || {
             // 初始化鼠标控制器
-            let mut enigo = Enigo::new();
+            let mut output = make_output();
             info!("成功初始化鼠标控制器");
 
             // 记录上次鼠标位置更新时间，用于计算鼠标速度
             let mut last_update = Instant::now();
+            
+            // 光标移动的小数部分，累积到下一次移动，每个控制线程单独保存
+            let mut accum = (0.0f32, 0.0f32);
 
             // 鼠标按键状态
             let mut mouse_buttons_down: HashMap<&str, bool> = HashMap::new();
             mouse_buttons_down.insert("left", false);
             mouse_buttons_down.insert("right", false);
             mouse_buttons_down.insert("middle", false);
+            mouse_buttons_down.insert("back", false);
+            mouse_buttons_down.insert("forward", false);
 
-            info!("开始监听手柄输入 (gamepad_id: {:?})", gamepad_id);
-            let mut gilrs = gilrs;
+            // 水平滚轮（倾斜）重复间隔计时
+            let mut last_tilt_scroll = Instant::now();
+            
+            // 键盘角色下按住的按键和摇杆方向键的重复计时
+            let mut keys_down: Vec<enigo::Key> = Vec::new();
+            let mut last_stick_key = Instant::now();
+            
+            // 交接模式下上一轮是否持有控制权
+            let mut had_control = true;
+            
+            // 精确/加速模式的上一轮状态，变化时发布事件
+            let mut precision_active = false;
+            let mut turbo_active = false;
+            
+            // 上一次移动后的光标位置，以及光标是否停在屏幕边缘
+            let mut last_position: Option<(i32, i32)> = None;
+            let mut at_screen_edge = false;
             
+            // 发布事件时使用的手柄名称，切换手柄后更新
+            let mut pad_name = bound_device(&binding_thread).name;
+
+            info!("开始监听手柄输入 (gamepad_id: {:?})", bound_device(&binding_thread).id);
             // 定义一个连接状态检查计时器
             let mut last_connection_check = Instant::now();
             let connection_check_interval = Duration::from_secs(1); // 每1秒检查一次连接状态
 
             // 主循环
             while running_clone.load(Ordering::Relaxed) {
+                let mut gamepad_id = bound_device(&binding_thread).id;
+                
                 // 定期检查手柄连接状态
                 if last_connection_check.elapsed() >= connection_check_interval {
                     last_connection_check = Instant::now();
                     
-                    // 检查手柄是否还连接着
-                    let gamepad = gilrs.gamepad(gamepad_id);
-                    if !gamepad.is_connected() {
+                    // 检查绑定的手柄是否还连接着，同一 ID 可能已分配给其他手柄
+                    let key = bound_device(&binding_thread).key();
+                    let mut attached = devices_thread.device(gamepad_id).is_some_and(|device| device.key() == key);
+                    if !attached {
+                        // 手柄 ID 在重新连接后可能改变，按 UUID 和名称找回同一个手柄
+                        if let Some(device) = devices_thread.find_unclaimed(&key) {
+                            info!("找回断开的手柄: {} (新 id: {:?})", device.name, device.id);
+                            gamepad_id = device.id;
+                            attached = attach_device(devices_thread.as_ref(), &binding_thread, &swap_face_buttons_clone, device);
+                        }
+                    }
+                    if !attached {
                         if is_connected_clone.load(Ordering::Relaxed) {
                             warn!("检测到手柄连接丢失");
                             is_connected_clone.store(false, Ordering::Relaxed);
+                            bus.publish(gamepad_id, &pad_name, ControllerEvent::Disconnected);
+                            report_connection(&reporter_thread, false);
                             
                             // 记录连接丢失时间
                             if let Ok(mut lost_time) = connection_lost_time_clone.lock() {
                                 *lost_time = Some(Instant::now());
                             }
                             
-                            // 确保所有鼠标按键都释放
-                            for (key, is_down) in mouse_buttons_down.iter() {
-                                if *is_down {
-                                    match *key {
-                                        "left" => enigo.mouse_up(enigo::MouseButton::Left),
-                                        "right" => enigo.mouse_up(enigo::MouseButton::Right),
-                                        "middle" => enigo.mouse_up(enigo::MouseButton::Middle),
-                                        _ => {}
-                                    }
-                                }
-                            }
-                            
-                            // 重置按键状态
-                            mouse_buttons_down.insert("left", false);
-                            mouse_buttons_down.insert("right", false);
-                            mouse_buttons_down.insert("middle", false);
+                            // 确保所有鼠标按键和键盘按键都释放
+                            release_all_buttons(output.as_mut(), &mut mouse_buttons_down);
+                            release_all_keys(output.as_mut(), &mut keys_down);
                         }
                         
                         // 手柄断开时，短暂休眠以减少CPU占用
@@ -135,6 +207,12 @@ impl GamepadController {
                         // 手柄重新连接
                         info!("手柄重新连接成功");
                         is_connected_clone.store(true, Ordering::Relaxed);
+                        pad_name = bound_device(&binding_thread).name;
+                        bus.publish(gamepad_id, &pad_name, ControllerEvent::Connected);
+                        report_connection(&reporter_thread, true);
+                        
+                        // 丢弃断开期间积压的事件
+                        while events.try_recv().is_ok() {}
                         
                         // 清除连接丢失时间
                         if let Ok(mut lost_time) = connection_lost_time_clone.lock() {
@@ -153,17 +231,44 @@ impl GamepadController {
                     *last_activity = Instant::now();
                 }
 
+                // 读取多手柄协作设置
+                let coordination = match coordination_thread.lock() {
+                    Ok(guard) => guard.clone(),
+                    Err(poisoned) => poisoned.into_inner().clone(),
+                };
+                
+                let paused = paused_thread.load(Ordering::Relaxed);
+                
                 // 处理手柄事件
-                while let Some(Event { id, event, time: _ }) = gilrs.next_event() {
-                    if id != gamepad_id {
-                        continue;
-                    }
-
+                // 面键校正：把手柄上报的按钮名称转换为按位置的名称
+                let face_swap = swap_face_buttons_clone.load(Ordering::Relaxed);
+                let to_positional = |name: String| -> String {
+                    if face_swap { swap_face_button(&name).to_string() } else { name }
+                };
+                
+                while let Ok(event) = events.try_recv() {
                     match event {
                         // 按钮按下事件
-                        EventType::ButtonPressed(button, _) => {
+                        // 暂停时忽略按下，释放事件照常处理
+                        DeviceEvent::ButtonPressed(id, _) if id == gamepad_id && paused => {}
+                        DeviceEvent::ButtonPressed(id, button) if id == gamepad_id => {
+                            // 交接模式下，按下按钮的手柄取得控制权
+                            coordination.claim();
+                            let button_str = to_positional(button_to_string(button));
+                            
+                            // 键盘角色：按钮直接映射为按键
+                            if coordination.role == PadRole::Keyboard {
+                                if let Some(key) = keyboard_key_for_button(&button_str) {
+                                    info!("键盘角色按下: {:?}", key);
+                                    output.key_down(key);
+                                    keys_down.push(key);
+                                    bus.publish(gamepad_id, &pad_name, ControllerEvent::KeyDown(format!("{:?}", key)));
+                                }
+                                continue;
+                            }
+                            
                             // 获取按钮名称，并处理可能的锁失败
-                            let config_guard = match config_thread.lock() {
+                            let mut config_guard = match config_thread.lock() {
                                 Ok(guard) => guard,
                                 Err(poisoned) => {
                                     error!("配置锁被毒化: {:?}", poisoned);
@@ -171,45 +276,123 @@ impl GamepadController {
                                 }
                             };
                             
-                            let button_str = button_to_string(button);
-                            
                             // 左键点击
                             if button_str == config_guard.left_click_button {
                                 info!("左键点击");
-                                enigo.mouse_down(enigo::MouseButton::Left);
+                                output.button_down(MouseAction::Left);
                                 mouse_buttons_down.insert("left", true);
+                                bus.publish(gamepad_id, &pad_name, ControllerEvent::MouseDown(MouseAction::Left));
                             }
 
                             // 右键点击
                             if button_str == config_guard.right_click_button {
                                 info!("右键点击");
-                                enigo.mouse_down(enigo::MouseButton::Right);
+                                output.button_down(MouseAction::Right);
                                 mouse_buttons_down.insert("right", true);
+                                bus.publish(gamepad_id, &pad_name, ControllerEvent::MouseDown(MouseAction::Right));
                             }
 
                             // 中键点击
                             if button_str == config_guard.middle_click_button {
                                 info!("中键点击");
-                                enigo.mouse_down(enigo::MouseButton::Middle);
+                                output.button_down(MouseAction::Middle);
                                 mouse_buttons_down.insert("middle", true);
+                                bus.publish(gamepad_id, &pad_name, ControllerEvent::MouseDown(MouseAction::Middle));
                             }
                             
-                            // 双击功能
+                            // 双击和三击（选中整段文字等）的点击之间有间隔，在释放配置锁后执行
+                            let mut repeated_clicks = Vec::new();
                             if button_str == config_guard.double_click_button {
                                 info!("双击");
-                                enigo.mouse_down(enigo::MouseButton::Left);
-                                enigo.mouse_up(enigo::MouseButton::Left);
-                                thread::sleep(Duration::from_millis(50));
-                                enigo.mouse_down(enigo::MouseButton::Left);
-                                enigo.mouse_up(enigo::MouseButton::Left);
+                                repeated_clicks.push(2);
+                            }
+                            if button_str == config_guard.triple_click_button {
+                                info!("三击");
+                                repeated_clicks.push(3);
                             }
                             
-                            // 配置锁在这里自动释放
+                            // 侧键：后退 / 前进
+                            if button_str == config_guard.back_button {
+                                if output.supports_side_buttons() {
+                                    info!("侧键后退");
+                                    output.button_down(MouseAction::Back);
+                                    mouse_buttons_down.insert("back", true);
+                                    bus.publish(gamepad_id, &pad_name, ControllerEvent::MouseDown(MouseAction::Back));
+                                } else {
+                                    warn!("当前平台不支持鼠标侧键");
+                                }
+                            }
+                            
+                            if button_str == config_guard.forward_button {
+                                if output.supports_side_buttons() {
+                                    info!("侧键前进");
+                                    output.button_down(MouseAction::Forward);
+                                    mouse_buttons_down.insert("forward", true);
+                                    bus.publish(gamepad_id, &pad_name, ControllerEvent::MouseDown(MouseAction::Forward));
+                                } else {
+                                    warn!("当前平台不支持鼠标侧键");
+                                }
+                            }
+                            
+                            // 记录当前光标位置
+                            if button_str == config_guard.save_position_button {
+                                let position = output.location();
+                                info!("已记录点击位置: {:?}", position);
+                                config_guard.saved_click_position = Some(position);
+                                bus.publish(gamepad_id, &pad_name, ControllerEvent::PositionSaved { x: position.0, y: position.1 });
+                            }
+                            
+                            // 在记录的位置点击，然后将光标移回原处
+                            if button_str == config_guard.saved_position_click_button {
+                                match config_guard.saved_click_position {
+                                    Some((x, y)) => {
+                                        info!("在记录的位置点击: ({}, {})", x, y);
+                                        let (origin_x, origin_y) = output.location();
+                                        output.move_to(x, y);
+                                        output.click(MouseAction::Left);
+                                        output.move_to(origin_x, origin_y);
+                                        bus.publish(gamepad_id, &pad_name, ControllerEvent::Click { button: MouseAction::Left, count: 1 });
+                                    }
+                                    None => warn!("尚未记录点击位置"),
+                                }
+                            }
+                            
+                            // 切换配置档案，由界面线程负责实际切换并下发新配置
+                            if button_str == config_guard.profile_cycle_button {
+                                info!("请求切换到下一个配置档案");
+                                profile_cycle_requested_clone.store(true, Ordering::Relaxed);
+                                bus.publish(gamepad_id, &pad_name, ControllerEvent::ProfileCycleRequested);
+                            }
+                            
+                            // 释放配置锁，连击期间界面可以继续更新配置
+                            drop(config_guard);
+                            for count in repeated_clicks {
+                                for i in 0..count {
+                                    if i > 0 {
+                                        thread::sleep(Duration::from_millis(50));
+                                    }
+                                    output.button_down(MouseAction::Left);
+                                    output.button_up(MouseAction::Left);
+                                }
+                                bus.publish(gamepad_id, &pad_name, ControllerEvent::Click { button: MouseAction::Left, count });
+                            }
                         }
 
                         // 按钮释放事件
-                        EventType::ButtonReleased(button, _) => {
-                            let button_str = button_to_string(button);
+                        DeviceEvent::ButtonReleased(id, button) if id == gamepad_id => {
+                            let button_str = to_positional(button_to_string(button));
+                            
+                            // 键盘角色：释放对应按键
+                            if coordination.role == PadRole::Keyboard {
+                                if let Some(key) = keyboard_key_for_button(&button_str)
+                                    && let Some(pos) = keys_down.iter().position(|k| *k == key)
+                                {
+                                    output.key_up(key);
+                                    keys_down.remove(pos);
+                                    bus.publish(gamepad_id, &pad_name, ControllerEvent::KeyUp(format!("{:?}", key)));
+                                }
+                                continue;
+                            }
                             
                             // 安全地获取配置，处理可能的锁失败
                             let config_guard = match config_thread.lock() {
@@ -224,55 +407,69 @@ impl GamepadController {
                             if button_str == config_guard.left_click_button
                                 && *mouse_buttons_down.get("left").unwrap_or(&false)
                             {
-                                enigo.mouse_up(enigo::MouseButton::Left);
+                                output.button_up(MouseAction::Left);
                                 mouse_buttons_down.insert("left", false);
+                                bus.publish(gamepad_id, &pad_name, ControllerEvent::MouseUp(MouseAction::Left));
                             }
 
                             // 右键释放
                             if button_str == config_guard.right_click_button
                                 && *mouse_buttons_down.get("right").unwrap_or(&false)
                             {
-                                enigo.mouse_up(enigo::MouseButton::Right);
+                                output.button_up(MouseAction::Right);
                                 mouse_buttons_down.insert("right", false);
+                                bus.publish(gamepad_id, &pad_name, ControllerEvent::MouseUp(MouseAction::Right));
                             }
 
                             // 中键释放
                             if button_str == config_guard.middle_click_button
                                 && *mouse_buttons_down.get("middle").unwrap_or(&false)
                             {
-                                enigo.mouse_up(enigo::MouseButton::Middle);
+                                output.button_up(MouseAction::Middle);
                                 mouse_buttons_down.insert("middle", false);
+                                bus.publish(gamepad_id, &pad_name, ControllerEvent::MouseUp(MouseAction::Middle));
+                            }
+
+                            // 侧键释放
+                            if button_str == config_guard.back_button
+                                && *mouse_buttons_down.get("back").unwrap_or(&false)
+                            {
+                                if output.supports_side_buttons() {
+                                    output.button_up(MouseAction::Back);
+                                    bus.publish(gamepad_id, &pad_name, ControllerEvent::MouseUp(MouseAction::Back));
+                                }
+                                mouse_buttons_down.insert("back", false);
+                            }
+
+                            if button_str == config_guard.forward_button
+                                && *mouse_buttons_down.get("forward").unwrap_or(&false)
+                            {
+                                if output.supports_side_buttons() {
+                                    output.button_up(MouseAction::Forward);
+                                    bus.publish(gamepad_id, &pad_name, ControllerEvent::MouseUp(MouseAction::Forward));
+                                }
+                                mouse_buttons_down.insert("forward", false);
                             }
                             
                             // 配置锁在这里自动释放
                         }
 
                         // 断开连接事件
-                        EventType::Disconnected => {
+                        DeviceEvent::Disconnected(id) if id == gamepad_id => {
                             warn!("检测到手柄断开连接事件");
-                            is_connected_clone.store(false, Ordering::Relaxed);
+                            if is_connected_clone.swap(false, Ordering::Relaxed) {
+                                bus.publish(gamepad_id, &pad_name, ControllerEvent::Disconnected);
+                                report_connection(&reporter_thread, false);
+                            }
                             
                             // 记录连接丢失时间
                             if let Ok(mut lost_time) = connection_lost_time_clone.lock() {
                                 *lost_time = Some(Instant::now());
                             }
                             
-                            // 确保所有鼠标按键都被释放
-                            for (key, is_down) in mouse_buttons_down.iter() {
-                                if *is_down {
-                                    match *key {
-                                        "left" => enigo.mouse_up(enigo::MouseButton::Left),
-                                        "right" => enigo.mouse_up(enigo::MouseButton::Right),
-                                        "middle" => enigo.mouse_up(enigo::MouseButton::Middle),
-                                        _ => {}
-                                    }
-                                }
-                            }
-                            
-                            // 重置按键状态
-                            mouse_buttons_down.insert("left", false);
-                            mouse_buttons_down.insert("right", false);
-                            mouse_buttons_down.insert("middle", false);
+                            // 确保所有鼠标按键和键盘按键都释放
+                            release_all_buttons(output.as_mut(), &mut mouse_buttons_down);
+                            release_all_keys(output.as_mut(), &mut keys_down);
                         }
 
                         // 其他按钮可以根据需要添加
@@ -292,7 +489,11 @@ impl GamepadController {
                 last_update = now;
                 
                 // 读取摇杆状态并移动鼠标
-                let gamepad = gilrs.gamepad(gamepad_id);
+                let gamepad = devices_thread.state(gamepad_id).unwrap_or_default();
+                let gamepad = match axis_remap_thread.lock() {
+                    Ok(remap) => remap.apply(&gamepad),
+                    Err(_) => gamepad,
+                };
                 
                 // 安全地获取配置
                 let config_guard = match config_thread.lock() {
@@ -311,6 +512,45 @@ impl GamepadController {
                 let left_z = gamepad.value(Axis::LeftZ);  // 左扳机
                 let right_z = gamepad.value(Axis::RightZ); // 右扳机
                 
+                // 交接模式下，摇杆有输入的手柄取得控制权
+                let claim_threshold = config_guard.dead_zone.max(0.1);
+                if [left_x, left_y, right_x, right_y].iter().any(|v| v.abs() > claim_threshold) {
+                    coordination.claim();
+                }
+                
+                // 暂停或失去控制权时释放所有按键，之后不再输出
+                let has_control = coordination.has_control() && !paused;
+                if had_control && !has_control {
+                    info!("{}", if paused { "已暂停输出" } else { "控制权已交给其他手柄" });
+                    release_all_buttons(output.as_mut(), &mut mouse_buttons_down);
+                    release_all_keys(output.as_mut(), &mut keys_down);
+                }
+                had_control = has_control;
+                if !has_control {
+                    drop(config_guard);
+                    thread::sleep(Duration::from_millis(4));
+                    continue;
+                }
+                
+                // 键盘角色：控制鼠标的摇杆映射为方向键，按住时重复
+                if coordination.role == PadRole::Keyboard {
+                    let (stick_x, stick_y) = if config_guard.use_left_stick_for_mouse {
+                        (left_x, left_y)
+                    } else {
+                        (right_x, right_y)
+                    };
+                    if let Some(key) = keyboard_key_for_stick(stick_x, stick_y, 0.6)
+                        && last_stick_key.elapsed() >= Duration::from_millis(150)
+                    {
+                        last_stick_key = Instant::now();
+                        output.key_tap(key);
+                        bus.publish(gamepad_id, &pad_name, ControllerEvent::KeyTap(format!("{:?}", key)));
+                    }
+                    drop(config_guard);
+                    thread::sleep(Duration::from_millis(4));
+                    continue;
+                }
+                
                 // 降低日志记录的阈值，使得我们能看到更多的摇杆动作
                 let log_threshold = 0.2; // 降低阈值，捕捉更小的摇杆动作
                 
@@ -351,17 +591,29 @@ impl GamepadController {
                     0.0
                 }; // 默认反转Y轴，与鼠标方向一致
 
+                // 精确模式和加速模式的切换
+                let precision = button_matches(&gamepad, face_swap, &config_guard.precision_mode_button);
+                let turbo = button_matches(&gamepad, face_swap, &config_guard.turbo_mode_button);
+                if precision != precision_active {
+                    precision_active = precision;
+                    bus.publish(gamepad_id, &pad_name, ControllerEvent::ModeChanged { mode: SpeedMode::Precision, active: precision });
+                }
+                if turbo != turbo_active {
+                    turbo_active = turbo;
+                    bus.publish(gamepad_id, &pad_name, ControllerEvent::ModeChanged { mode: SpeedMode::Turbo, active: turbo });
+                }
+                
                 if x_move != 0.0 || y_move != 0.0 {
                     // 检查精确模式和加速模式
                     let mut sensitivity_multiplier = 1.0;
                     
                     // 精确模式 - 降低灵敏度
-                    if button_matches(&gamepad, &config_guard.precision_mode_button) {
+                    if precision {
                         sensitivity_multiplier *= 0.3; // 降低到30%速度
                     }
                     
                     // 加速模式 - 提高灵敏度
-                    if button_matches(&gamepad, &config_guard.turbo_mode_button) {
+                    if turbo {
                         sensitivity_multiplier *= 2.0; // 提高到200%速度
                     }
                     
@@ -396,15 +648,9 @@ impl GamepadController {
                         * (1.0 + 6.0 * y_move.abs()); // 大幅增加大幅度移动的速度
                     
                     // 保持小数部分以积累微小移动
-                    static mut ACCUM_X: f32 = 0.0;
-                    static mut ACCUM_Y: f32 = 0.0;
-                    
-                    // 安全地访问静态变量
-                    let (accum_x, accum_y) = unsafe {
-                        ACCUM_X += x_speed * dt;
-                        ACCUM_Y += y_speed * dt;
-                        (ACCUM_X, ACCUM_Y)
-                    };
+                    accum.0 += x_speed * dt;
+                    accum.1 += y_speed * dt;
+                    let (accum_x, accum_y) = accum;
                     
                     // 为小值提供额外加速，确保即使微小移动也能生成整数位移
                     let boost_small_movements = |val: f32| -> f32 {
@@ -422,13 +668,12 @@ impl GamepadController {
                     let dx = boosted_x.trunc() as i32;
                     let dy = boosted_y.trunc() as i32;
                     
-                    // 安全地更新静态变量，保留小数部分
-                    unsafe {
-                        // 更新累积值，但保留一些动量以提高响应性
-                        let momentum_factor = 0.7; // 保留70%的动量
-                        ACCUM_X = (boosted_x - dx as f32) * momentum_factor;
-                        ACCUM_Y = (boosted_y - dy as f32) * momentum_factor;
-                    }
+                    // 更新累积值，保留小数部分和一些动量以提高响应性
+                    let momentum_factor = 0.7; // 保留70%的动量
+                    accum = (
+                        (boosted_x - dx as f32) * momentum_factor,
+                        (boosted_y - dy as f32) * momentum_factor,
+                    );
                     
                     if dx != 0 || dy != 0 {
                         // 移动鼠标（相对移动）
@@ -437,11 +682,42 @@ impl GamepadController {
                         
                         // 安全地移动鼠标，避免因为硬件错误导致崩溃
                         match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                            enigo.mouse_move_relative(dx, dy);
+                            output.move_relative(dx, dy);
                         })) {
-                            Ok(_) => {}, // 鼠标移动成功
+                            Ok(_) => bus.publish(gamepad_id, &pad_name, ControllerEvent::MouseMove { dx, dy }),
                             Err(e) => error!("移动鼠标时发生错误: {:?}", e)
                         }
+                        
+                        // 移动后位置在该方向上没有变化，说明光标被屏幕边缘挡住
+                        let position = output.location();
+                        let blocked = last_position.is_some_and(|last| {
+                            (dx != 0 && position.0 == last.0) || (dy != 0 && position.1 == last.1)
+                        });
+                        if blocked && !at_screen_edge {
+                            bus.publish(gamepad_id, &pad_name, ControllerEvent::ScreenEdge);
+                        }
+                        at_screen_edge = blocked;
+                        last_position = Some(position);
+                    } else {
+                        last_position = None;
+                    }
+                }
+                
+                // 水平滚轮 - 按住按钮时模拟滚轮左右倾斜，按固定间隔重复滚动
+                let tilt = if button_matches(&gamepad, face_swap, &config_guard.scroll_left_button) {
+                    -1
+                } else if button_matches(&gamepad, face_swap, &config_guard.scroll_right_button) {
+                    1
+                } else {
+                    0
+                };
+                if tilt != 0 && last_tilt_scroll.elapsed() >= Duration::from_millis(80) {
+                    last_tilt_scroll = Instant::now();
+                    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        output.scroll(true, tilt);
+                    })) {
+                        Ok(_) => bus.publish(gamepad_id, &pad_name, ControllerEvent::Scroll { horizontal: true, amount: tilt }),
+                        Err(e) => error!("水平滚轮操作时发生错误: {:?}", e)
                     }
                 }
                 
@@ -572,9 +848,9 @@ impl GamepadController {
                         // 安全地执行滚轮操作，避免因为硬件错误导致崩溃
                         match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                             // 执行滚轮操作，反转符号使得摇杆向下时滚轮向下滚动
-                            enigo.mouse_scroll_y(-final_amount);
+                            output.scroll(false, -final_amount);
                         })) {
-                            Ok(_) => {}, // 滚轮操作成功
+                            Ok(_) => bus.publish(gamepad_id, &pad_name, ControllerEvent::Scroll { horizontal: false, amount: -final_amount }),
                             Err(e) => error!("滚轮操作时发生错误: {:?}", e)
                         }
                     }
@@ -596,11 +872,18 @@ impl GamepadController {
         Self {
             thread_handle: Some(thread_handle),
             running,
-            gamepad_id,
+            binding,
+            devices,
             config,
             last_activity,
             is_connected,
             connection_lost_time,
+            profile_cycle_requested,
+            swap_face_buttons,
+            coordination,
+            paused,
+            axis_remap,
+            connection_reporter,
         }
     }
 
@@ -608,6 +891,7 @@ impl GamepadController {
     pub fn stop(&mut self) {
         info!("正在停止手柄控制器");
         self.running.store(false, Ordering::Relaxed);
+        self.devices.release(bound_device(&self.binding).id);
 
         if let Some(handle) = self.thread_handle.take() {
             // 等待线程结束，但设置超时避免永久阻塞
@@ -649,75 +933,54 @@ impl GamepadController {
             state.borrow_mut().reset();
         });
     }    /// 尝试恢复连接
-    pub fn try_reconnect(&mut self) -> bool {
+    ///
+    /// 原手柄重新连接时（ID 可能已改变）重新绑定到它；按 `policy` 的设置，
+    /// 原手柄超过 `timeout` 仍未返回时改为绑定其他空闲的手柄。
+    pub fn try_reconnect(&self, policy: ReconnectPolicy, timeout: Duration) -> bool {
         // 检查是否已经连接
         if self.is_connected.load(Ordering::Relaxed) {
             debug!("手柄已连接，无需重连");
             return true; // 已经连接，不需要重连
         }
-
-        info!("尝试恢复手柄连接...");
         
         // 记录上次断开连接的持续时间
-        let disconnection_duration = if let Ok(lost_time) = self.connection_lost_time.lock() {
-            lost_time.map(|t| t.elapsed().as_secs()).unwrap_or(0)
-        } else {
-            warn!("无法获取连接丢失时间，可能发生了互斥锁问题");
-            0
+        let disconnection_duration = match self.connection_lost_time.lock() {
+            Ok(lost_time) => lost_time.map(|t| t.elapsed()).unwrap_or_default(),
+            Err(_) => {
+                warn!("无法获取连接丢失时间，可能发生了互斥锁问题");
+                Duration::ZERO
+            }
         };
         
-        if disconnection_duration > 0 {
-            info!("手柄已断开连接 {} 秒", disconnection_duration);
+        // 已重新绑定到原手柄，等待控制线程恢复
+        let device = bound_device(&self.binding);
+        let key = device.key();
+        if self.devices.device(device.id).is_some_and(|current| current.key() == key) {
+            return true;
         }
         
-        // 尝试重新初始化gilrs
-        match Gilrs::new() {
-            Ok(gilrs) => {
-                // 检查手柄是否存在
-                let gamepad = gilrs.gamepad(self.gamepad_id);
-                if gamepad.is_connected() {
-                    info!("手柄重新连接成功，恢复运行状态");
-                    info!("已恢复连接的手柄：{} (id: {:?})", gamepad.name(), self.gamepad_id);
-                    self.is_connected.store(true, Ordering::Relaxed);
-                    
-                    // 重置连接丢失时间
-                    if let Ok(mut lost_time) = self.connection_lost_time.lock() {
-                        *lost_time = None;
-                    } else {
-                        warn!("无法重置连接丢失时间");
-                    }
-                    
-                    // 更新上次活动时间
-                    if let Ok(mut last_activity) = self.last_activity.lock() {
-                        *last_activity = Instant::now();
-                    } else {
-                        warn!("无法更新最后活动时间");
-                    }
-                    
-                    return true;
-                } else {
-                    info!("手柄仍然断开连接，无法恢复 ID: {:?}", self.gamepad_id);
-                    
-                    // 尝试查找其他可用手柄
-                    let mut found_alternative = false;
-                    for (id, gp) in gilrs.gamepads() {
-                        info!("发现可用的替代手柄：{} (id: {:?})", gp.name(), id);
-                        found_alternative = true;
-                        // 仅记录，不自动切换
-                    }
-                    
-                    if !found_alternative {
-                        info!("未发现其他可用手柄");
-                    }
-                    
-                    return false;
-                }
-            },
-            Err(err) => {
-                error!("重新初始化手柄系统失败: {}", err);
-                return false;
-            }
+        // 原手柄已返回
+        if let Some(device) = self.devices.find_unclaimed(&key) {
+            info!("原手柄已重新连接：{} (id: {:?})", device.name, device.id);
+            return attach_device(self.devices.as_ref(), &self.binding, &self.swap_face_buttons, device);
         }
+        
+        if policy == ReconnectPolicy::SwitchToAvailable
+            && disconnection_duration >= timeout
+            && let Some(device) = self.devices.unclaimed_devices().into_iter().next()
+        {
+            info!("原手柄 {} 秒内未返回，切换到手柄：{} (id: {:?})",
+                 disconnection_duration.as_secs(), device.name, device.id);
+            return attach_device(self.devices.as_ref(), &self.binding, &self.swap_face_buttons, device);
+        }
+        
+        debug!("手柄仍然断开连接 ({} 秒): {}", disconnection_duration.as_secs(), key.name);
+        false
+    }
+
+    /// 当前绑定的手柄
+    pub fn device(&self) -> DeviceInfo {
+        bound_device(&self.binding)
     }
 
     /// 检查控制器是否正在运行
@@ -735,6 +998,49 @@ impl GamepadController {
         self.last_activity.lock().ok().map(|guard| *guard)
     }
     
+    /// 设置多手柄模式下的角色和控制权
+    pub fn set_coordination(&self, coordination: PadCoordination) {
+        info!("手柄角色: {}, 交接控制: {}", coordination.role.display_name(), coordination.handover.is_some());
+        match self.coordination.lock() {
+            Ok(mut guard) => *guard = coordination,
+            Err(e) => *e.into_inner() = coordination,
+        }
+    }
+    
+    /// 设置该手柄的轴重映射
+    pub fn set_axis_remap(&self, remap: AxisRemap) {
+        match self.axis_remap.lock() {
+            Ok(mut guard) => *guard = remap,
+            Err(e) => *e.into_inner() = remap,
+        }
+    }
+    
+    /// 设置连接状态的报告端，控制线程在手柄断开和重新连接时通知状态机
+    pub fn set_connection_reporter(&self, reporter: ConnectionReporter) {
+        match self.connection_reporter.lock() {
+            Ok(mut guard) => *guard = Some(reporter),
+            Err(e) => *e.into_inner() = Some(reporter),
+        }
+    }
+    
+    /// 暂停或恢复输出
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+    
+    /// 取出手柄发出的档案切换请求（读取后清除）
+    pub fn take_profile_cycle_request(&self) -> bool {
+        self.profile_cycle_requested.swap(false, Ordering::Relaxed)
+    }
+    
+    /// 获取控制线程中记录的点击位置
+    pub fn saved_click_position(&self) -> Option<(i32, i32)> {
+        match self.config.lock() {
+            Ok(guard) => guard.saved_click_position,
+            Err(e) => e.into_inner().saved_click_position,
+        }
+    }
+    
     /// 获取连接丢失时间
     pub fn get_connection_lost_time(&self) -> Option<Instant> {
         match self.connection_lost_time.lock() {
@@ -751,6 +1057,72 @@ impl Drop for GamepadController {
     }
 }
 
+/// 读取控制器当前绑定的手柄
+fn report_connection(reporter: &Mutex<Option<ConnectionReporter>>, connected: bool) {
+    let guard = reporter.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(reporter) = guard.as_ref() {
+        if connected {
+            reporter.restored();
+        } else {
+            reporter.lost();
+        }
+    }
+}
+
+fn bound_device(binding: &Mutex<DeviceInfo>) -> DeviceInfo {
+    match binding.lock() {
+        Ok(guard) => guard.clone(),
+        Err(e) => e.into_inner().clone(),
+    }
+}
+
+/// 把控制器绑定到指定手柄，手柄已被其他控制器占用时返回 false
+fn attach_device(
+    devices: &dyn InputSource,
+    binding: &Mutex<DeviceInfo>,
+    swap_face_buttons: &AtomicBool,
+    device: DeviceInfo,
+) -> bool {
+    let mut guard = match binding.lock() {
+        Ok(guard) => guard,
+        Err(e) => e.into_inner(),
+    };
+    if guard.id != device.id {
+        if !devices.claim(device.id) {
+            return false;
+        }
+        devices.release(guard.id);
+    }
+    info!("控制器已绑定到手柄: {} (id: {:?})", device.name, device.id);
+    swap_face_buttons.store(device.detected.swap_face_buttons, Ordering::Relaxed);
+    *guard = device;
+    true
+}
+
+/// 释放所有按下的鼠标按键
+fn release_all_buttons(output: &mut dyn OutputSink, mouse_buttons_down: &mut HashMap<&str, bool>) {
+    for (key, is_down) in mouse_buttons_down.iter_mut() {
+        if *is_down {
+            match *key {
+                "left" => output.button_up(MouseAction::Left),
+                "right" => output.button_up(MouseAction::Right),
+                "middle" => output.button_up(MouseAction::Middle),
+                "back" => output.button_up(MouseAction::Back),
+                "forward" => output.button_up(MouseAction::Forward),
+                _ => {}
+            }
+            *is_down = false;
+        }
+    }
+}
+
+/// 释放键盘角色下按住的所有按键
+fn release_all_keys(output: &mut dyn OutputSink, keys_down: &mut Vec<enigo::Key>) {
+    for key in keys_down.drain(..) {
+        output.key_up(key);
+    }
+}
+
 /// 将Button枚举转换为字符串
 fn button_to_string(button: Button) -> String {
     match button {
@@ -777,8 +1149,9 @@ fn button_to_string(button: Button) -> String {
     }
 }
 
-/// 检查游戏手柄上的按钮是否处于按下状态
-fn button_matches(gamepad: &gilrs::Gamepad, button_name: &str) -> bool {
+/// 检查游戏手柄上的按钮是否处于按下状态，button_name 为按位置的名称
+fn button_matches(gamepad: &PadState, face_swap: bool, button_name: &str) -> bool {
+    let button_name = if face_swap { swap_face_button(button_name) } else { button_name };
     match button_name {
         "South" => gamepad.is_pressed(Button::South),
         "East" => gamepad.is_pressed(Button::East),
@@ -802,3 +1175,122 @@ fn button_matches(gamepad: &gilrs::Gamepad, button_name: &str) -> bool {
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_manager::PadId;
+    use crate::input::{scripted_device, ScriptAction, ScriptStep, ScriptedSource};
+    use crate::output::{OutputAction, RecordingSink};
+
+    const PAD: PadId = PadId(0);
+
+    /// 在脚本输入源上启动控制器，输出记录到 `RecordingSink`
+    fn start(config: Config) -> (Arc<ScriptedSource>, GamepadController, RecordingSink) {
+        let device = scripted_device(0, "Xbox Wireless Controller", [7; 16]);
+        let source = ScriptedSource::start(vec![ScriptStep {
+            at: Duration::ZERO,
+            action: ScriptAction::Connect(device.clone()),
+        }]);
+        while !source.is_finished() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        let sink = RecordingSink::new();
+        let controller = GamepadController::with_output(
+            source.clone(),
+            Arc::new(EventBus::default()),
+            device,
+            config,
+            sink.factory(),
+        );
+        (source, controller, sink)
+    }
+
+    fn actions(sink: &RecordingSink) -> Vec<OutputAction> {
+        sink.log().lock().unwrap().iter().map(|output| output.action).collect()
+    }
+
+    /// 等待输出满足条件，超时返回 false
+    fn wait_for(sink: &RecordingSink, done: impl Fn(&[OutputAction]) -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if done(&actions(sink)) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    #[test]
+    fn stick_moves_cursor() {
+        let (source, mut controller, sink) = start(Config::default());
+        source.registry().set_axis(PAD, Axis::LeftStickX, 1.0);
+        assert!(wait_for(&sink, |actions| actions.len() >= 3), "{:?}", actions(&sink));
+        controller.stop();
+
+        let moves: Vec<(i32, i32)> = actions(&sink).into_iter()
+            .filter_map(|action| match action {
+                OutputAction::MoveRelative { dx, dy } => Some((dx, dy)),
+                _ => None,
+            })
+            .collect();
+        assert!(moves.len() >= 3);
+        assert!(moves.iter().all(|&(dx, dy)| dx > 0 && dy == 0), "{:?}", moves);
+    }
+
+    #[test]
+    fn buttons_click_mouse() {
+        let (source, mut controller, sink) = start(Config::default());
+        source.registry().set_button(PAD, Button::South, true);
+        source.registry().set_button(PAD, Button::South, false);
+        source.registry().set_button(PAD, Button::East, true);
+        source.registry().set_button(PAD, Button::East, false);
+        // 默认配置中北键双击左键
+        source.registry().set_button(PAD, Button::North, true);
+        source.registry().set_button(PAD, Button::North, false);
+        assert!(wait_for(&sink, |actions| actions.len() >= 8), "{:?}", actions(&sink));
+        controller.stop();
+
+        assert_eq!(
+            actions(&sink),
+            vec![
+                OutputAction::ButtonDown(MouseAction::Left),
+                OutputAction::ButtonUp(MouseAction::Left),
+                OutputAction::ButtonDown(MouseAction::Right),
+                OutputAction::ButtonUp(MouseAction::Right),
+                OutputAction::ButtonDown(MouseAction::Left),
+                OutputAction::ButtonUp(MouseAction::Left),
+                OutputAction::ButtonDown(MouseAction::Left),
+                OutputAction::ButtonUp(MouseAction::Left),
+            ]
+        );
+    }
+
+    #[test]
+    fn right_stick_scrolls() {
+        let (source, mut controller, sink) = start(Config::default());
+        // 摇杆向下时滚轮向下滚动
+        source.registry().set_axis(PAD, Axis::RightStickY, -1.0);
+        assert!(wait_for(&sink, |actions| !actions.is_empty()), "没有滚动输出");
+        controller.stop();
+
+        let actions = actions(&sink);
+        assert!(
+            actions.iter().all(|action| matches!(action, OutputAction::Scroll { horizontal: false, amount } if *amount > 0)),
+            "{:?}",
+            actions
+        );
+    }
+
+    #[test]
+    fn paused_controller_produces_no_output() {
+        let (source, mut controller, sink) = start(Config::default());
+        controller.set_paused(true);
+        source.registry().set_button(PAD, Button::South, true);
+        source.registry().set_axis(PAD, Axis::LeftStickX, 1.0);
+        thread::sleep(Duration::from_millis(100));
+        controller.stop();
+        assert_eq!(actions(&sink), Vec::new());
+    }
+}
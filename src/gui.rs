@@ -1,198 +1,592 @@
 use eframe::{egui, CreationContext};
 use egui::{Align, Color32, Layout, Slider, FontData, FontFamily};
-use gilrs::{Gilrs};
 use log::{info, error, warn};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
 use std::time::{Instant, Duration};
 
+use crate::app_focus::{AppProfileRule, AppProfileSwitcher};
+use crate::cli::CliArgs;
 use crate::embedded_font;
 use crate::config::Config;
+use crate::connection::{ConnectionInput, ConnectionState, SharedConnection, Transition};
+use crate::controller_model::ControllerModel;
+use crate::event_bus::{self, ActivityStats, BusMessage, ControllerEvent, EventBus};
+use crate::device_manager::{DeviceEvent, DeviceInfo, PadId, ReconnectPolicy, TRACKED_AXES};
+use crate::battery::{BatteryLevel, BatteryMonitor};
+use crate::axis_remap::{axis_display_name, AxisConversion, AxisRemap};
+use crate::haptics::{self, CueDispatcher, HapticCue, HapticsBackend, MockHaptics};
+use crate::input::{InputBackend, InputSource};
+use crate::sdl_mapping::{self, MappingWizard};
+use crate::session::{self, load_replay, SessionRecorder};
 use crate::gamepad_controller::GamepadController;
+use crate::output::{OutputBackend, RecordingSink};
+use crate::multi_pad::{HandoverArbiter, MultiPadPolicy, PadCoordination, PadRole};
+use crate::presets::{self, PRESETS};
+use crate::profile::ProfileStore;
+use crate::config_format::{self, ConfigFormat};
+use crate::config_watch::ConfigWatcher;
+use crate::config_backup;
+use crate::validation::{self, ValidationIssue};
 
-/// 获取按钮显示名称
-fn button_display_name(button_name: &str) -> &str {
-    match button_name {
-        "South" => "A/南按钮",
-        "East" => "B/东按钮",
-        "North" => "Y/北按钮",
-        "West" => "X/西按钮",
-        "LeftTrigger" => "左扳机",
-        "RightTrigger" => "右扳机",
-        "LeftThumb" => "左摇杆按下",
-        "RightThumb" => "右摇杆按下",
-        "Start" => "开始按钮",
-        "Select" => "选择按钮",
-        "Mode" => "模式按钮",
-        "DPadUp" => "十字键上",
-        "DPadDown" => "十字键下",
-        "DPadLeft" => "十字键左",
-        "DPadRight" => "十字键右",
-        _ => "未知按钮",
-    }
+/// 获取按钮显示名称，已识别手柄型号时面键显示该型号的印刷标签
+fn button_display_name(model: Option<ControllerModel>, button_name: &str) -> String {
+    let direction = match button_name {
+        "South" => "南按钮",
+        "East" => "东按钮",
+        "North" => "北按钮",
+        "West" => "西按钮",
+        "LeftTrigger" => return "左扳机".to_string(),
+        "RightTrigger" => return "右扳机".to_string(),
+        "LeftTrigger2" => return "左扳机2".to_string(),
+        "RightTrigger2" => return "右扳机2".to_string(),
+        "LeftThumb" => return "左摇杆按下".to_string(),
+        "RightThumb" => return "右摇杆按下".to_string(),
+        "Start" => return "开始按钮".to_string(),
+        "Select" => return "选择按钮".to_string(),
+        "Mode" => return "模式按钮".to_string(),
+        "DPadUp" => return "十字键上".to_string(),
+        "DPadDown" => return "十字键下".to_string(),
+        "DPadLeft" => return "十字键左".to_string(),
+        "DPadRight" => return "十字键右".to_string(),
+        "" => return "未绑定".to_string(),
+        _ => return "未知按钮".to_string(),
+    };
+    
+    // 未识别型号时沿用 Xbox 的标签
+    let label = model
+        .and_then(|model| model.face_button_label(button_name))
+        .or_else(|| ControllerModel::Xbox.face_button_label(button_name))
+        .unwrap_or("");
+    format!("{}/{}", label, direction)
 }
 
 /// 获取按钮选项列表
-fn get_button_options() -> Vec<(String, &'static str)> {
-    vec![
-        ("South".to_string(), "A/南按钮"),
-        ("East".to_string(), "B/东按钮"),
-        ("North".to_string(), "Y/北按钮"),
-        ("West".to_string(), "X/西按钮"),
-        ("LeftTrigger".to_string(), "左扳机"),
-        ("RightTrigger".to_string(), "右扳机"),
-        ("LeftThumb".to_string(), "左摇杆按下"),
-        ("RightThumb".to_string(), "右摇杆按下")
+fn get_button_options(model: Option<ControllerModel>) -> Vec<(String, String)> {
+    [
+        "South", "East", "North", "West",
+        "LeftTrigger", "RightTrigger", "LeftThumb", "RightThumb",
+        "Start", "Select",
+        "DPadUp", "DPadDown", "DPadLeft", "DPadRight",
+        "",
     ]
+    .iter()
+    .map(|name| (name.to_string(), button_display_name(model, name)))
+    .collect()
+}
+
+/// 显示按钮映射下拉框
+fn button_selector(ui: &mut egui::Ui, model: Option<ControllerModel>, id: &str, binding: &mut String) {
+    egui::ComboBox::from_id_source(id)
+        .width(180.0)
+        .selected_text(button_display_name(model, binding))
+        .show_ui(ui, |ui| {
+            for (name, display) in get_button_options(model) {
+                ui.selectable_value(binding, name, display);
+            }
+        });
+}
+
+/// 正在运行的手柄
+struct ActivePad {
+    // 控制器当前绑定的手柄，重新连接或切换手柄后会更新
+    device: DeviceInfo,
+    controller: Arc<Mutex<GamepadController>>,
+    // 试运行时记录输出的输出端
+    recording: Option<RecordingSink>,
 }
 
 /// GUI应用程序状态
 pub struct GamepadMouseApp {
+    // 配置档案库，config 为当前激活档案的编辑副本
+    profiles: ProfileStore,
     config: Config,
-    controller: Option<Arc<Mutex<GamepadController>>>,
+    // 档案管理输入框和提示信息
+    profile_name_input: String,
+    profile_message: Option<(String, Color32)>,
+    // 导入档案库的文件路径
+    import_path_input: String,
+    // 预设库中选中的预设
+    selected_preset: usize,
+    // 按前台应用自动切换档案，当前平台不支持时为 None
+    app_switcher: Option<AppProfileSwitcher>,
+    // 共享的手柄设备管理服务及其事件订阅
+    devices: Option<Arc<dyn InputSource>>,
+    device_events: Option<Receiver<DeviceEvent>>,
+    device_list: Vec<DeviceInfo>,
+    // 正在运行的手柄，第一个为主手柄
+    pads: Vec<ActivePad>,
     active: bool,
     gamepad_name: String,
     status_message: String,
     status_color: Color32,
+    // 最近一次状态变化，鼠标悬停在状态上时显示
+    status_detail: String,
     show_help: bool,
     tray_tooltip: String,
     // 可用的手柄列表
-    available_gamepads: Vec<(gilrs::GamepadId, String)>,
+    available_gamepads: Vec<(PadId, String)>,
     selected_gamepad_index: usize,
     // 扫描计时器，用于定期检查手柄连接状态
     last_scan_time: Instant,
     // 手柄选择更新标志
     selected_gamepad_changed: Option<usize>,
-    // 最后一次连接尝试时间，防止频繁重试
-    last_connection_attempt: Instant,
-    // 连接重试计数器
-    connection_retry_count: usize,
-    // 上次错误消息，避免重复记录相同错误
-    last_error_message: Option<String>,
+    // 连接状态机及其推送的状态变化
+    connection: SharedConnection,
+    transitions: Receiver<Transition>,
+    // 控制器事件总线，以及界面的活动记录和统计
+    event_bus: Arc<EventBus>,
+    activity_events: Receiver<BusMessage>,
+    activity_feed: VecDeque<BusMessage>,
+    activity_stats: ActivityStats,
+    // 试运行：只记录输出，不移动真实光标，下次启动手柄时生效
+    dry_run: bool,
+    // 正在进行的录制，以及命令行指定的保存路径
+    recorder: Option<SessionRecorder>,
+    record_path: Option<PathBuf>,
+    // 正在回放的录制文件
+    replay: Option<(Arc<dyn InputSource>, String)>,
+    session_files: Vec<PathBuf>,
+    session_message: Option<(String, Color32)>,
+    // 映射向导及其结果
+    mapping_wizard: Option<MappingWizard>,
+    mapping_device_index: usize,
+    mapping_message: Option<(String, Color32)>,
+    mapping_output: Option<String>,
+    // 震动提示，试运行时只记录不震动
+    haptics: Arc<Mutex<CueDispatcher>>,
+    haptics_mock: Option<MockHaptics>,
+    // 低电量警告
+    battery_monitor: BatteryMonitor,
+    battery_warning: Option<String>,
+    // 配置检查发现的问题
+    config_issues: Vec<ValidationIssue>,
+    // 配置文件在外部修改后自动重新加载
+    config_watcher: ConfigWatcher,
+    // 命令行指定的手柄，只使用匹配的手柄；命令行指定的档案见 `ProfileStore::has_active_override`
+    device_filter: Option<String>,
 }
 
+// 活动记录保留的条数
+const ACTIVITY_FEED_LEN: usize = 50;
+// 试运行时每个手柄保留的输出记录条数
+const DRY_RUN_LOG_LEN: usize = 200;
+
 impl GamepadMouseApp {
-    /// 创建新的应用程序实例
-    pub fn new(cc: &CreationContext) -> Self {
-        // 加载配置
-        let config = Config::load();
-        
+    /// 创建新的应用程序实例，`profiles` 为启动时加载的配置档案
+    pub fn new(cc: &CreationContext, cli: CliArgs, profiles: ProfileStore, config_issues: Vec<ValidationIssue>) -> Self {
         // 设置自定义字体
         setup_custom_fonts(&cc.egui_ctx);
         
-        Self {
+        Self::without_window(cli, profiles, config_issues)
+    }
+    
+    /// 创建不显示窗口的应用程序实例，后台运行时通过 `tick` 驱动
+    pub fn without_window(cli: CliArgs, profiles: ProfileStore, config_issues: Vec<ValidationIssue>) -> Self {
+        let config = profiles.active_config().clone();
+        let (connection, transitions) = SharedConnection::new(profiles.connection_backoff);
+        let event_bus = EventBus::new();
+        let activity_events = event_bus.subscribe();
+        event_bus::spawn_log_subscriber(&event_bus);
+        if cli.events_json {
+            event_bus::spawn_json_subscriber(&event_bus);
+        }
+        let haptics = Arc::new(Mutex::new(CueDispatcher::new(profiles.haptics.clone())));
+        haptics::spawn_cue_subscriber(&event_bus, haptics.clone());
+        if let Some(selector) = &cli.device {
+            info!("只使用与 \"{}\" 匹配的手柄", selector);
+        }
+        
+        let mut app = Self {
+            profiles,
             config,
-            controller: None,
+            profile_name_input: String::new(),
+            profile_message: None,
+            import_path_input: String::new(),
+            selected_preset: 0,
+            app_switcher: AppProfileSwitcher::for_current_platform(),
+            devices: None,
+            device_events: None,
+            device_list: Vec::new(),
+            pads: Vec::new(),
             active: false,
             gamepad_name: "无手柄连接".to_string(),
-            status_message: "未启动".to_string(),
+            status_message: connection.message(),
             status_color: Color32::GRAY,
+            status_detail: String::new(),
             show_help: false,
             tray_tooltip: "游戏手柄鼠标控制器 - 未启动".to_string(),
             available_gamepads: Vec::new(),
             selected_gamepad_index: 0,
             last_scan_time: Instant::now(),
             selected_gamepad_changed: None,
-            last_connection_attempt: Instant::now().checked_sub(Duration::from_secs(10)).unwrap_or(Instant::now()),
-            connection_retry_count: 0,
-            last_error_message: None,
+            connection,
+            transitions,
+            event_bus,
+            activity_events,
+            activity_feed: VecDeque::new(),
+            activity_stats: ActivityStats::default(),
+            dry_run: false,
+            recorder: None,
+            record_path: None,
+            replay: None,
+            session_files: session::list_sessions(),
+            session_message: None,
+            mapping_wizard: None,
+            mapping_device_index: 0,
+            mapping_message: None,
+            mapping_output: None,
+            haptics,
+            haptics_mock: None,
+            battery_monitor: BatteryMonitor::default(),
+            battery_warning: None,
+            config_issues,
+            config_watcher: ConfigWatcher::new(),
+            device_filter: cli.device,
+        };
+        app.ensure_device_manager();
+        
+        // 命令行指定的录制和回放
+        if let Some(path) = cli.session.replay {
+            app.start_replay(&path);
+        }
+        if let Some(path) = cli.session.record {
+            app.start_recording(Some(path));
         }
+        app
     }
 
-    /// 启动手柄控制器
-    pub fn start_controller(&mut self) {
-        info!("尝试启动控制器");
-        self.connection_retry_count += 1;
-        
-        // 尝试初始化Gilrs
-        match Gilrs::new() {
-            Ok(gilrs) => {
-                // 检查是否有手柄连接
-                let mut found_gamepad = false;
-                
-                for (id, gamepad) in gilrs.gamepads() {
-                    let name = gamepad.name().to_string();
-                    info!("发现手柄: {} 已连接", name);
-                    
-                    // 创建控制器实例
-                    let controller = GamepadController::new(
-                        gilrs,
-                        id,
-                        self.config.clone(),
-                    );
-                    
-                    // 保存控制器引用
-                    self.controller = Some(Arc::new(Mutex::new(controller)));
-                    self.gamepad_name = name;
-                    self.status_message = "已连接，控制器运行中".to_string();
-                    self.status_color = Color32::GREEN;
-                    self.active = true;
-                    self.tray_tooltip = format!("游戏手柄鼠标控制器 - {}", self.gamepad_name);
-                    self.connection_retry_count = 0; // 重置重试计数
-                    found_gamepad = true;
-                    break;
-                }
-                
-                if !found_gamepad {
-                    self.status_message = "未找到连接的手柄".to_string();
-                    self.status_color = Color32::RED;
-                    self.active = false;
-                    warn!("未找到连接的手柄");
-                }
-            },
-            Err(err) => {
-                let error_message = format!("无法初始化手柄: {}", err);
-                
-                // 只有当错误消息不同时才记录
-                if self.last_error_message.as_ref() != Some(&error_message) {
-                    error!("{}", error_message);
-                    self.last_error_message = Some(error_message.clone());
-                }
-                
-                self.status_message = error_message;
-                self.status_color = Color32::RED;
-                self.active = false;
-            }
-        }
+    /// 处理手柄、配置文件和事件，窗口每帧调用一次，后台运行时定期调用
+    pub fn tick(&mut self) {
+        // 更新控制器状态
+        self.update_controller();
         
-        // 更新最后连接尝试时间
-        self.last_connection_attempt = Instant::now();
+        // 配置文件在外部修改时重新加载
+        self.reload_config_if_changed();
+        
+        // 扫描并更新可用的手柄列表
+        self.scan_gamepads();
+        
+        // 同步手柄上记录的点击位置
+        self.sync_saved_click_position();
+        
+        // 检查手柄电量
+        self.check_batteries();
+        
+        // 接收控制器事件
+        self.receive_activity();
+        
+        // 按前台应用切换档案
+        self.update_app_profile();
+    }
+    
+    /// 回放是否已经结束，没有正在回放的文件（包括加载失败）时也返回 true
+    pub fn replay_finished(&self) -> bool {
+        self.replay.as_ref().is_none_or(|(source, _)| source.is_finished())
+    }
+    
+    /// 命令行指定 `--device` 时只保留匹配的手柄
+    fn filter_devices(&self, devices: Vec<DeviceInfo>) -> Vec<DeviceInfo> {
+        match &self.device_filter {
+            Some(selector) => devices.into_iter().filter(|device| device.matches(selector)).collect(),
+            None => devices,
+        }
     }
     
-    /// 停止手柄控制器
+    /// 停止所有手柄控制器，之后不再自动连接
     pub fn stop_controller(&mut self) {
+        self.stop_all_pads();
+        self.connection.handle(ConnectionInput::Stop, Instant::now());
+    }
+    
+    /// 停止所有控制线程
+    fn stop_all_pads(&mut self) {
         if self.active {
             info!("停止控制器");
-            if let Some(controller) = &self.controller {
-                match controller.lock() {
-                    Ok(mut controller) => {
-                        controller.stop();
-                    },
-                    Err(e) => {
-                        error!("停止控制器时获取锁失败: {}", e);
-                    }
-                }
+            for pad in self.pads.drain(..) {
+                stop_pad_controller(&pad);
             }
-            
-            self.controller = None;
             self.active = false;
-            self.status_message = "已停止".to_string();
-            self.status_color = Color32::GRAY;
-            self.tray_tooltip = "游戏手柄鼠标控制器 - 已停止".to_string();
+        }
+    }
+    
+    /// 暂停或恢复所有手柄的输出
+    fn set_paused(&mut self, paused: bool) {
+        for pad in &self.pads {
+            if let Ok(controller) = pad.controller.lock() {
+                controller.set_paused(paused);
+            }
+        }
+        let input = if paused { ConnectionInput::Suspend } else { ConnectionInput::Resume };
+        self.connection.handle(input, Instant::now());
+        self.publish_to_pads(if paused { ControllerEvent::Paused } else { ControllerEvent::Resumed });
+    }
+    
+    /// 手柄的电量，没有输入源时为未知
+    fn battery_of(&self, id: PadId) -> BatteryLevel {
+        self.devices.as_ref()
+            .and_then(|devices| devices.state(id))
+            .map(|state| state.battery)
+            .unwrap_or_default()
+    }
+    
+    /// 运行中的手柄电量低于阈值时发出警告，震动提示由事件总线触发
+    fn check_batteries(&mut self) {
+        let settings = self.profiles.battery;
+        if !settings.warn_enabled {
+            self.battery_warning = None;
+            return;
+        }
+        for pad in &self.pads {
+            let level = self.battery_of(pad.device.id);
+            if let Some(percent) = self.battery_monitor.check(pad.device.id, level, settings.threshold) {
+                let message = format!("手柄 {} 电量不足（{}%），请及时充电", pad.device.name, percent);
+                warn!("{}", message);
+                self.battery_warning = Some(message);
+                self.event_bus.publish(pad.device.id, &pad.device.name, ControllerEvent::BatteryLow(percent));
+            }
+        }
+        // 所有手柄都不再低电量时清除警告
+        if self.pads.iter().all(|pad| !self.battery_of(pad.device.id).is_low(settings.threshold)) {
+            self.battery_warning = None;
+        }
+    }
+    
+    /// 以每个运行中手柄的名义发布事件
+    fn publish_to_pads(&self, event: ControllerEvent) {
+        for pad in &self.pads {
+            self.event_bus.publish(pad.device.id, &pad.device.name, event.clone());
+        }
+    }
+    
+    /// 按当前输入源和试运行设置更换震动后端
+    fn update_haptics_backend(&mut self) {
+        let backend: Option<Box<dyn HapticsBackend>> = if self.dry_run {
+            let mock = MockHaptics::new();
+            self.haptics_mock = Some(mock.clone());
+            Some(Box::new(mock))
+        } else {
+            self.haptics_mock = None;
+            self.devices.clone().map(|devices| Box::new(devices) as Box<dyn HapticsBackend>)
+        };
+        if let Ok(mut haptics) = self.haptics.lock() {
+            haptics.set_backend(backend);
+        }
+    }
+    
+    /// 开始录制当前输入源的输入，`path` 为空时保存到录制目录
+    fn start_recording(&mut self, path: Option<PathBuf>) {
+        let Some(devices) = self.ensure_device_manager() else {
+            self.session_message = Some(("设备管理服务未启动，无法录制".to_string(), Color32::RED));
+            return;
+        };
+        self.recorder = Some(SessionRecorder::start(devices));
+        self.record_path = path;
+        self.session_message = Some(("正在录制".to_string(), Color32::YELLOW));
+    }
+    
+    /// 停止录制并保存
+    fn stop_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        let recorded = recorder.stop();
+        let path = self.record_path.take().unwrap_or_else(session::new_session_path);
+        self.session_message = Some(match recorded.save(&path) {
+            Ok(()) => (format!("已保存录制: {}", path.display()), Color32::GREEN),
+            Err(e) => (e, Color32::RED),
+        });
+        self.session_files = session::list_sessions();
+    }
+    
+    /// 用录制文件替换当前输入源，控制器按当前档案处理回放的输入
+    fn start_replay(&mut self, path: &std::path::Path) {
+        let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let loaded = match load_replay(path) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                error!("{}", e);
+                self.session_message = Some((e, Color32::RED));
+                return;
+            }
+        };
+        self.stop_recording();
+        self.stop_all_pads();
+        if let Some((previous, _)) = self.replay.take() {
+            previous.stop();
+        }
+        
+        info!("开始回放录制文件: {:?}", path);
+        let (source, message) = loaded.start(&name);
+        self.switch_input_source(source.clone());
+        self.replay = Some((source, name));
+        self.session_message = Some((message, Color32::YELLOW));
+    }
+    
+    /// 结束回放，恢复真实的手柄
+    fn stop_replay(&mut self) {
+        let Some((source, _)) = self.replay.take() else {
+            return;
+        };
+        self.stop_all_pads();
+        source.stop();
+        self.restart_input_source();
+        self.session_message = Some(("回放已结束".to_string(), Color32::GRAY));
+    }
+    
+    /// 按配置重新启动输入源，切换输入后端或修改 evdev 映射后调用
+    fn restart_input_source(&mut self) {
+        self.stop_all_pads();
+        self.mapping_wizard = None;
+        if let Some(devices) = self.devices.take() {
+            devices.stop();
+        }
+        self.device_events = None;
+        self.device_list.clear();
+        self.available_gamepads.clear();
+        self.ensure_device_manager();
+        self.connection.handle(ConnectionInput::Scan, Instant::now());
+    }
+    
+    /// 切换输入源并重新查找手柄
+    fn switch_input_source(&mut self, source: Arc<dyn InputSource>) {
+        self.device_events = Some(source.subscribe());
+        self.device_list = self.filter_devices(source.devices());
+        self.available_gamepads.clear();
+        self.selected_gamepad_index = 0;
+        self.devices = Some(source);
+        self.update_haptics_backend();
+        self.connection.handle(ConnectionInput::Scan, Instant::now());
+    }
+    
+    /// 接收控制器事件，更新活动记录和统计
+    fn receive_activity(&mut self) {
+        while let Ok(message) = self.activity_events.try_recv() {
+            self.activity_stats.record(&message.event);
+            if message.event.is_high_frequency() {
+                continue;
+            }
+            if self.activity_feed.len() >= ACTIVITY_FEED_LEN {
+                self.activity_feed.pop_back();
+            }
+            self.activity_feed.push_front(message);
+        }
+    }
+    
+    /// 接收状态机推送的状态变化并更新状态栏
+    fn receive_transitions(&mut self) {
+        while let Ok(transition) = self.transitions.try_recv() {
+            self.status_color = match transition.to {
+                ConnectionState::Active => Color32::GREEN,
+                ConnectionState::Connecting | ConnectionState::Reconnecting | ConnectionState::Suspended => Color32::YELLOW,
+                ConnectionState::Failed => Color32::RED,
+                ConnectionState::Scanning if self.connection.message() != "正在查找手柄" => Color32::RED,
+                ConnectionState::Idle | ConnectionState::Scanning => Color32::GRAY,
+            };
+            self.status_detail = format!("{} → {}", transition.from.display_name(), transition.to.display_name());
+            self.status_message = transition.message;
+            self.tray_tooltip = match transition.to {
+                ConnectionState::Active => format!("游戏手柄鼠标控制器 - {}", self.gamepad_name),
+                state => format!("游戏手柄鼠标控制器 - {}", state.display_name()),
+            };
+        }
+    }
+    
+    /// 停止单个手柄控制器
+    fn stop_pad(&mut self, index: usize) {
+        if index >= self.pads.len() {
+            return;
+        }
+        if self.pads.len() == 1 {
+            self.stop_controller();
+            return;
+        }
+        let pad = self.pads.remove(index);
+        info!("停止手柄: {}", pad.device.name);
+        stop_pad_controller(&pad);
+        self.gamepad_name = self.pads[0].device.name.clone();
+        self.coordinate_pads();
+    }
+    
+    /// 主手柄的型号
+    fn primary_model(&self) -> Option<ControllerModel> {
+        self.pads.first().map(|pad| pad.device.detected.model)
+    }
+    
+    /// 手柄使用的档案名称，None 表示跟随当前激活档案
+    ///
+    /// 主手柄始终跟随激活档案；其他手柄优先使用单独指定的档案，其次使用型号默认档案。
+    fn pad_profile(&self, index: usize) -> Option<String> {
+        let pad = self.pads.get(index)?;
+        if index == 0 {
+            return None;
+        }
+        self.profiles.pad_profile(&pad.device.key())
+            .or_else(|| self.profiles.model_profiles.get(&pad.device.detected.model))
+            .filter(|profile| **profile != self.profiles.active_profile)
+            .cloned()
+    }
+    
+    /// 手柄当前应使用的配置
+    fn config_for_pad(&self, index: usize) -> Config {
+        self.pad_profile(index)
+            .and_then(|profile| self.profiles.config_of(&profile).cloned())
+            .unwrap_or_else(|| self.config.clone())
+    }
+    
+    /// 根据多手柄策略为每个手柄分配角色和控制权
+    fn coordinate_pads(&self) {
+        let policy = self.profiles.multi_pad_policy;
+        let mut arbiter = HandoverArbiter::new();
+        for pad in &self.pads {
+            let role = match policy {
+                MultiPadPolicy::SeparateRoles => self.profiles.pad_role(&pad.device.key()),
+                _ => PadRole::Pointer,
+            };
+            let handover = if policy == MultiPadPolicy::Handover && self.pads.len() > 1 {
+                Some(arbiter.token())
+            } else {
+                None
+            };
+            match pad.controller.lock() {
+                Ok(controller) => controller.set_coordination(PadCoordination { role, handover }),
+                Err(e) => error!("设置手柄角色时获取锁失败: {}", e),
+            }
+        }
+    }
+    
+    /// 把每个手柄的轴重映射交给控制器
+    fn apply_axis_remaps(&self) {
+        for pad in &self.pads {
+            let remap = self.profiles.axis_remap(&pad.device.key());
+            match pad.controller.lock() {
+                Ok(controller) => controller.set_axis_remap(remap),
+                Err(e) => error!("设置轴映射时获取锁失败: {}", e),
+            }
         }
     }
     
     /// 保存当前配置
-    fn save_config(&self) {
-        if let Err(e) = self.config.save() {
+    fn save_config(&mut self) {
+        *self.profiles.active_config_mut() = self.config.clone();
+        if let Err(e) = self.profiles.save() {
             error!("保存配置失败: {}", e);
         }
+        self.config_issues = validation::validate_store(&self.profiles);
         
         // 如果控制器正在运行，更新配置
-        if let Some(controller) = &self.controller {
-            match controller.lock() {
+        self.apply_config_to_controller();
+    }
+    
+    /// 将当前配置下发给运行中的控制器
+    fn apply_config_to_controller(&self) {
+        for (index, pad) in self.pads.iter().enumerate() {
+            match pad.controller.lock() {
                 Ok(mut controller) => {
-                    controller.update_config(self.config.clone());
-                    info!("已更新控制器配置");
+                    controller.update_config(self.config_for_pad(index));
+                    info!("已更新控制器配置: {}", pad.device.name);
                 },
                 Err(e) => {
                     error!("更新控制器配置时获取锁失败: {}", e);
@@ -201,11 +595,250 @@ impl GamepadMouseApp {
         }
     }
     
+    /// 切换激活的配置档案，未保存的修改会保留在原档案中
+    fn switch_profile(&mut self, name: &str) {
+        *self.profiles.active_config_mut() = self.config.clone();
+        if let Err(e) = self.profiles.set_active(name) {
+            self.profile_message = Some((e, Color32::RED));
+            return;
+        }
+        self.on_active_profile_changed();
+    }
+    
+    /// 激活档案变化后刷新编辑副本、控制器和配置文件
+    fn on_active_profile_changed(&mut self) {
+        self.config = self.profiles.active_config().clone();
+        self.apply_config_to_controller();
+        if let Err(e) = self.profiles.save() {
+            error!("保存配置失败: {}", e);
+        }
+        self.profile_message = Some((format!("当前档案: {}", self.profiles.active_profile), Color32::GREEN));
+        self.publish_to_pads(ControllerEvent::ProfileSwitched(self.profiles.active_profile.clone()));
+    }
+    
+    /// 切换到手柄型号对应的默认档案
+    ///
+    /// 任天堂布局的手柄首次连接且没有指定档案时，以型号默认配置创建档案并记为该型号的默认档案；
+    /// 其他型号的默认配置与通用默认配置相同，继续使用当前档案。
+    fn apply_model_profile(&mut self, model: ControllerModel) {
+        match self.profiles.model_profiles.get(&model).cloned() {
+            Some(profile) if profile != self.profiles.active_profile => {
+                info!("{} 手柄已连接，切换到默认档案 {}", model.display_name(), profile);
+                self.switch_profile(&profile);
+            }
+            Some(_) => {}
+            None if model.has_nintendo_layout() => {
+                info!("{} 手柄首次连接，创建该型号的默认档案", model.display_name());
+                self.create_model_profile(model);
+            }
+            None => {}
+        }
+    }
+    
+    /// 以型号默认配置创建档案，并设为该型号的默认档案
+    fn create_model_profile(&mut self, model: ControllerModel) {
+        let name = self.profiles.unique_name(model.display_name());
+        *self.profiles.active_config_mut() = self.config.clone();
+        match self.profiles.add(&name, model.default_config()) {
+            Ok(()) => {
+                self.profiles.model_profiles.insert(model, name.clone());
+                self.switch_profile(&name);
+            }
+            Err(e) => self.profile_message = Some((e, Color32::RED)),
+        }
+    }
+    
+    /// 根据前台应用自动切换档案，命令行指定了档案时不切换
+    fn update_app_profile(&mut self) {
+        if !self.profiles.auto_switch_enabled || self.profiles.has_active_override() {
+            return;
+        }
+        
+        if let Some(switcher) = &mut self.app_switcher
+            && let Some(profile) = switcher.poll(&self.profiles.app_rules)
+            && profile != self.profiles.active_profile
+        {
+            self.switch_profile(&profile);
+        }
+    }
+    
+    /// 配置文件在外部修改后重新加载并下发给运行中的控制器，
+    /// 新文件无法解析时继续使用之前的配置并在“配置检查”中显示错误
+    ///
+    /// 当前档案未保存的修改在文件中该档案没有变化时保留，否则被文件中的设置替换并提示用户。
+    fn reload_config_if_changed(&mut self) {
+        let Some(result) = self.config_watcher.poll(&self.profiles) else {
+            return;
+        };
+        match result {
+            Ok((mut store, issues)) => {
+                // 命令行临时指定的档案在重新加载后继续生效
+                if self.profiles.has_active_override()
+                    && let Err(e) = store.set_active_override(&self.profiles.active_profile)
+                {
+                    warn!("{}，使用配置文件中的激活档案", e);
+                }
+                let profile_changed = store.active_profile != self.profiles.active_profile;
+                let unsaved = self.config != *self.profiles.active_config();
+                let keep_edits = unsaved && !profile_changed && store.active_config() == self.profiles.active_config();
+                self.profiles = store;
+                if !keep_edits {
+                    self.config = self.profiles.active_config().clone();
+                }
+                self.config_issues = issues;
+                self.apply_config_to_controller();
+                self.apply_axis_remaps();
+                self.coordinate_pads();
+                self.connection.set_backoff(self.profiles.connection_backoff);
+                match self.haptics.lock() {
+                    Ok(mut haptics) => haptics.set_settings(self.profiles.haptics.clone()),
+                    Err(e) => error!("更新震动提示设置时获取锁失败: {}", e),
+                }
+                self.profile_message = Some(if keep_edits {
+                    ("配置文件已在外部修改，已重新加载，当前档案未保存的修改已保留".to_string(), Color32::GREEN)
+                } else if unsaved {
+                    warn!("配置文件已在外部修改，当前档案未保存的修改已丢弃");
+                    ("配置文件已在外部修改，已重新加载，当前档案未保存的修改已被文件中的设置替换".to_string(), Color32::YELLOW)
+                } else {
+                    ("配置文件已在外部修改，已重新加载".to_string(), Color32::GREEN)
+                });
+                if profile_changed {
+                    self.publish_to_pads(ControllerEvent::ProfileSwitched(self.profiles.active_profile.clone()));
+                }
+            }
+            Err(e) => {
+                self.config_issues = vec![ValidationIssue::error(
+                    "",
+                    format!("重新加载配置文件失败，继续使用之前的配置: {}", e),
+                )];
+            }
+        }
+    }
+    
+    /// 将选中的预设应用为新档案并激活
+    fn apply_preset_as_profile(&mut self) {
+        let preset = &PRESETS[self.selected_preset];
+        let config = match preset.config() {
+            Ok(config) => config,
+            Err(e) => {
+                error!("{}", e);
+                self.profile_message = Some((e, Color32::RED));
+                return;
+            }
+        };
+        
+        let base_name = match self.profile_name_input.trim() {
+            "" => preset.name.to_string(),
+            name => name.to_string(),
+        };
+        let name = self.profiles.unique_name(&base_name);
+        *self.profiles.active_config_mut() = self.config.clone();
+        match self.profiles.add(&name, config) {
+            Ok(()) => {
+                self.profile_name_input.clear();
+                self.switch_profile(&name);
+            }
+            Err(e) => self.profile_message = Some((e, Color32::RED)),
+        }
+    }
+    
+    /// 导出配置并显示结果
+    fn export_config(&mut self, name: &str, config: &Config) {
+        self.profile_message = Some(match presets::export_config(name, config) {
+            Ok(path) => (format!("已导出到 {}", path.display()), Color32::GREEN),
+            Err(e) => {
+                error!("{}", e);
+                (e, Color32::RED)
+            }
+        });
+    }
+    
+    /// 导出整个档案库
+    fn export_store(&mut self, format: ConfigFormat) {
+        *self.profiles.active_config_mut() = self.config.clone();
+        self.profile_message = Some(match config_format::export_store(&self.profiles.persisted(), format) {
+            Ok(path) => (format!("已导出到 {}", path.display()), Color32::GREEN),
+            Err(e) => {
+                error!("{}", e);
+                (e, Color32::RED)
+            }
+        });
+    }
+    
+    /// 从 JSON 或 TOML 文件导入档案库，替换当前的所有档案
+    fn import_store(&mut self) {
+        let path = PathBuf::from(self.import_path_input.trim());
+        if self.replace_store_from(&path, "import", "导入") {
+            self.profile_message = Some((format!("已从 {} 导入 {} 个档案", path.display(), self.profiles.profiles.len()), Color32::GREEN));
+        }
+    }
+    
+    /// 恢复到一个历史备份
+    fn restore_backup(&mut self, path: &Path) {
+        if self.replace_store_from(path, "restore", "恢复") {
+            self.profile_message = Some((format!("已恢复到备份 {}", path.display()), Color32::GREEN));
+        }
+    }
+    
+    /// 用文件中的档案库替换当前的所有档案，替换前先备份原配置文件，成功时返回 true
+    fn replace_store_from(&mut self, path: &Path, backup_reason: &str, action: &str) -> bool {
+        let (store, issues) = match ProfileStore::import(path) {
+            Ok(result) => result,
+            Err(e) => {
+                error!("{}配置失败: {}", action, e);
+                self.profile_message = Some((format!("{}失败: {}", action, e), Color32::RED));
+                return false;
+            }
+        };
+        let config_path = PathBuf::from(Config::get_config_path());
+        if config_path.exists()
+            && let Err(e) = config_backup::backup_file(&config_path, backup_reason)
+        {
+            self.profile_message = Some((format!("{}，已取消{}", e, action), Color32::RED));
+            return false;
+        }
+        self.profiles = store;
+        self.config_issues = issues;
+        self.on_active_profile_changed();
+        true
+    }
+    
+    /// 处理档案管理操作的结果
+    fn handle_profile_result(&mut self, result: Result<(), String>, success: String) {
+        match result {
+            Ok(()) => {
+                self.profile_name_input.clear();
+                if let Err(e) = self.profiles.save() {
+                    error!("保存配置失败: {}", e);
+                }
+                self.profile_message = Some((success, Color32::GREEN));
+            }
+            Err(e) => {
+                warn!("配置档案操作失败: {}", e);
+                self.profile_message = Some((e, Color32::RED));
+            }
+        }
+    }
+    
     /// 更新控制器状态
     fn update_controller(&mut self) {
+        // 处理手柄上的档案切换请求
+        let cycle_requested = self.pads.iter().fold(false, |requested, pad| {
+            let taken = pad.controller.lock().map(|c| c.take_profile_cycle_request()).unwrap_or(false);
+            taken || requested
+        });
+        if cycle_requested && self.profiles.has_active_override() {
+            info!("本次运行使用命令行指定的档案，忽略手柄上的档案切换");
+            self.profile_message = Some(("命令行指定了档案，手柄上的档案切换已忽略".to_string(), Color32::YELLOW));
+        } else if cycle_requested {
+            *self.profiles.active_config_mut() = self.config.clone();
+            self.profiles.cycle_next();
+            self.on_active_profile_changed();
+        }
+        
         // 处理手柄选择变更
         if let Some(index) = self.selected_gamepad_changed.take() {
-            if self.active {
+            if self.active && !self.profiles.multi_pad_enabled {
                 info!("手柄选择已变更，停止当前控制器");
                 self.stop_controller();
             }
@@ -215,8 +848,9 @@ impl GamepadMouseApp {
                 info!("已选择新手柄: {} (ID: {:?})", name, id);
                 self.gamepad_name = name.clone();
                 
-                // 当有手柄选择更改且未启动时，自动尝试启动
-                if !self.active && !self.available_gamepads.is_empty() {
+                // 当有手柄选择更改且该手柄未启动时，自动尝试启动
+                let already_running = self.pads.iter().any(|pad| pad.device.id == *id);
+                if !already_running && !self.available_gamepads.is_empty() {
                     info!("检测到手柄选择变更，尝试自动连接到: {}", name);
                     self.start_selected_controller();
                 }
@@ -225,82 +859,114 @@ impl GamepadMouseApp {
             }
         }
 
-        // 添加周期性状态检查
         let now = Instant::now();
-        let check_interval = Duration::from_secs(5);
-        let retry_interval = Duration::from_secs(if self.connection_retry_count > 3 { 15 } else { 5 });
-        
-        static mut LAST_CHECK: Option<Instant> = None;
-        let should_check = unsafe {
-            if let Some(last) = LAST_CHECK {
-                if now.duration_since(last) >= check_interval {
-                    LAST_CHECK = Some(now);
-                    true
-                } else {
-                    false
+        
+        // 重新启动意外停止的控制线程
+        let stopped: Vec<usize> = self.pads.iter().enumerate()
+            .filter(|(_, pad)| !pad.controller.lock().is_ok_and(|controller| controller.is_running()))
+            .map(|(index, _)| index)
+            .collect();
+        let restart: Vec<(PadId, String)> = stopped.into_iter().rev()
+            .map(|index| {
+                let pad = self.pads.remove(index);
+                info!("控制器 {} 已停止运行，需要重新启动", pad.device.name);
+                stop_pad_controller(&pad);
+                (pad.device.id, pad.device.name)
+            })
+            .collect();
+        self.active = !self.pads.is_empty();
+        for (id, name) in restart.into_iter().rev() {
+            self.start_pad(id, &name);
+        }
+        
+        // 断开的手柄按 UUID 找回，原手柄不返回时按策略切换；全部断开时按退避间隔重试
+        let state = self.connection.state();
+        let reconnect_due = match state {
+            ConnectionState::Active | ConnectionState::Suspended => true,
+            ConnectionState::Reconnecting => self.connection.retry_due(now),
+            _ => false,
+        };
+        if reconnect_due {
+            let reconnect_timeout = Duration::from_secs(self.profiles.reconnect_timeout_secs);
+            // 指定了手柄时不切换到其他手柄
+            let policy = match self.device_filter {
+                Some(_) => ReconnectPolicy::WaitForOriginal,
+                None => self.profiles.reconnect_policy,
+            };
+            let mut restored = false;
+            for pad in &self.pads {
+                if let Ok(controller) = pad.controller.lock()
+                    && !controller.is_connected()
+                {
+                    restored |= controller.try_reconnect(policy, reconnect_timeout);
                 }
-            } else {
-                LAST_CHECK = Some(now);
-                true
             }
-        };
-
-        if should_check {
-            // 使用一个布尔值来跟踪是否需要重新启动控制器，而不是在同一作用域内修改controller
-            let mut need_restart = false;
-            
-            // 定期检查控制器状态
-            if let Some(controller) = &self.controller {
-                match controller.lock() {
-                    Ok(controller) => {
-                        // 检查控制器状态
-                        if controller.is_running() && controller.is_connected() {
-                            self.status_message = "已连接，控制器运行中".to_string();
-                            self.status_color = Color32::GREEN;
-                            // 只在状态变化时记录日志
-                            if self.status_color != Color32::GREEN {
-                                info!("控制器状态检查：正常运行中");
-                            }
-                        } else if controller.is_running() && !controller.is_connected() {
-                            info!("控制器运行中但手柄已断开连接，尝试重新连接");
-                            self.status_message = "手柄已断开，尝试重新连接".to_string();
-                            self.status_color = Color32::YELLOW;
-                            
-                            // 由于控制器已断开连接，我们需要重新启动它
-                            need_restart = true;
-                            info!("准备重新启动控制器以尝试恢复连接");
-                        } else {
-                            info!("控制器已停止运行，需要重新启动");
-                            self.status_message = "控制器已停止，尝试重新启动".to_string();
-                            self.status_color = Color32::YELLOW;
-                            need_restart = true;
-                        }
-                    },
-                    Err(e) => {
-                        error!("无法获取控制器锁: {}", e);
-                        self.status_message = "控制器状态异常".to_string();
-                        self.status_color = Color32::RED;
-                        need_restart = true;
-                    }
+            if state == ConnectionState::Reconnecting {
+                if self.pads.is_empty() && !self.available_gamepads.is_empty() {
+                    self.start_selected_controller();
+                } else if !restored {
+                    self.connection.handle(ConnectionInput::RetryFailed, now);
                 }
-            } else if !self.active && !self.available_gamepads.is_empty() {
-                // 如果没有活动的控制器但有可用的手柄，尝试自动连接
-                let should_retry = now.duration_since(self.last_connection_attempt) >= retry_interval;
-                
-                if should_retry {
-                    info!("尝试自动连接可用的手柄 (重试次数: {})", self.connection_retry_count);
-                    need_restart = true;
+            }
+        }
+        
+        // 超过重试次数后停止控制线程，等待手动启动
+        if self.connection.state() == ConnectionState::Failed && self.active {
+            self.stop_all_pads();
+        }
+        
+        // 查找手柄状态下自动连接
+        if self.connection.state() == ConnectionState::Scanning && self.connection.retry_due(now) {
+            if self.devices.is_none() {
+                self.ensure_device_manager();
+            } else if !self.available_gamepads.is_empty() {
+                if self.selected_gamepad_index >= self.available_gamepads.len() {
+                    self.selected_gamepad_index = 0;
                 }
+                info!("发现可用手柄，尝试自动连接");
+                self.start_selected_controller();
             }
-
-            // 在检查完成后处理重新启动逻辑
-            if need_restart {
-                self.active = false;
-                self.controller = None;
-                
-                // 如果手柄仍然存在，尝试重新启动控制器
-                if !self.available_gamepads.is_empty() {
-                    self.start_selected_controller();
+        }
+        
+        self.refresh_pad_bindings();
+        self.receive_transitions();
+    }
+    
+    /// 同步控制器当前绑定的手柄，切换到其他手柄后重新下发配置和角色
+    fn refresh_pad_bindings(&mut self) {
+        let mut switched = false;
+        for pad in &mut self.pads {
+            let device = match pad.controller.lock() {
+                Ok(controller) => controller.device(),
+                Err(_) => continue,
+            };
+            if device.key() != pad.device.key() {
+                info!("手柄 {} 已由 {} 接替", pad.device.name, device.name);
+                switched = true;
+            }
+            pad.device = device;
+        }
+        if switched {
+            self.gamepad_name = self.pads[0].device.name.clone();
+            self.tray_tooltip = format!("游戏手柄鼠标控制器 - {}", self.gamepad_name);
+            self.apply_config_to_controller();
+            self.coordinate_pads();
+            self.apply_axis_remaps();
+        }
+    }
+    
+    /// 同步控制线程中记录的点击位置到界面配置
+    fn sync_saved_click_position(&mut self) {
+        for index in 0..self.pads.len() {
+            // 只同步使用当前激活档案的手柄
+            if self.pad_profile(index).is_some() {
+                continue;
+            }
+            if let Ok(controller) = self.pads[index].controller.lock() {
+                let position = controller.saved_click_position();
+                if position.is_some() && position != self.config.saved_click_position {
+                    info!("界面已同步记录的点击位置: {:?}", position);
+                    self.config.saved_click_position = position;
                 }
             }
         }
@@ -314,15 +980,16 @@ impl GamepadMouseApp {
         if self.last_scan_time.elapsed() >= SCAN_INTERVAL {
             self.last_scan_time = Instant::now();
             
-            // 尝试初始化Gilrs
-            if let Ok(gilrs) = Gilrs::new() {
+            // 从设备管理服务接收手柄列表的变化
+            if self.devices.is_some() {
+                self.receive_device_events();
                 let mut new_gamepads = Vec::new();
-                let mut current_id_exists = false;
                 let mut found_new_gamepad = false;
                 
                 // 获取当前已连接的手柄
-                for (id, gamepad) in gilrs.gamepads() {
-                    let name = gamepad.name().to_string();
+                for device in &self.device_list {
+                    let id = device.id;
+                    let name = device.name.clone();
                     
                     // 检查是否是新发现的手柄
                     let is_new = !self.available_gamepads.iter().any(|(existing_id, existing_name)| {
@@ -330,38 +997,21 @@ impl GamepadMouseApp {
                     });
                     
                     if is_new {
-                        info!("发现新的手柄: {} (id: {:?})", name, id);
+                        info!("发现新的手柄: {} (id: {:?}, UUID: {})", name, id, device.uuid_string());
                         found_new_gamepad = true;
                     }
                     
-                    new_gamepads.push((id, name.clone()));
-                    
-                    // 检查当前选择的游戏手柄是否存在
-                    if self.selected_gamepad_index < self.available_gamepads.len() {
-                        if let Some((selected_id, _)) = self.available_gamepads.get(self.selected_gamepad_index) {
-                            if *selected_id == id {
-                                current_id_exists = true;
-                                
-                                // 如果控制器不在运行状态但手柄已连接，尝试自动重连
-                                if !self.active && self.gamepad_name == name {
-                                    info!("检测到之前选择的手柄已重新连接，尝试自动恢复连接");
-                                    // 延迟执行重连操作，避免UI线程阻塞
-                                    self.selected_gamepad_changed = Some(self.selected_gamepad_index);
-                                }
-                            }
-                        }
-                    }
+                    new_gamepads.push((id, name));
                 }
                 
                 // 检查是否有手柄被移除
                 let gamepad_removed = self.available_gamepads.len() > new_gamepads.len();
                 
-                // 如果当前手柄不存在但界面显示仍然活跃，停止控制器
-                if !current_id_exists && self.active {
-                    info!("检测到当前连接的手柄已断开");
-                    self.stop_controller();
-                    self.status_message = "手柄已断开连接".to_string();
-                    self.status_color = Color32::RED;
+                // 已断开的手柄保留控制器，重新连接后按 UUID 和名称自动绑定
+                for pad in &self.pads {
+                    if !new_gamepads.iter().any(|(id, _)| *id == pad.device.id) && gamepad_removed {
+                        info!("检测到手柄已断开，等待重新连接: {}", pad.device.name);
+                    }
                 }
                 
                 // 更新可用的手柄列表
@@ -369,109 +1019,258 @@ impl GamepadMouseApp {
                     info!("更新可用的手柄列表: 从 {} 个手柄到 {} 个手柄", 
                          self.available_gamepads.len(), new_gamepads.len());
                     self.available_gamepads = new_gamepads;
-                    
-                    // 如果没有活动的控制器但有可用的手柄，尝试自动连接第一个
-                    if !self.active && !self.available_gamepads.is_empty() && self.connection_retry_count < 3 {
-                        if self.last_connection_attempt.elapsed() > Duration::from_secs(3) {
-                            info!("发现可用手柄，尝试自动连接");
-                            self.selected_gamepad_index = 0;
-                            self.selected_gamepad_changed = Some(0);
-                        }
+                }
+                
+                // 多手柄模式下自动启动其余可用的手柄
+                if self.profiles.multi_pad_enabled
+                    && matches!(self.connection.state(), ConnectionState::Active | ConnectionState::Suspended)
+                {
+                    let idle = self.idle_gamepads();
+                    for (id, name) in idle {
+                        info!("多手柄模式：自动启动手柄 {}", name);
+                        self.start_pad(id, &name);
                     }
                 }
             }
         }
     }
     
-    /// 获取当前选中的手柄（如果有）
-    fn get_selected_gamepad(&self) -> Option<(gilrs::GamepadId, String)> {
-        self.available_gamepads.get(self.selected_gamepad_index).cloned()
+    /// 获取设备管理服务，尚未启动时尝试启动
+    fn ensure_device_manager(&mut self) -> Option<Arc<dyn InputSource>> {
+        if self.devices.is_none() {
+            match self.profiles.input_backend.start() {
+                Ok(devices) => {
+                    self.device_events = Some(devices.subscribe());
+                    self.device_list = self.filter_devices(devices.devices());
+                    self.devices = Some(devices);
+                    self.update_haptics_backend();
+                },
+                Err(error_message) => {
+                    self.connection.handle(ConnectionInput::ConnectFailed(error_message), Instant::now());
+                }
+            }
+        }
+        self.devices.clone()
     }
     
-    /// 启动选中的手柄控制器
-    pub fn start_selected_controller(&mut self) {
-        // 获取当前选中的手柄
-        if let Some(gamepad) = self.get_selected_gamepad() {
-            info!("正在尝试连接手柄: {} (id: {:?})", gamepad.1, gamepad.0);
-            
-            // 尝试初始化Gilrs
-            match Gilrs::new() {
-                Ok(gilrs) => {
-                    // 检查手柄是否还存在
-                    if gilrs.gamepad(gamepad.0).is_connected() {
-                        info!("手柄已连接，开始初始化控制器");
-                        
-                        // 创建控制器实例并检查是否成功
-                        let controller = GamepadController::new(
-                            gilrs,
-                            gamepad.0,
-                            self.config.clone(),
-                        );
-                        
-                        // 检查控制器是否正常初始化并运行
-                        if controller.is_running() {
-                            // 保存控制器引用
-                            self.controller = Some(Arc::new(Mutex::new(controller)));
-                            self.gamepad_name = gamepad.1.clone();
-                            self.status_message = "已连接，控制器运行中".to_string();
-                            self.status_color = Color32::GREEN;
-                            self.active = true;
-                            self.tray_tooltip = format!("游戏手柄鼠标控制器 - {}", self.gamepad_name);
-                            self.connection_retry_count = 0; // 重置重试计数
-                            info!("手柄控制器启动成功");
-                        } else {
-                            self.status_message = "控制器初始化失败，未能启动".to_string();
-                            self.status_color = Color32::RED;
-                            self.active = false;
-                            info!("控制器初始化成功但未能启动");
+    /// 处理设备管理服务广播的事件，更新手柄列表
+    fn receive_device_events(&mut self) {
+        if let Some(events) = &self.device_events {
+            while let Ok(event) = events.try_recv() {
+                match event {
+                    DeviceEvent::DevicesChanged(list) => self.device_list = self.filter_devices(list),
+                    // 新连接的手柄立即尝试连接，不等待退避间隔
+                    DeviceEvent::Connected(id) => {
+                        info!("手柄 {} 已连接", id);
+                        self.connection.retry_now();
+                    }
+                    DeviceEvent::RawInput(id, input) => {
+                        if let Some(wizard) = &mut self.mapping_wizard
+                            && wizard.device.id == id
+                        {
+                            wizard.feed(input);
                         }
-                    } else {
-                        self.status_message = format!("手柄已断开连接: {}", gamepad.1);
-                        self.status_color = Color32::RED;
-                        self.active = false;
-                        info!("手柄已断开连接: {}", gamepad.1);
                     }
-                },
-                Err(err) => {
-                    self.status_message = format!("无法初始化手柄系统: {}", err);
-                    self.status_color = Color32::RED;
-                    self.active = false;
-                    error!("无法初始化手柄系统: {}", err);
+                    _ => {}
                 }
             }
-        } else {
-            self.status_message = "未选择手柄".to_string();
-            self.status_color = Color32::RED;
-            info!("未选择手柄，无法启动控制器");
         }
-        
-        // 更新最后连接尝试时间
-        self.last_connection_attempt = Instant::now();
     }
     
-    /// 刷新可用的手柄列表
-    pub fn refresh_gamepads(&mut self) {
-        info!("手动刷新可用的手柄列表");
-        
-        // 尝试初始化Gilrs
-        if let Ok(gilrs) = Gilrs::new() {
-            let mut new_gamepads = Vec::new();
-            
-            // 获取当前已连接的手柄
-            for (id, gamepad) in gilrs.gamepads() {
-                let name = gamepad.name().to_string();
-                new_gamepads.push((id, name));
-            }
+    /// 开始为手柄录入映射
+    fn start_mapping_wizard(&mut self, device: DeviceInfo) {
+        let Some(devices) = self.ensure_device_manager() else {
+            return;
+        };
+        // 录入期间停止该手柄的控制，以免按键触发鼠标操作
+        if let Some(index) = self.pads.iter().position(|pad| pad.device.id == device.id) {
+            self.stop_pad(index);
+        }
+        devices.set_raw_capture(true);
+        info!("开始为手柄 {} 录入映射", device.name);
+        self.mapping_wizard = Some(MappingWizard::new(device));
+        self.mapping_message = None;
+        self.mapping_output = None;
+    }
+    
+    /// 结束映射向导，`save` 为 true 时应用并保存录入的映射
+    fn finish_mapping_wizard(&mut self, save: bool) {
+        let Some(wizard) = self.mapping_wizard.take() else {
+            return;
+        };
+        let Some(devices) = self.devices.clone() else {
+            return;
+        };
+        devices.set_raw_capture(false);
+        if !save {
+            return;
+        }
+        let result = devices
+            .apply_mapping(wizard.device.id, wizard.captured(), &wizard.device.name)
+            .and_then(|mapping| sdl_mapping::save_user_mapping(&mapping).map(|()| mapping));
+        match result {
+            Ok(mapping) => {
+                let note = if sdl_mapping::has_hat_mapping(wizard.captured()) { "，十字键的 hat 映射在重新启动后生效" } else { "" };
+                self.mapping_message = Some((
+                    format!("已应用并保存到 {}{}", sdl_mapping::user_mappings_path().display(), note),
+                    Color32::GREEN,
+                ));
+                self.mapping_output = Some(mapping);
+            }
+            Err(e) => {
+                error!("{}", e);
+                self.mapping_message = Some((e, Color32::RED));
+            }
+        }
+    }
+    
+    /// 没有被控制器占用的手柄
+    ///
+    /// 运行中的手柄按 ID 排除，两个相同型号的手柄可以同时启动；
+    /// 与已断开手柄 UUID 和名称相同的手柄留给原控制器重新绑定，不单独启动。
+    fn idle_gamepads(&self) -> Vec<(PadId, String)> {
+        let Some(devices) = &self.devices else {
+            return Vec::new();
+        };
+        self.filter_devices(devices.unclaimed_devices()).into_iter()
+            .filter(|device| !self.pads.iter().any(|pad| {
+                let waiting = pad.device.key() == device.key()
+                    && !pad.controller.lock().is_ok_and(|controller| controller.is_connected());
+                pad.device.id == device.id || waiting
+            }))
+            .map(|device| (device.id, device.name))
+            .collect()
+    }
+    
+    /// 获取当前选中的手柄（如果有）
+    fn get_selected_gamepad(&self) -> Option<(PadId, String)> {
+        self.available_gamepads.get(self.selected_gamepad_index).cloned()
+    }
+    
+    /// 启动选中的手柄控制器
+    pub fn start_selected_controller(&mut self) {
+        self.connection.handle(ConnectionInput::Start, Instant::now());
+        
+        // 获取当前选中的手柄
+        if let Some((id, name)) = self.get_selected_gamepad() {
+            if self.pads.iter().any(|pad| pad.device.id == id) {
+                info!("手柄 {} 已在运行", name);
+            } else {
+                // 单手柄模式下先停止正在运行的手柄
+                if !self.profiles.multi_pad_enabled {
+                    self.stop_controller();
+                }
+                self.start_pad(id, &name);
+            }
+        } else {
+            info!("未选择手柄，无法启动控制器");
+            self.connection.handle(ConnectionInput::ConnectFailed("未选择手柄".to_string()), Instant::now());
+        }
+    }
+    
+    /// 启动指定手柄的控制器，成功时加入运行中的手柄列表
+    fn start_pad(&mut self, id: PadId, name: &str) -> bool {
+        info!("正在尝试连接手柄: {} (id: {:?})", name, id);
+        
+        let Some(devices) = self.ensure_device_manager() else {
+            self.active = !self.pads.is_empty();
+            return false;
+        };
+        
+        // 检查手柄是否还存在
+        let Some(device) = devices.device(id) else {
+            self.active = !self.pads.is_empty();
+            info!("手柄已断开连接: {}", name);
+            self.connection.handle(ConnectionInput::ConnectFailed(format!("手柄已断开连接: {}", name)), Instant::now());
+            return false;
+        };
+        info!("手柄已连接，开始初始化控制器");
+        
+        // 第一个手柄连接时应用该型号的默认档案，命令行指定了档案时除外
+        let detected = device.detected;
+        if self.pads.is_empty() && !self.profiles.has_active_override() {
+            self.apply_model_profile(detected.model);
+        }
+        
+        // 创建控制器实例并检查是否成功
+        let recording = self.dry_run.then(|| RecordingSink::with_limit(DRY_RUN_LOG_LEN));
+        let output = match &recording {
+            Some(sink) => {
+                info!("试运行模式，输出只记录不执行");
+                sink.factory()
+            }
+            None => self.profiles.output_backend.factory(),
+        };
+        let controller = GamepadController::with_output(
+            devices,
+            self.event_bus.clone(),
+            device.clone(),
+            self.config.clone(),
+            output,
+        );
+        
+        // 检查控制器是否正常初始化并运行
+        if !controller.is_running() {
+            self.active = !self.pads.is_empty();
+            info!("控制器初始化成功但未能启动");
+            self.connection.handle(ConnectionInput::ConnectFailed("控制器初始化失败，未能启动".to_string()), Instant::now());
+            return false;
+        }
+        controller.set_paused(self.connection.state() == ConnectionState::Suspended);
+        controller.set_connection_reporter(self.connection.reporter());
+        
+        // 保存控制器引用
+        self.pads.push(ActivePad {
+            device,
+            controller: Arc::new(Mutex::new(controller)),
+            recording,
+        });
+        if self.pads.len() > 1 {
+            // 非主手柄可能使用单独的档案
+            let index = self.pads.len() - 1;
+            if let Ok(mut controller) = self.pads[index].controller.lock() {
+                controller.update_config(self.config_for_pad(index));
+            }
+        }
+        self.coordinate_pads();
+        self.apply_axis_remaps();
+        
+        self.gamepad_name = self.pads[0].device.name.clone();
+        self.active = true;
+        self.connection.handle(ConnectionInput::Connected(self.pads.len()), Instant::now());
+        info!("手柄控制器启动成功: {}", name);
+        true
+    }
+    
+    /// 刷新可用的手柄列表
+    pub fn refresh_gamepads(&mut self) {
+        info!("手动刷新可用的手柄列表");
+        
+        // 直接读取设备管理服务维护的手柄列表
+        if let Some(devices) = self.ensure_device_manager() {
+            self.receive_device_events();
+            self.device_list = self.filter_devices(devices.devices());
             
             // 更新可用的手柄列表
-            self.available_gamepads = new_gamepads;
+            self.available_gamepads = self.device_list.iter()
+                .map(|device| (device.id, device.name.clone()))
+                .collect();
             info!("发现 {} 个可用手柄", self.available_gamepads.len());
         } else {
-            error!("刷新手柄列表时无法初始化Gilrs");
+            error!("刷新手柄列表时无法启动手柄设备管理服务");
+        }
+    }
+}
+
+/// 停止手柄的控制线程
+fn stop_pad_controller(pad: &ActivePad) {
+    match pad.controller.lock() {
+        Ok(mut controller) => {
+            controller.stop();
+        },
+        Err(e) => {
+            error!("停止控制器时获取锁失败: {}", e);
         }
-        
-        // 重置连接尝试计时
-        self.last_connection_attempt = Instant::now().checked_sub(Duration::from_secs(3)).unwrap_or(Instant::now());
     }
 }
 
@@ -545,13 +1344,20 @@ fn setup_custom_fonts(ctx: &egui::Context) {
     info!("已配置UI字体和样式以提高可读性和美观度");
 }
 
+impl Drop for GamepadMouseApp {
+    fn drop(&mut self) {
+        // 退出时保存未结束的录制
+        self.stop_recording();
+    }
+}
+
 impl eframe::App for GamepadMouseApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // 更新控制器状态
-        self.update_controller();
+        // 窗口没有输入事件时也要定期刷新，以便处理手柄和焦点窗口的变化
+        ctx.request_repaint_after(Duration::from_millis(200));
         
-        // 扫描并更新可用的手柄列表
-        self.scan_gamepads();
+        // 处理手柄、配置文件和事件
+        self.tick();
         
         // 顶部菜单栏
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
@@ -581,6 +1387,8 @@ impl eframe::App for GamepadMouseApp {
         
         // 主内容区域
         egui::CentralPanel::default().show(ctx, |ui| {
+            // 按钮名称按当前手柄型号显示
+            let model = self.primary_model();
             ui.vertical_centered(|ui| {
                 ui.heading("游戏手柄鼠标控制器");
             });
@@ -589,7 +1397,17 @@ impl eframe::App for GamepadMouseApp {
             // 状态面板
             ui.horizontal(|ui| {
                 ui.strong("状态: ");
-                ui.colored_label(self.status_color, &self.status_message);
+                let status = ui.colored_label(self.status_color, &self.status_message);
+                if !self.status_detail.is_empty() {
+                    status.on_hover_text(&self.status_detail);
+                }
+                if let Some(pad) = self.pads.first() {
+                    let battery = self.battery_of(pad.device.id);
+                    if battery != BatteryLevel::Unknown {
+                        let color = if battery.is_low(self.profiles.battery.threshold) { Color32::RED } else { Color32::GRAY };
+                        ui.colored_label(color, battery.describe());
+                    }
+                }
                 
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                     if self.active {
@@ -597,6 +1415,12 @@ impl eframe::App for GamepadMouseApp {
                         if stop_btn.clicked() {
                             self.stop_controller();
                         }
+                        
+                        let paused = self.connection.state() == ConnectionState::Suspended;
+                        let pause_btn = ui.add(egui::Button::new(if paused { "▶ 继续" } else { "⏸ 暂停" }).min_size(egui::vec2(80.0, 28.0)));
+                        if pause_btn.clicked() {
+                            self.set_paused(!paused);
+                        }
                     } else {
                         let start_btn = ui.add(egui::Button::new("▶ 启动").min_size(egui::vec2(80.0, 28.0)));
                         if start_btn.clicked() {
@@ -611,6 +1435,10 @@ impl eframe::App for GamepadMouseApp {
                 });
             });
             
+            if let Some(warning) = &self.battery_warning {
+                ui.colored_label(Color32::RED, format!("⚠ {}", warning));
+            }
+            
             // 手柄选择
             ui.horizontal(|ui| {
                 ui.strong("选择手柄: ");
@@ -634,8 +1462,8 @@ impl eframe::App for GamepadMouseApp {
                     
                 // 在UI闭包外处理手柄选择变更
                 if let Some((i, name)) = selected_index {
-                    // 如果当前有控制器在运行，先停止
-                    if self.active {
+                    // 单手柄模式下，如果当前有控制器在运行，先停止
+                    if self.active && !self.profiles.multi_pad_enabled {
                         self.stop_controller();
                     }
                     self.selected_gamepad_index = i;
@@ -650,7 +1478,895 @@ impl eframe::App for GamepadMouseApp {
             egui::ScrollArea::vertical()
                 .auto_shrink([false, false])
                 .show(ui, |ui| {
+                    if !self.config_issues.is_empty() {
+                        let errors = self.config_issues.iter().filter(|issue| issue.is_error()).count();
+                        let title = format!("⚠ 配置检查（{} 个错误，{} 个警告）", errors, self.config_issues.len() - errors);
+                        egui::CollapsingHeader::new(title)
+                            .id_source("config_issues")
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                for issue in &self.config_issues {
+                                    ui.horizontal_wrapped(|ui| {
+                                        let color = if issue.is_error() { Color32::RED } else { Color32::YELLOW };
+                                        ui.colored_label(color, issue.severity.display_name());
+                                        if !issue.path.is_empty() {
+                                            ui.monospace(&issue.path);
+                                        }
+                                        ui.label(&issue.message);
+                                    });
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.button("重新检查").clicked() {
+                                        *self.profiles.active_config_mut() = self.config.clone();
+                                        self.config_issues = validation::validate_store(&self.profiles);
+                                    }
+                                    if ui.button("忽略").clicked() {
+                                        self.config_issues.clear();
+                                    }
+                                });
+                                ui.small("加载时有错误的值已恢复为默认值，保存后错误会从配置文件中消失");
+                            });
+                    }
+                    
+                    egui::CollapsingHeader::new("🎮 已连接的手柄")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            let mut store_changed = false;
+                            if ui.checkbox(&mut self.profiles.multi_pad_enabled, "同时使用多个手柄").changed() {
+                                store_changed = true;
+                                // 关闭多手柄模式时只保留主手柄
+                                if !self.profiles.multi_pad_enabled {
+                                    while self.pads.len() > 1 {
+                                        self.stop_pad(self.pads.len() - 1);
+                                    }
+                                }
+                            }
+                            
+                            ui.horizontal(|ui| {
+                                ui.label("输出方式:");
+                                egui::ComboBox::from_id_source("output_backend")
+                                    .selected_text(self.profiles.output_backend.display_name())
+                                    .show_ui(ui, |ui| {
+                                        for backend in OutputBackend::AVAILABLE {
+                                            if ui.selectable_value(&mut self.profiles.output_backend, *backend, backend.display_name()).changed() {
+                                                store_changed = true;
+                                            }
+                                        }
+                                    });
+                            }).response.on_hover_text("下次启动手柄时生效；Wayland 或终端下请选择 uinput");
+                            
+                            let mut input_changed = false;
+                            ui.horizontal(|ui| {
+                                ui.label("输入方式:");
+                                egui::ComboBox::from_id_source("input_backend")
+                                    .selected_text(self.profiles.input_backend.display_name())
+                                    .show_ui(ui, |ui| {
+                                        for backend in InputBackend::AVAILABLE {
+                                            if ui.selectable_value(&mut self.profiles.input_backend, *backend, backend.display_name()).changed() {
+                                                input_changed = true;
+                                            }
+                                        }
+                                    });
+                            }).response.on_hover_text("gilrs 不认识的手柄可以改用 evdev，并在映射文件中为它指定键码");
+                            #[cfg(target_os = "linux")]
+                            if self.profiles.input_backend == InputBackend::Evdev {
+                                ui.horizontal(|ui| {
+                                    ui.small(format!("映射文件: {}", crate::evdev::EvdevMapping::mappings_path().display()));
+                                    if ui.small_button("重新加载映射").clicked() {
+                                        input_changed = true;
+                                    }
+                                });
+                                if let Some(devices) = &self.devices {
+                                    for device in &self.device_list {
+                                        let raw = devices.last_raw_input(device.id).unwrap_or_else(|| "等待输入".to_string());
+                                        ui.small(format!("{}: 最近的原始输入 {}", device.name, raw));
+                                    }
+                                }
+                            }
+                            if input_changed {
+                                store_changed = true;
+                                // 回放的输入源也会被替换
+                                self.replay = None;
+                                self.restart_input_source();
+                            }
+                            
+                            if ui.checkbox(&mut self.dry_run, "试运行（只记录输出，不移动光标）")
+                                .on_hover_text("下次启动手柄时生效，记录的输出显示在“活动记录”中；震动提示也只记录不震动")
+                                .changed()
+                            {
+                                self.update_haptics_backend();
+                            }
+                            
+                            ui.add_enabled_ui(self.profiles.multi_pad_enabled, |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label("协作方式:");
+                                    egui::ComboBox::from_id_source("multi_pad_policy")
+                                        .selected_text(self.profiles.multi_pad_policy.display_name())
+                                        .show_ui(ui, |ui| {
+                                            for policy in MultiPadPolicy::ALL {
+                                                if ui.selectable_value(&mut self.profiles.multi_pad_policy, policy, policy.display_name()).changed() {
+                                                    store_changed = true;
+                                                }
+                                            }
+                                        });
+                                });
+                            });
+                            match self.profiles.multi_pad_policy {
+                                MultiPadPolicy::SharedCursor => ui.small("所有手柄同时控制同一个光标"),
+                                MultiPadPolicy::SeparateRoles => ui.small("键盘角色的手柄用摇杆和十字键发送方向键，面键发送回车/Esc/退格/空格，RB 发送 Tab"),
+                                MultiPadPolicy::Handover => ui.small("最近有操作的手柄获得控制权，其他手柄暂停输出"),
+                            };
+                            
+                            ui.horizontal(|ui| {
+                                ui.label("手柄断开后:");
+                                egui::ComboBox::from_id_source("reconnect_policy")
+                                    .selected_text(self.profiles.reconnect_policy.display_name())
+                                    .show_ui(ui, |ui| {
+                                        for policy in ReconnectPolicy::ALL {
+                                            if ui.selectable_value(&mut self.profiles.reconnect_policy, policy, policy.display_name()).changed() {
+                                                store_changed = true;
+                                            }
+                                        }
+                                    });
+                                if self.profiles.reconnect_policy == ReconnectPolicy::SwitchToAvailable {
+                                    ui.label("等待");
+                                    if ui.add(egui::DragValue::new(&mut self.profiles.reconnect_timeout_secs).clamp_range(1..=600).suffix(" 秒")).changed() {
+                                        store_changed = true;
+                                    }
+                                }
+                            });
+                            
+                            ui.horizontal(|ui| {
+                                store_changed |= ui.checkbox(&mut self.profiles.battery.warn_enabled, "低电量警告").changed();
+                                ui.label("电量低于");
+                                store_changed |= ui.add_enabled(
+                                    self.profiles.battery.warn_enabled,
+                                    egui::DragValue::new(&mut self.profiles.battery.threshold).clamp_range(1..=90).suffix("%"),
+                                ).changed();
+                                ui.label("时提示");
+                            }).response.on_hover_text("在状态栏显示警告，并按“震动提示”中的设置震动");
+                            
+                            ui.horizontal(|ui| {
+                                let backoff = &mut self.profiles.connection_backoff;
+                                ui.label("重试间隔: 初始");
+                                let mut changed = ui.add(egui::DragValue::new(&mut backoff.initial_secs).clamp_range(0.1..=60.0).speed(0.1).suffix(" 秒")).changed();
+                                ui.label("最长");
+                                changed |= ui.add(egui::DragValue::new(&mut backoff.max_secs).clamp_range(1.0..=600.0).suffix(" 秒")).changed();
+                                ui.label("倍数");
+                                changed |= ui.add(egui::DragValue::new(&mut backoff.factor).clamp_range(1.0..=5.0).speed(0.1)).changed();
+                                ui.label("最多");
+                                changed |= ui.add(egui::DragValue::new(&mut backoff.max_attempts).clamp_range(0..=100).suffix(" 次")).changed();
+                                if changed {
+                                    store_changed = true;
+                                }
+                            }).response.on_hover_text("每次失败后等待时间按倍数增加，最多次数为 0 表示一直重试");
+                            if store_changed {
+                                self.connection.set_backoff(self.profiles.connection_backoff);
+                            }
+                            ui.add_space(4.0);
+                            
+                            if self.pads.is_empty() {
+                                ui.colored_label(Color32::GRAY, "没有正在运行的手柄");
+                            }
+                            
+                            let profile_names = self.profiles.names();
+                            let mut stop_index = None;
+                            let mut pads_changed = false;
+                            egui::Grid::new("active_pads_grid")
+                                .num_columns(6)
+                                .spacing([10.0, 6.0])
+                                .show(ui, |ui| {
+                                    for (index, pad) in self.pads.iter().enumerate() {
+                                        let connected = pad.controller.lock().map(|c| c.is_connected()).unwrap_or(false);
+                                        ui.label(&pad.device.name);
+                                        ui.label(pad.device.detected.model.display_name());
+                                        let battery = self.battery_of(pad.device.id);
+                                        let battery_color = if battery.is_low(self.profiles.battery.threshold) { Color32::RED } else { Color32::GRAY };
+                                        ui.colored_label(battery_color, battery.describe());
+                                        if connected {
+                                            ui.colored_label(Color32::GREEN, "运行中");
+                                        } else {
+                                            ui.colored_label(Color32::YELLOW, "已断开");
+                                        }
+                                        
+                                        if self.profiles.multi_pad_policy == MultiPadPolicy::SeparateRoles {
+                                            let role = self.profiles.pad_role(&pad.device.key());
+                                            egui::ComboBox::from_id_source(format!("pad_role_{}", index))
+                                                .width(70.0)
+                                                .selected_text(role.display_name())
+                                                .show_ui(ui, |ui| {
+                                                    for option in [PadRole::Pointer, PadRole::Keyboard] {
+                                                        if ui.selectable_label(role == option, option.display_name()).clicked() && role != option {
+                                                            self.profiles.set_pad_role(&pad.device.key(), option);
+                                                            pads_changed = true;
+                                                        }
+                                                    }
+                                                });
+                                        } else {
+                                            ui.label("");
+                                        }
+                                        
+                                        if index == 0 {
+                                            ui.label(format!("档案: {}", self.profiles.active_profile));
+                                        } else {
+                                            let assigned = self.profiles.pad_profile(&pad.device.key()).cloned();
+                                            let selected_text = assigned.clone().unwrap_or_else(|| "跟随当前档案".to_string());
+                                            egui::ComboBox::from_id_source(format!("pad_profile_{}", index))
+                                                .width(120.0)
+                                                .selected_text(selected_text)
+                                                .show_ui(ui, |ui| {
+                                                    if ui.selectable_label(assigned.is_none(), "跟随当前档案").clicked() && assigned.is_some() {
+                                                        self.profiles.set_pad_profile(&pad.device.key(), None);
+                                                        pads_changed = true;
+                                                    }
+                                                    for name in &profile_names {
+                                                        let is_assigned = assigned.as_ref() == Some(name);
+                                                        if ui.selectable_label(is_assigned, name).clicked() && !is_assigned {
+                                                            self.profiles.set_pad_profile(&pad.device.key(), Some(name.clone()));
+                                                            pads_changed = true;
+                                                        }
+                                                    }
+                                                });
+                                        }
+                                        
+                                        if ui.small_button("停止").clicked() {
+                                            stop_index = Some(index);
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                            
+                            if let Some(index) = stop_index {
+                                self.stop_pad(index);
+                            }
+                            if pads_changed {
+                                store_changed = true;
+                                self.apply_config_to_controller();
+                            }
+                            if store_changed {
+                                self.coordinate_pads();
+                                if let Err(e) = self.profiles.save() {
+                                    error!("保存配置失败: {}", e);
+                                }
+                            }
+                            
+                            if self.profiles.multi_pad_enabled {
+                                let idle = self.idle_gamepads();
+                                for (id, name) in idle {
+                                    ui.horizontal(|ui| {
+                                        ui.colored_label(Color32::GRAY, &name);
+                                        if ui.small_button("启动").clicked() {
+                                            self.start_pad(id, &name);
+                                        }
+                                    });
+                                }
+                            }
+                        });
+                    
+                    egui::CollapsingHeader::new("🕹 轴映射")
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            ui.label("按手柄调整摇杆和扳机，适用于摇杆装反或轴方向不同的硬件，在其他所有处理之前生效。");
+                            if self.device_list.is_empty() {
+                                ui.colored_label(Color32::GRAY, "没有已连接的手柄");
+                            }
+                            let mut remap_changed = false;
+                            for device in &self.device_list {
+                                let mut remap = self.profiles.axis_remap(&device.key());
+                                let mut changed = false;
+                                egui::CollapsingHeader::new(&device.name)
+                                    .id_source(("axis_remap", device.id))
+                                    .show(ui, |ui| {
+                                        ui.horizontal(|ui| {
+                                            changed |= ui.checkbox(&mut remap.swap_sticks, "交换左右摇杆").changed();
+                                            changed |= ui.checkbox(&mut remap.swap_xy, "交换 X/Y").changed();
+                                        });
+                                        egui::Grid::new(("axis_rules", device.id))
+                                            .num_columns(3)
+                                            .show(ui, |ui| {
+                                                for axis in TRACKED_AXES {
+                                                    let mut rule = remap.rule(axis);
+                                                    ui.label(axis_display_name(axis));
+                                                    let mut rule_changed = ui.checkbox(&mut rule.invert, "反转").changed();
+                                                    egui::ComboBox::from_id_source(("axis_conversion", device.id, axis_display_name(axis)))
+                                                        .selected_text(rule.conversion.display_name())
+                                                        .show_ui(ui, |ui| {
+                                                            for conversion in AxisConversion::ALL {
+                                                                rule_changed |= ui.selectable_value(&mut rule.conversion, conversion, conversion.display_name()).changed();
+                                                            }
+                                                        });
+                                                    if rule_changed {
+                                                        remap.set_rule(axis, rule);
+                                                        changed = true;
+                                                    }
+                                                    ui.end_row();
+                                                }
+                                            });
+                                        if !remap.is_identity() && ui.small_button("恢复默认").clicked() {
+                                            remap = AxisRemap::default();
+                                            changed = true;
+                                        }
+                                    });
+                                if changed {
+                                    self.profiles.set_axis_remap(&device.key(), remap);
+                                    remap_changed = true;
+                                }
+                            }
+                            if remap_changed {
+                                self.apply_axis_remaps();
+                                if let Err(e) = self.profiles.save() {
+                                    error!("保存配置失败: {}", e);
+                                }
+                            }
+                        });
+                    
+                    egui::CollapsingHeader::new("📳 震动提示")
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            let mut changed = ui.checkbox(&mut self.profiles.haptics.enabled, "启用震动提示").changed();
+                            ui.small("强/弱为两个马达的强度，次数大于 1 时按相同间隔重复");
+                            let mut test_cue = None;
+                            ui.add_enabled_ui(self.profiles.haptics.enabled, |ui| {
+                                egui::Grid::new("haptic_cues")
+                                    .num_columns(6)
+                                    .spacing([8.0, 4.0])
+                                    .show(ui, |ui| {
+                                        for cue in HapticCue::ALL {
+                                            let setting = self.profiles.haptics.cue_mut(cue);
+                                            changed |= ui.checkbox(&mut setting.enabled, cue.display_name()).changed();
+                                            changed |= ui.add(egui::Slider::new(&mut setting.pattern.strong, 0.0..=1.0).text("强")).changed();
+                                            changed |= ui.add(egui::Slider::new(&mut setting.pattern.weak, 0.0..=1.0).text("弱")).changed();
+                                            changed |= ui.add(egui::DragValue::new(&mut setting.pattern.duration_ms).clamp_range(10..=2000).suffix(" 毫秒")).changed();
+                                            changed |= ui.add(egui::DragValue::new(&mut setting.pattern.pulses).clamp_range(1..=5).suffix(" 次")).changed();
+                                            if ui.add_enabled(setting.enabled && !self.pads.is_empty(), egui::Button::new("试一试").small()).clicked() {
+                                                test_cue = Some(cue);
+                                            }
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                            if changed {
+                                if let Ok(mut haptics) = self.haptics.lock() {
+                                    haptics.set_settings(self.profiles.haptics.clone());
+                                }
+                                if let Err(e) = self.profiles.save() {
+                                    error!("保存配置失败: {}", e);
+                                }
+                            }
+                            if let (Some(cue), Some(pad)) = (test_cue, self.pads.first())
+                                && let Ok(mut haptics) = self.haptics.lock()
+                            {
+                                haptics.trigger(pad.device.id, cue, Instant::now());
+                            }
+                            
+                            if let Some(mock) = &self.haptics_mock {
+                                ui.add_space(4.0);
+                                ui.label("试运行中的震动记录:");
+                                let played = mock.played();
+                                if played.is_empty() {
+                                    ui.colored_label(Color32::GRAY, "暂无");
+                                }
+                                for (pad, pattern) in played.iter().rev().take(10) {
+                                    ui.small(format!("手柄 {}: 强 {:.1} 弱 {:.1}，{} 毫秒 × {}", pad, pattern.strong, pattern.weak, pattern.duration_ms, pattern.pulses));
+                                }
+                            }
+                        });
+                    
+                    egui::CollapsingHeader::new("🧭 手柄映射")
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            ui.label("gilrs 不认识的手柄按钮可能错位或不可用。映射向导会依次提示按下每个按钮、移动每个摇杆，生成 SDL 格式的映射。");
+                            ui.small(format!("用户映射文件: {}（启动时加载，格式与 gamecontrollerdb.txt 相同）", sdl_mapping::user_mappings_path().display()));
+                            
+                            let mut finish = None;
+                            if let Some(wizard) = &mut self.mapping_wizard {
+                                let (done, total) = wizard.progress();
+                                ui.label(format!("正在录入: {}（{}/{}）", wizard.device.name, done, total));
+                                match wizard.current() {
+                                    Some(target) => {
+                                        ui.colored_label(Color32::YELLOW, target.prompt());
+                                    }
+                                    None => {
+                                        ui.colored_label(Color32::GREEN, format!("录入完成，共 {} 项", wizard.captured().len()));
+                                    }
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.add_enabled(!wizard.is_done(), egui::Button::new("跳过")).on_hover_text("手柄上没有这个按钮时跳过").clicked() {
+                                        wizard.skip();
+                                    }
+                                    if ui.add_enabled(done > 0, egui::Button::new("上一步")).clicked() {
+                                        wizard.back();
+                                    }
+                                    if ui.add_enabled(wizard.is_done(), egui::Button::new("💾 应用并保存")).clicked() {
+                                        finish = Some(true);
+                                    }
+                                    if ui.button("取消").clicked() {
+                                        finish = Some(false);
+                                    }
+                                });
+                            } else {
+                                let mut start = None;
+                                ui.horizontal(|ui| {
+                                    ui.label("手柄:");
+                                    let index = self.mapping_device_index.min(self.device_list.len().saturating_sub(1));
+                                    let selected = self.device_list.get(index).map(|device| device.name.clone()).unwrap_or_else(|| "无手柄连接".to_string());
+                                    egui::ComboBox::from_id_source("mapping_device")
+                                        .selected_text(selected)
+                                        .show_ui(ui, |ui| {
+                                            for (i, device) in self.device_list.iter().enumerate() {
+                                                let label = if device.detected.mapping_source == gilrs::MappingSource::None {
+                                                    format!("{}（未识别）", device.name)
+                                                } else {
+                                                    device.name.clone()
+                                                };
+                                                ui.selectable_value(&mut self.mapping_device_index, i, label);
+                                            }
+                                        });
+                                    if ui.add_enabled(!self.device_list.is_empty(), egui::Button::new("开始录入")).clicked() {
+                                        start = self.device_list.get(index).cloned();
+                                    }
+                                });
+                                if let Some(device) = start {
+                                    self.start_mapping_wizard(device);
+                                }
+                            }
+                            if let Some(save) = finish {
+                                self.finish_mapping_wizard(save);
+                            }
+                            
+                            if let Some((message, color)) = &self.mapping_message {
+                                ui.colored_label(*color, message);
+                            }
+                            if let Some(mapping) = &mut self.mapping_output {
+                                ui.add(egui::TextEdit::multiline(mapping).desired_rows(2).desired_width(f32::INFINITY))
+                                    .on_hover_text("可以复制到其他使用 SDL 的程序中");
+                            }
+                        });
+                    
+                    egui::CollapsingHeader::new("📈 活动记录")
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            let stats = &self.activity_stats;
+                            egui::Grid::new("activity_stats_grid")
+                                .num_columns(2)
+                                .spacing([20.0, 4.0])
+                                .show(ui, |ui| {
+                                    ui.label("点击次数:");
+                                    ui.label(stats.clicks.to_string());
+                                    ui.end_row();
+                                    ui.label("滚动步数:");
+                                    ui.label(stats.scroll_steps.to_string());
+                                    ui.end_row();
+                                    ui.label("按键次数:");
+                                    ui.label(stats.key_presses.to_string());
+                                    ui.end_row();
+                                    ui.label("模式切换:");
+                                    ui.label(stats.mode_changes.to_string());
+                                    ui.end_row();
+                                    ui.label("断开次数:");
+                                    ui.label(stats.disconnects.to_string());
+                                    ui.end_row();
+                                    ui.label("光标移动:");
+                                    ui.label(format!("{:.0} 像素", stats.distance));
+                                    ui.end_row();
+                                });
+                            
+                            ui.add_space(5.0);
+                            if ui.button("清空").clicked() {
+                                self.activity_feed.clear();
+                                self.activity_stats = ActivityStats::default();
+                            }
+                            
+                            egui::ScrollArea::vertical()
+                                .id_source("activity_feed")
+                                .max_height(150.0)
+                                .show(ui, |ui| {
+                                    if self.activity_feed.is_empty() {
+                                        ui.colored_label(Color32::GRAY, "暂无活动");
+                                    }
+                                    for message in &self.activity_feed {
+                                        let time = chrono::DateTime::<chrono::Local>::from(message.time);
+                                        ui.horizontal(|ui| {
+                                            ui.colored_label(Color32::GRAY, time.format("%H:%M:%S").to_string());
+                                            if self.pads.len() > 1 {
+                                                ui.colored_label(Color32::LIGHT_BLUE, &message.pad);
+                                            }
+                                            ui.label(message.event.describe());
+                                        });
+                                    }
+                                });
+                            
+                            // 试运行的输出记录
+                            for pad in &self.pads {
+                                let Some(recording) = &pad.recording else {
+                                    continue;
+                                };
+                                ui.add_space(5.0);
+                                ui.label(format!("试运行输出 - {}", pad.device.name));
+                                egui::ScrollArea::vertical()
+                                    .id_source(("dry_run_log", pad.device.id))
+                                    .max_height(120.0)
+                                    .stick_to_bottom(true)
+                                    .show(ui, |ui| {
+                                        let log = recording.log();
+                                        let Ok(log) = log.lock() else {
+                                            return;
+                                        };
+                                        for entry in log.iter() {
+                                            ui.monospace(format!("{:>9.3}s  {:?}", entry.elapsed.as_secs_f32(), entry.action));
+                                        }
+                                    });
+                            }
+                        });
+                    
+                    egui::CollapsingHeader::new("⏺ 录制与回放")
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            ui.label("录制手柄的原始输入，之后可以用任意档案回放，便于复现问题和比较曲线设置。");
+                            ui.horizontal(|ui| {
+                                if let Some(recorder) = &self.recorder {
+                                    let elapsed = recorder.elapsed().as_secs_f32();
+                                    if ui.button("⏹ 停止录制").clicked() {
+                                        self.stop_recording();
+                                    }
+                                    ui.colored_label(Color32::YELLOW, format!("录制中 {:.1} 秒", elapsed));
+                                } else if ui.add_enabled(self.replay.is_none(), egui::Button::new("⏺ 开始录制")).clicked() {
+                                    self.start_recording(None);
+                                }
+                            });
+                            
+                            if let Some((source, name)) = &self.replay {
+                                let finished = source.is_finished();
+                                let name = name.clone();
+                                ui.horizontal(|ui| {
+                                    let state = if finished { "回放完毕" } else { "正在回放" };
+                                    ui.colored_label(Color32::YELLOW, format!("{}: {}", state, name));
+                                    if ui.button("结束回放").clicked() {
+                                        self.stop_replay();
+                                    }
+                                });
+                            }
+                            
+                            if let Some((message, color)) = &self.session_message {
+                                ui.colored_label(*color, message);
+                            }
+                            
+                            ui.add_space(5.0);
+                            ui.horizontal(|ui| {
+                                ui.label("录制文件:");
+                                if ui.small_button("刷新").clicked() {
+                                    self.session_files = session::list_sessions();
+                                }
+                            });
+                            if self.session_files.is_empty() {
+                                ui.colored_label(Color32::GRAY, format!("{} 中暂无录制文件", session::sessions_dir().display()));
+                            }
+                            let mut replay_path = None;
+                            egui::ScrollArea::vertical()
+                                .id_source("session_files")
+                                .max_height(120.0)
+                                .show(ui, |ui| {
+                                    for path in &self.session_files {
+                                        ui.horizontal(|ui| {
+                                            if ui.add_enabled(self.recorder.is_none(), egui::Button::new("▶ 回放").small()).clicked() {
+                                                replay_path = Some(path.clone());
+                                            }
+                                            ui.label(path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default());
+                                        });
+                                    }
+                                });
+                            if let Some(path) = replay_path {
+                                self.start_replay(&path);
+                            }
+                        });
+                    
+                    egui::CollapsingHeader::new("📁 配置档案")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("当前档案:");
+                                let mut selected_profile = None;
+                                egui::ComboBox::from_id_source("profile_selection")
+                                    .width(180.0)
+                                    .selected_text(&self.profiles.active_profile)
+                                    .show_ui(ui, |ui| {
+                                        for name in self.profiles.names() {
+                                            let is_active = name == self.profiles.active_profile;
+                                            if ui.selectable_label(is_active, &name).clicked() && !is_active {
+                                                selected_profile = Some(name);
+                                            }
+                                        }
+                                    });
+                                if let Some(name) = selected_profile {
+                                    self.switch_profile(&name);
+                                }
+                            });
+                            
+                            ui.horizontal(|ui| {
+                                ui.label("档案名称:");
+                                ui.add(egui::TextEdit::singleline(&mut self.profile_name_input).desired_width(140.0));
+                                
+                                let name = self.profile_name_input.trim().to_string();
+                                let active = self.profiles.active_profile.clone();
+                                if ui.button("新建").clicked() {
+                                    let result = self.profiles.create(&name);
+                                    self.handle_profile_result(result, format!("已创建档案: {}", name));
+                                }
+                                if ui.button("克隆").clicked() {
+                                    *self.profiles.active_config_mut() = self.config.clone();
+                                    let result = self.profiles.clone_profile(&active, &name);
+                                    self.handle_profile_result(result, format!("已将 {} 克隆为 {}", active, name));
+                                }
+                                if ui.button("重命名").clicked() {
+                                    let result = self.profiles.rename(&active, &name);
+                                    self.handle_profile_result(result, format!("已将 {} 重命名为 {}", active, name));
+                                }
+                                if ui.button("删除").clicked() {
+                                    match self.profiles.delete(&active) {
+                                        Ok(()) => self.on_active_profile_changed(),
+                                        Err(e) => self.profile_message = Some((e, Color32::RED)),
+                                    }
+                                }
+                                if ui.button("导出").clicked() {
+                                    let config = self.config.clone();
+                                    self.export_config(&active, &config);
+                                }
+                            });
+                            
+                            ui.horizontal(|ui| {
+                                ui.label("导入:");
+                                ui.add(egui::TextEdit::singleline(&mut self.import_path_input)
+                                    .hint_text(".json 或 .toml 文件路径")
+                                    .desired_width(220.0));
+                                if ui.add_enabled(!self.import_path_input.trim().is_empty(), egui::Button::new("导入"))
+                                    .on_hover_text("替换当前的所有档案，原配置文件会先备份")
+                                    .clicked()
+                                {
+                                    self.import_store();
+                                }
+                            });
+                            
+                            ui.horizontal(|ui| {
+                                ui.label("导出全部档案为:");
+                                for format in ConfigFormat::ALL {
+                                    if ui.button(format.display_name()).clicked() {
+                                        self.export_store(format);
+                                    }
+                                }
+                            });
+                            
+                            let config_path = Config::get_config_path();
+                            ui.small(format!(
+                                "配置文件: {}（{}）",
+                                config_path,
+                                ConfigFormat::from_path(Path::new(&config_path)).display_name(),
+                            ));
+                            
+                            egui::CollapsingHeader::new("🕘 历史版本")
+                                .default_open(false)
+                                .show(ui, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.label("保留最近");
+                                        if ui.add(egui::DragValue::new(&mut self.profiles.backup_count).clamp_range(0..=100).suffix(" 个")).changed()
+                                            && let Err(e) = self.profiles.save()
+                                        {
+                                            error!("保存配置失败: {}", e);
+                                        }
+                                        ui.label("自动备份");
+                                    }).response.on_hover_text("保存配置前备份原文件，一分钟内多次保存只备份一次；为 0 时不备份");
+                                    
+                                    let backups = config_backup::list_backups();
+                                    if backups.is_empty() {
+                                        ui.colored_label(Color32::GRAY, "还没有备份");
+                                    }
+                                    let mut restored = None;
+                                    egui::Grid::new("config_backups_grid")
+                                        .num_columns(3)
+                                        .spacing([12.0, 4.0])
+                                        .show(ui, |ui| {
+                                            for backup in &backups {
+                                                ui.label(backup.time.format("%Y-%m-%d %H:%M:%S").to_string());
+                                                ui.label(backup.reason_name())
+                                                    .on_hover_text(backup.path.display().to_string());
+                                                if ui.button("恢复").clicked() {
+                                                    restored = Some(backup.path.clone());
+                                                }
+                                                ui.end_row();
+                                            }
+                                        });
+                                    if let Some(path) = restored {
+                                        self.restore_backup(&path);
+                                    }
+                                });
+                            
+                            if let Some((message, color)) = &self.profile_message {
+                                ui.colored_label(*color, message);
+                            }
+                            
+                            ui.separator();
+                            ui.horizontal(|ui| {
+                                match self.pads.first().map(|pad| pad.device.detected) {
+                                    Some(detected) => {
+                                        ui.label(format!("手柄型号: {} (映射来源: {:?})", detected.model.display_name(), detected.mapping_source));
+                                        if ui.button("设为此型号的默认档案").clicked() {
+                                            self.profiles.model_profiles.insert(detected.model, self.profiles.active_profile.clone());
+                                            if let Err(e) = self.profiles.save() {
+                                                error!("保存配置失败: {}", e);
+                                            }
+                                        }
+                                        if ui.button("按型号创建默认档案").clicked() {
+                                            self.create_model_profile(detected.model);
+                                        }
+                                    }
+                                    None => {
+                                        ui.label("手柄型号: 未识别");
+                                    }
+                                }
+                            });
+                            
+                            let mut cleared_model = None;
+                            for model in ControllerModel::ALL {
+                                if let Some(profile) = self.profiles.model_profiles.get(&model) {
+                                    ui.horizontal(|ui| {
+                                        ui.label(format!("{} → {}", model.display_name(), profile));
+                                        if ui.small_button("取消").clicked() {
+                                            cleared_model = Some(model);
+                                        }
+                                    });
+                                }
+                            }
+                            if let Some(model) = cleared_model {
+                                self.profiles.model_profiles.remove(&model);
+                                if let Err(e) = self.profiles.save() {
+                                    error!("保存配置失败: {}", e);
+                                }
+                            }
+                            
+                            ui.separator();
+                            if ui.checkbox(&mut self.profiles.auto_switch_enabled, "按前台应用自动切换档案").changed()
+                                && let Err(e) = self.profiles.save()
+                            {
+                                error!("保存配置失败: {}", e);
+                            }
+                            
+                            match self.app_switcher.as_ref().map(|s| s.current_app().cloned()) {
+                                None => {
+                                    ui.colored_label(Color32::GRAY, "当前环境不支持（需要 Linux X11）");
+                                }
+                                Some(current_app) => {
+                                    ui.horizontal(|ui| {
+                                        let app_name = current_app.as_ref()
+                                            .map(|app| app.display_name())
+                                            .unwrap_or_else(|| "无".to_string());
+                                        ui.label(format!("当前应用: {}", app_name));
+                                        if let Some(app) = current_app
+                                            && ui.button("为此应用添加规则").clicked()
+                                        {
+                                            let app = app.process_name.clone()
+                                                .unwrap_or_else(|| app.window_class.clone());
+                                            self.profiles.app_rules.push(AppProfileRule {
+                                                app,
+                                                profile: self.profiles.active_profile.clone(),
+                                            });
+                                            if let Err(e) = self.profiles.save() {
+                                                error!("保存配置失败: {}", e);
+                                            }
+                                        }
+                                    });
+                                }
+                            }
+                            
+                            let profile_names = self.profiles.names();
+                            let mut removed_rule = None;
+                            egui::Grid::new("app_rules_grid")
+                                .num_columns(3)
+                                .spacing([12.0, 6.0])
+                                .show(ui, |ui| {
+                                    for (i, rule) in self.profiles.app_rules.iter_mut().enumerate() {
+                                        ui.add(egui::TextEdit::singleline(&mut rule.app)
+                                            .hint_text("窗口类或进程名")
+                                            .desired_width(140.0));
+                                        egui::ComboBox::from_id_source(("app_rule_profile", i))
+                                            .width(140.0)
+                                            .selected_text(&rule.profile)
+                                            .show_ui(ui, |ui| {
+                                                for name in &profile_names {
+                                                    ui.selectable_value(&mut rule.profile, name.clone(), name);
+                                                }
+                                            });
+                                        if ui.button("移除").clicked() {
+                                            removed_rule = Some(i);
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                            if let Some(i) = removed_rule {
+                                self.profiles.app_rules.remove(i);
+                                if let Err(e) = self.profiles.save() {
+                                    error!("保存配置失败: {}", e);
+                                }
+                            }
+                        });
+                    
+                    ui.add_space(8.0);
+                    
                     // 配置选项
+                    egui::CollapsingHeader::new("📚 预设库")
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("预设:");
+                                egui::ComboBox::from_id_source("preset_selection")
+                                    .width(180.0)
+                                    .selected_text(PRESETS[self.selected_preset].name)
+                                    .show_ui(ui, |ui| {
+                                        for (i, preset) in PRESETS.iter().enumerate() {
+                                            ui.selectable_value(&mut self.selected_preset, i, preset.name);
+                                        }
+                                    });
+                                
+                                if ui.button("应用为新档案").clicked() {
+                                    self.apply_preset_as_profile();
+                                }
+                                if ui.button("导出").clicked() {
+                                    let preset = &PRESETS[self.selected_preset];
+                                    match preset.config() {
+                                        Ok(config) => self.export_config(preset.id, &config),
+                                        Err(e) => self.profile_message = Some((e, Color32::RED)),
+                                    }
+                                }
+                            });
+                            
+                            let preset = &PRESETS[self.selected_preset];
+                            ui.label(preset.description);
+                            
+                            // 预览预设的曲线和按键设置
+                            match preset.config() {
+                                Ok(preview) => {
+                                    egui::Grid::new("preset_preview_grid")
+                                        .num_columns(4)
+                                        .spacing([16.0, 4.0])
+                                        .striped(true)
+                                        .show(ui, |ui| {
+                                            ui.label("灵敏度:");
+                                            ui.label(format!("{:.1}", preview.mouse_sensitivity));
+                                            ui.label("死区:");
+                                            ui.label(format!("{:.2}", preview.dead_zone));
+                                            ui.end_row();
+                                            
+                                            ui.label("滚轮灵敏度:");
+                                            ui.label(format!("{:.1}", preview.scroll_sensitivity));
+                                            ui.label("加速曲线:");
+                                            ui.label(format!("{:.1}", preview.mouse_acceleration));
+                                            ui.end_row();
+                                            
+                                            let bindings = [
+                                                ("左键", &preview.left_click_button),
+                                                ("右键", &preview.right_click_button),
+                                                ("中键", &preview.middle_click_button),
+                                                ("双击", &preview.double_click_button),
+                                                ("后退", &preview.back_button),
+                                                ("前进", &preview.forward_button),
+                                                ("精确模式", &preview.precision_mode_button),
+                                                ("加速模式", &preview.turbo_mode_button),
+                                            ];
+                                            for pair in bindings.chunks(2) {
+                                                for (label, button) in pair {
+                                                    ui.label(format!("{}:", label));
+                                                    ui.label(button_display_name(model, button));
+                                                }
+                                                ui.end_row();
+                                            }
+                                        });
+                                }
+                                Err(e) => {
+                                    ui.colored_label(Color32::RED, e);
+                                }
+                            }
+                        });
+                    
+                    ui.add_space(8.0);
+                    
                     egui::CollapsingHeader::new("🖱️ 鼠标灵敏度设置")
                         .default_open(true)
                         .show(ui, |ui| {
@@ -724,9 +2440,9 @@ impl eframe::App for GamepadMouseApp {
                                     ui.label("左键点击:");
                                     egui::ComboBox::from_id_source("left_click_button")
                                         .width(180.0)
-                                        .selected_text(button_display_name(&self.config.left_click_button))
+                                        .selected_text(button_display_name(model, &self.config.left_click_button))
                                         .show_ui(ui, |ui| {
-                                            for (name, display) in get_button_options() {
+                                            for (name, display) in get_button_options(model) {
                                                 ui.selectable_value(&mut self.config.left_click_button, name, display);
                                             }
                                         });
@@ -735,9 +2451,9 @@ impl eframe::App for GamepadMouseApp {
                                     ui.label("右键点击:");
                                     egui::ComboBox::from_id_source("right_click_button")
                                         .width(180.0)
-                                        .selected_text(button_display_name(&self.config.right_click_button))
+                                        .selected_text(button_display_name(model, &self.config.right_click_button))
                                         .show_ui(ui, |ui| {
-                                            for (name, display) in get_button_options() {
+                                            for (name, display) in get_button_options(model) {
                                                 ui.selectable_value(&mut self.config.right_click_button, name, display);
                                             }
                                         });
@@ -746,9 +2462,9 @@ impl eframe::App for GamepadMouseApp {
                                     ui.label("中键点击:");
                                     egui::ComboBox::from_id_source("middle_click_button")
                                         .width(180.0)
-                                        .selected_text(button_display_name(&self.config.middle_click_button))
+                                        .selected_text(button_display_name(model, &self.config.middle_click_button))
                                         .show_ui(ui, |ui| {
-                                            for (name, display) in get_button_options() {
+                                            for (name, display) in get_button_options(model) {
                                                 ui.selectable_value(&mut self.config.middle_click_button, name, display);
                                             }
                                         });
@@ -757,14 +2473,63 @@ impl eframe::App for GamepadMouseApp {
                                     ui.label("双击按钮:");
                                     egui::ComboBox::from_id_source("double_click_button")
                                         .width(180.0)
-                                        .selected_text(button_display_name(&self.config.double_click_button))
+                                        .selected_text(button_display_name(model, &self.config.double_click_button))
                                         .show_ui(ui, |ui| {
-                                            for (name, display) in get_button_options() {
+                                            for (name, display) in get_button_options(model) {
                                                 ui.selectable_value(&mut self.config.double_click_button, name, display);
                                             }
                                         });
                                     ui.end_row();
                                     
+                                    ui.label("三击按钮:");
+                                    button_selector(ui, model, "triple_click_button", &mut self.config.triple_click_button);
+                                    ui.end_row();
+                                    
+                                    ui.label("侧键后退 (X1):");
+                                    button_selector(ui, model, "back_button", &mut self.config.back_button);
+                                    ui.end_row();
+                                    
+                                    ui.label("侧键前进 (X2):");
+                                    button_selector(ui, model, "forward_button", &mut self.config.forward_button);
+                                    ui.end_row();
+                                    
+                                    ui.label("滚轮左倾:");
+                                    button_selector(ui, model, "scroll_left_button", &mut self.config.scroll_left_button);
+                                    ui.end_row();
+                                    
+                                    ui.label("滚轮右倾:");
+                                    button_selector(ui, model, "scroll_right_button", &mut self.config.scroll_right_button);
+                                    ui.end_row();
+                                    
+                                    ui.label("记录光标位置:");
+                                    button_selector(ui, model, "save_position_button", &mut self.config.save_position_button);
+                                    ui.end_row();
+                                    
+                                    ui.label("在记录位置点击:");
+                                    button_selector(ui, model, "saved_position_click_button", &mut self.config.saved_position_click_button);
+                                    ui.end_row();
+                                    
+                                    ui.label("切换配置档案:");
+                                    button_selector(ui, model, "profile_cycle_button", &mut self.config.profile_cycle_button);
+                                    ui.end_row();
+                                    
+                                    ui.label("记录的位置:");
+                                    ui.horizontal(|ui| {
+                                        match self.config.saved_click_position.as_mut() {
+                                            Some((x, y)) => {
+                                                ui.add(egui::DragValue::new(x).prefix("X: "));
+                                                ui.add(egui::DragValue::new(y).prefix("Y: "));
+                                                if ui.button("清除").clicked() {
+                                                    self.config.saved_click_position = None;
+                                                }
+                                            }
+                                            None => {
+                                                ui.label("未记录");
+                                            }
+                                        }
+                                    });
+                                    ui.end_row();
+                                    
                                     // 特殊模式按钮
                                     ui.strong("特殊模式");
                                     ui.strong("触发按钮");
@@ -773,7 +2538,7 @@ impl eframe::App for GamepadMouseApp {
                                     ui.label("精确模式按钮:");
                                     egui::ComboBox::from_id_source("precision_mode_button")
                                         .width(180.0)
-                                        .selected_text(button_display_name(&self.config.precision_mode_button))
+                                        .selected_text(button_display_name(model, &self.config.precision_mode_button))
                                         .show_ui(ui, |ui| {
                                             ui.selectable_value(&mut self.config.precision_mode_button, "LeftTrigger".to_string(), "左扳机");
                                             ui.selectable_value(&mut self.config.precision_mode_button, "RightTrigger".to_string(), "右扳机");
@@ -785,7 +2550,7 @@ impl eframe::App for GamepadMouseApp {
                                     ui.label("加速模式按钮:");
                                     egui::ComboBox::from_id_source("turbo_mode_button")
                                         .width(180.0)
-                                        .selected_text(button_display_name(&self.config.turbo_mode_button))
+                                        .selected_text(button_display_name(model, &self.config.turbo_mode_button))
                                         .show_ui(ui, |ui| {
                                             ui.selectable_value(&mut self.config.turbo_mode_button, "LeftTrigger".to_string(), "左扳机");
                                             ui.selectable_value(&mut self.config.turbo_mode_button, "RightTrigger".to_string(), "右扳机");
@@ -835,6 +2600,12 @@ impl eframe::App for GamepadMouseApp {
                             ui.label("• 精确模式 - 按住按钮降低鼠标速度，用于精确控制");
                             ui.label("• 加速模式 - 按住按钮提高鼠标速度，用于快速移动");
                             ui.label("• 双击按钮 - 快速执行双击操作");
+                            ui.label("• 侧键 - 浏览器和文件管理器的后退/前进");
+                            ui.label("• 滚轮倾斜 - 按住按钮水平滚动");
+                            ui.label("• 记录位置 - 记录光标位置，之后可一键在该位置点击");
+                            ui.label("• 配置档案 - 为不同场景保存多套设置，可用手柄按钮循环切换");
+                            ui.label("• 多手柄 - 多个手柄可共享光标、分别控制鼠标和键盘，或按最近操作交接控制");
+                            ui.label("• 暂停 - 保留手柄连接但暂时停止控制鼠标");
                             
                             ui.add_space(8.0);
                             ui.heading("故障排除");
@@ -0,0 +1,335 @@
+// 手柄震动提示
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::device_manager::PadId;
+use crate::event_bus::{BusMessage, ControllerEvent, EventBus, SpeedMode};
+use crate::input::InputSource;
+
+/// 触发震动的事件
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HapticCue {
+    ProfileSwitch,
+    PrecisionMode,
+    TurboMode,
+    Paused,
+    Resumed,
+    LowBattery,
+    ScreenEdge,
+}
+
+impl HapticCue {
+    pub const ALL: [HapticCue; 7] = [
+        HapticCue::ProfileSwitch,
+        HapticCue::PrecisionMode,
+        HapticCue::TurboMode,
+        HapticCue::Paused,
+        HapticCue::Resumed,
+        HapticCue::LowBattery,
+        HapticCue::ScreenEdge,
+    ];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            HapticCue::ProfileSwitch => "切换配置档案",
+            HapticCue::PrecisionMode => "进入精确模式",
+            HapticCue::TurboMode => "进入加速模式",
+            HapticCue::Paused => "暂停输出",
+            HapticCue::Resumed => "恢复输出",
+            HapticCue::LowBattery => "电量不足",
+            HapticCue::ScreenEdge => "光标到达屏幕边缘",
+        }
+    }
+
+    /// 控制器事件对应的提示，退出模式等事件没有提示
+    pub fn for_event(event: &ControllerEvent) -> Option<Self> {
+        match event {
+            ControllerEvent::ProfileSwitched(_) => Some(HapticCue::ProfileSwitch),
+            ControllerEvent::ModeChanged { mode: SpeedMode::Precision, active: true } => Some(HapticCue::PrecisionMode),
+            ControllerEvent::ModeChanged { mode: SpeedMode::Turbo, active: true } => Some(HapticCue::TurboMode),
+            ControllerEvent::Paused => Some(HapticCue::Paused),
+            ControllerEvent::Resumed => Some(HapticCue::Resumed),
+            ControllerEvent::ScreenEdge => Some(HapticCue::ScreenEdge),
+            ControllerEvent::BatteryLow(_) => Some(HapticCue::LowBattery),
+            _ => None,
+        }
+    }
+
+    /// 默认的震动方式
+    pub fn default_pattern(self) -> RumblePattern {
+        let pattern = |strong, weak, duration_ms, pulses| RumblePattern { strong, weak, duration_ms, pulses };
+        match self {
+            HapticCue::ProfileSwitch => pattern(0.6, 0.3, 120, 2),
+            HapticCue::PrecisionMode => pattern(0.0, 0.5, 80, 1),
+            HapticCue::TurboMode => pattern(0.7, 0.0, 80, 1),
+            HapticCue::Paused => pattern(0.5, 0.5, 250, 1),
+            HapticCue::Resumed => pattern(0.3, 0.3, 80, 2),
+            HapticCue::LowBattery => pattern(0.8, 0.4, 200, 3),
+            HapticCue::ScreenEdge => pattern(0.0, 0.3, 40, 1),
+        }
+    }
+}
+
+/// 一次震动，强弱两个马达的强度为 0..1，`pulses` 次之间间隔与单次时长相同
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RumblePattern {
+    pub strong: f32,
+    pub weak: f32,
+    pub duration_ms: u32,
+    pub pulses: u8,
+}
+
+impl RumblePattern {
+    /// 从开始到最后一次震动结束的总时长
+    pub fn total_duration(&self) -> Duration {
+        let pulses = self.pulses.max(1) as u32;
+        Duration::from_millis((self.duration_ms * (pulses * 2 - 1)) as u64)
+    }
+}
+
+/// 单个事件的震动设置
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CueSetting {
+    pub enabled: bool,
+    pub pattern: RumblePattern,
+}
+
+/// 震动提示设置
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HapticSettings {
+    pub enabled: bool,
+    pub cues: BTreeMap<HapticCue, CueSetting>,
+}
+
+impl Default for HapticSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cues: HapticCue::ALL.iter()
+                .map(|cue| (*cue, CueSetting {
+                    // 边缘提示在大屏幕上比较频繁，默认关闭
+                    enabled: *cue != HapticCue::ScreenEdge,
+                    pattern: cue.default_pattern(),
+                }))
+                .collect(),
+        }
+    }
+}
+
+impl HapticSettings {
+    /// 事件的设置，缺少时使用默认值
+    pub fn cue(&self, cue: HapticCue) -> CueSetting {
+        self.cues.get(&cue).copied().unwrap_or(CueSetting {
+            enabled: false,
+            pattern: cue.default_pattern(),
+        })
+    }
+
+    pub fn cue_mut(&mut self, cue: HapticCue) -> &mut CueSetting {
+        self.cues.entry(cue).or_insert(CueSetting {
+            enabled: false,
+            pattern: cue.default_pattern(),
+        })
+    }
+}
+
+/// 产生震动的后端
+pub trait HapticsBackend: Send + Sync {
+    fn play(&self, pad: PadId, pattern: RumblePattern) -> Result<(), String>;
+}
+
+/// 通过输入源（gilrs 力反馈）震动
+impl HapticsBackend for Arc<dyn InputSource> {
+    fn play(&self, pad: PadId, pattern: RumblePattern) -> Result<(), String> {
+        self.rumble(pad, pattern)
+    }
+}
+
+/// 只记录震动请求的后端，用于没有手柄时检查触发逻辑
+#[derive(Clone, Default)]
+pub struct MockHaptics {
+    played: Arc<Mutex<Vec<(PadId, RumblePattern)>>>,
+}
+
+impl MockHaptics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 已请求的震动
+    pub fn played(&self) -> Vec<(PadId, RumblePattern)> {
+        self.played.lock().map(|played| played.clone()).unwrap_or_default()
+    }
+}
+
+impl HapticsBackend for MockHaptics {
+    fn play(&self, pad: PadId, pattern: RumblePattern) -> Result<(), String> {
+        if let Ok(mut played) = self.played.lock() {
+            played.push((pad, pattern));
+        }
+        Ok(())
+    }
+}
+
+// 同一手柄同一提示的最短间隔
+const CUE_COOLDOWN: Duration = Duration::from_millis(500);
+
+/// 把总线上的事件转换为震动
+///
+/// 按设置过滤事件，同一手柄的同一提示在冷却时间内只震动一次。
+pub struct CueDispatcher {
+    settings: HapticSettings,
+    backend: Option<Box<dyn HapticsBackend>>,
+    last_played: HashMap<(PadId, HapticCue), Instant>,
+}
+
+impl CueDispatcher {
+    pub fn new(settings: HapticSettings) -> Self {
+        Self {
+            settings,
+            backend: None,
+            last_played: HashMap::new(),
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: HapticSettings) {
+        self.settings = settings;
+    }
+
+    /// 更换震动后端，输入源改变时调用
+    pub fn set_backend(&mut self, backend: Option<Box<dyn HapticsBackend>>) {
+        self.backend = backend;
+        self.last_played.clear();
+    }
+
+    /// 处理一条总线消息，触发震动时返回对应的提示
+    pub fn handle(&mut self, message: &BusMessage, now: Instant) -> Option<HapticCue> {
+        let cue = HapticCue::for_event(&message.event)?;
+        self.trigger(message.pad_id, cue, now).then_some(cue)
+    }
+
+    /// 触发提示，被设置关闭或仍在冷却时返回 false
+    pub fn trigger(&mut self, pad: PadId, cue: HapticCue, now: Instant) -> bool {
+        let setting = self.settings.cue(cue);
+        if !self.settings.enabled || !setting.enabled {
+            return false;
+        }
+        let Some(backend) = &self.backend else {
+            return false;
+        };
+        if self.last_played.get(&(pad, cue)).is_some_and(|last| now.duration_since(*last) < CUE_COOLDOWN) {
+            return false;
+        }
+        self.last_played.insert((pad, cue), now);
+        debug!("震动提示: 手柄 {} {}", pad, cue.display_name());
+        if let Err(e) = backend.play(pad, setting.pattern) {
+            warn!("{}", e);
+        }
+        true
+    }
+}
+
+/// 启动把总线事件转换为震动的订阅线程，总线释放后线程自动退出
+pub fn spawn_cue_subscriber(bus: &EventBus, dispatcher: Arc<Mutex<CueDispatcher>>) {
+    let events = bus.subscribe();
+    thread::spawn(move || {
+        while let Ok(message) = events.recv() {
+            if message.event.is_high_frequency() {
+                continue;
+            }
+            if let Ok(mut dispatcher) = dispatcher.lock() {
+                dispatcher.handle(&message, Instant::now());
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn dispatcher(settings: HapticSettings) -> (CueDispatcher, MockHaptics) {
+        let mock = MockHaptics::new();
+        let mut dispatcher = CueDispatcher::new(settings);
+        dispatcher.set_backend(Some(Box::new(mock.clone())));
+        (dispatcher, mock)
+    }
+
+    #[test]
+    fn enabled_cue_plays_its_pattern() {
+        let mut settings = HapticSettings::default();
+        let pattern = RumblePattern { strong: 1.0, weak: 0.0, duration_ms: 30, pulses: 4 };
+        settings.cue_mut(HapticCue::Paused).pattern = pattern;
+        let (mut dispatcher, mock) = dispatcher(settings);
+
+        assert!(dispatcher.trigger(PadId(2), HapticCue::Paused, Instant::now()));
+        assert_eq!(mock.played(), vec![(PadId(2), pattern)]);
+    }
+
+    #[test]
+    fn disabled_cues_do_not_play() {
+        let mut settings = HapticSettings::default();
+        settings.cue_mut(HapticCue::TurboMode).enabled = false;
+        let (mut dispatcher, mock) = dispatcher(settings.clone());
+        let now = Instant::now();
+
+        assert!(!dispatcher.trigger(PadId(0), HapticCue::TurboMode, now));
+        // 边缘提示默认关闭
+        assert!(!dispatcher.trigger(PadId(0), HapticCue::ScreenEdge, now));
+        assert!(dispatcher.trigger(PadId(0), HapticCue::PrecisionMode, now));
+
+        // 总开关关闭时所有提示都不震动
+        settings.enabled = false;
+        dispatcher.set_settings(settings);
+        assert!(!dispatcher.trigger(PadId(0), HapticCue::Resumed, now));
+        assert_eq!(mock.played().len(), 1);
+
+        // 没有后端时不震动
+        let mut dispatcher = CueDispatcher::new(HapticSettings::default());
+        assert!(!dispatcher.trigger(PadId(0), HapticCue::Resumed, now));
+    }
+
+    #[test]
+    fn cooldown_applies_per_pad_and_cue() {
+        let (mut dispatcher, mock) = dispatcher(HapticSettings::default());
+        let now = Instant::now();
+
+        assert!(dispatcher.trigger(PadId(0), HapticCue::Paused, now));
+        assert!(!dispatcher.trigger(PadId(0), HapticCue::Paused, now + Duration::from_millis(499)));
+        // 其他手柄和其他提示不受冷却影响
+        assert!(dispatcher.trigger(PadId(1), HapticCue::Paused, now + Duration::from_millis(100)));
+        assert!(dispatcher.trigger(PadId(0), HapticCue::Resumed, now + Duration::from_millis(100)));
+        assert!(dispatcher.trigger(PadId(0), HapticCue::Paused, now + CUE_COOLDOWN));
+        assert_eq!(mock.played().len(), 4);
+
+        // 更换后端后冷却重新计算
+        dispatcher.set_backend(Some(Box::new(mock.clone())));
+        assert!(dispatcher.trigger(PadId(0), HapticCue::Paused, now + CUE_COOLDOWN));
+    }
+
+    #[test]
+    fn bus_messages_map_to_cues() {
+        let (mut dispatcher, mock) = dispatcher(HapticSettings::default());
+        let message = |event| BusMessage { time: SystemTime::now(), pad: "手柄".to_string(), pad_id: PadId(3), event };
+        let now = Instant::now();
+
+        let precision = message(ControllerEvent::ModeChanged { mode: SpeedMode::Precision, active: true });
+        assert_eq!(dispatcher.handle(&precision, now), Some(HapticCue::PrecisionMode));
+        let leave = message(ControllerEvent::ModeChanged { mode: SpeedMode::Precision, active: false });
+        assert_eq!(dispatcher.handle(&leave, now), None);
+        assert_eq!(dispatcher.handle(&message(ControllerEvent::BatteryLow(10)), now), Some(HapticCue::LowBattery));
+        assert_eq!(dispatcher.handle(&message(ControllerEvent::MouseMove { dx: 1, dy: 0 }), now), None);
+        assert_eq!(
+            mock.played(),
+            vec![
+                (PadId(3), HapticCue::PrecisionMode.default_pattern()),
+                (PadId(3), HapticCue::LowBattery.default_pattern()),
+            ]
+        );
+    }
+}
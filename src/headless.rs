@@ -0,0 +1,32 @@
+// 不显示窗口的后台运行模式
+use log::{info, warn};
+use std::thread;
+use std::time::Duration;
+
+use crate::cli::CliArgs;
+use crate::gui::GamepadMouseApp;
+use crate::profile::ProfileStore;
+use crate::validation::ValidationIssue;
+
+// 两次处理之间的间隔，与窗口的刷新间隔相同
+const TICK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 不显示窗口运行，直到进程被结束；回放录制文件时在回放结束后退出
+///
+/// 手柄的连接、重连、档案切换和配置文件热重载与窗口模式相同，状态只输出到日志。
+pub fn run(cli: CliArgs, profiles: ProfileStore, config_issues: Vec<ValidationIssue>) {
+    for issue in &config_issues {
+        warn!("配置检查: {}", issue);
+    }
+    let replay = cli.session.replay.is_some();
+    let mut app = GamepadMouseApp::without_window(cli, profiles, config_issues);
+    info!("已在后台运行，结束进程即可退出");
+    loop {
+        app.tick();
+        if replay && app.replay_finished() {
+            info!("回放结束，程序退出");
+            break;
+        }
+        thread::sleep(TICK_INTERVAL);
+    }
+}
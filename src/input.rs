@@ -0,0 +1,433 @@
+// 手柄输入源
+use gilrs::{Axis, Button, MappingSource};
+use log::{info, error};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::controller_model::{ControllerModel, DetectedController};
+use crate::device_manager::{DeviceEvent, DeviceInfo, DeviceKey, PadId, PadState};
+use crate::haptics::RumblePattern;
+use crate::sdl_mapping::{MappedInput, MappingTarget};
+
+/// 输入源共享的设备登记表
+///
+/// 记录手柄列表、每个手柄的按钮和摇杆状态、事件订阅者以及被控制器占用的手柄。
+/// 输入源负责写入，界面和控制线程通过 `InputSource` 读取。
+#[derive(Clone, Default)]
+pub struct DeviceRegistry {
+    devices: Arc<Mutex<Vec<DeviceInfo>>>,
+    states: Arc<Mutex<HashMap<PadId, PadState>>>,
+    subscribers: Arc<Mutex<Vec<Sender<DeviceEvent>>>>,
+    claimed: Arc<Mutex<HashSet<PadId>>>,
+}
+
+impl DeviceRegistry {
+    pub fn devices(&self) -> Vec<DeviceInfo> {
+        self.devices.lock().map(|devices| devices.clone()).unwrap_or_default()
+    }
+
+    pub fn set_devices(&self, list: Vec<DeviceInfo>) {
+        if let Ok(mut devices) = self.devices.lock() {
+            *devices = list;
+        }
+    }
+
+    /// 添加或替换手柄并广播连接事件
+    pub fn add_device(&self, device: DeviceInfo) {
+        let id = device.id;
+        let list = {
+            let Ok(mut devices) = self.devices.lock() else {
+                return;
+            };
+            devices.retain(|existing| existing.id != id);
+            devices.push(device);
+            devices.clone()
+        };
+        self.update_states(|states| states.entry(id).or_default().connected = true);
+        self.broadcast(DeviceEvent::Connected(id));
+        self.broadcast(DeviceEvent::DevicesChanged(list));
+    }
+
+    /// 移除手柄并广播断开事件
+    pub fn remove_device(&self, id: PadId) {
+        let list = {
+            let Ok(mut devices) = self.devices.lock() else {
+                return;
+            };
+            devices.retain(|existing| existing.id != id);
+            devices.clone()
+        };
+        self.update_states(|states| {
+            if let Some(state) = states.get_mut(&id) {
+                *state = PadState::default();
+            }
+        });
+        self.broadcast(DeviceEvent::Disconnected(id));
+        self.broadcast(DeviceEvent::DevicesChanged(list));
+    }
+
+    /// 更新按钮状态并广播按键事件
+    pub fn set_button(&self, id: PadId, button: Button, pressed: bool) {
+        self.update_states(|states| states.entry(id).or_default().set_pressed(button, pressed));
+        let event = if pressed {
+            DeviceEvent::ButtonPressed(id, button)
+        } else {
+            DeviceEvent::ButtonReleased(id, button)
+        };
+        self.broadcast(event);
+    }
+
+    pub fn set_axis(&self, id: PadId, axis: Axis, value: f32) {
+        self.update_states(|states| states.entry(id).or_default().set_value(axis, value));
+    }
+
+    pub fn state(&self, id: PadId) -> Option<PadState> {
+        self.states.lock().ok().and_then(|states| states.get(&id).cloned())
+    }
+
+    /// 修改手柄状态
+    pub fn update_states(&self, update: impl FnOnce(&mut HashMap<PadId, PadState>)) {
+        if let Ok(mut states) = self.states.lock() {
+            update(&mut states);
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<DeviceEvent> {
+        let (tx, rx) = mpsc::channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(tx);
+        }
+        rx
+    }
+
+    /// 把事件发送给所有订阅者，顺便移除已关闭的订阅
+    pub fn broadcast(&self, event: DeviceEvent) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        }
+    }
+
+    pub fn claim(&self, id: PadId) -> bool {
+        self.claimed.lock().map(|mut claimed| claimed.insert(id)).unwrap_or(false)
+    }
+
+    pub fn release(&self, id: PadId) {
+        if let Ok(mut claimed) = self.claimed.lock() {
+            claimed.remove(&id);
+        }
+    }
+
+    pub fn is_claimed(&self, id: PadId) -> bool {
+        self.claimed.lock().is_ok_and(|claimed| claimed.contains(&id))
+    }
+}
+
+/// 配置中选择的输入后端
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputBackend {
+    Gilrs,  // 通过 gilrs 读取，使用 SDL 映射
+    Evdev,  // 直接读取 /dev/input/event*，使用自定义键码映射
+}
+
+impl InputBackend {
+    /// 当前平台可用的后端
+    #[cfg(target_os = "linux")]
+    pub const AVAILABLE: &'static [InputBackend] = &[InputBackend::Gilrs, InputBackend::Evdev];
+    #[cfg(not(target_os = "linux"))]
+    pub const AVAILABLE: &'static [InputBackend] = &[InputBackend::Gilrs];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            InputBackend::Gilrs => "gilrs（标准手柄）",
+            InputBackend::Evdev => "evdev（自定义键码映射）",
+        }
+    }
+
+    /// 启动该后端的输入源
+    pub fn start(self) -> Result<Arc<dyn InputSource>, String> {
+        match self {
+            InputBackend::Gilrs => Ok(crate::device_manager::DeviceManager::start()?),
+            #[cfg(target_os = "linux")]
+            InputBackend::Evdev => {
+                let mappings = crate::evdev::EvdevMapping::load_all();
+                Ok(crate::evdev::EvdevSource::start(mappings)?)
+            }
+            #[cfg(not(target_os = "linux"))]
+            InputBackend::Evdev => Err("evdev 只在 Linux 上可用".to_string()),
+        }
+    }
+}
+
+/// 手柄输入源
+///
+/// 提供手柄的连接和按钮事件（`subscribe`）以及按钮和摇杆的状态快照（`state`）。
+/// 真实手柄由 `DeviceManager` 提供，`ScriptedSource` 按脚本或录制的会话产生输入，
+/// 没有手柄的机器上也可以驱动完整的控制流程。
+pub trait InputSource: Send + Sync {
+    /// 输入源写入的设备登记表
+    fn registry(&self) -> &DeviceRegistry;
+
+    /// 停止输入源的后台线程
+    fn stop(&self);
+
+    /// 脚本或转储等有限的输入是否已经结束
+    fn is_finished(&self) -> bool {
+        false
+    }
+
+    /// 手柄最近的原始输入，帮助用户编写映射
+    fn last_raw_input(&self, _id: PadId) -> Option<String> {
+        None
+    }
+
+    /// 让手柄按指定方式震动
+    fn rumble(&self, id: PadId, _pattern: RumblePattern) -> Result<(), String> {
+        Err(format!("当前输入方式不支持手柄 {} 震动", id))
+    }
+
+    /// 是否把原始键码作为 `DeviceEvent::RawInput` 广播，供映射向导使用
+    fn set_raw_capture(&self, _enabled: bool) {}
+
+    /// 为手柄设置映射，成功时返回 SDL 映射字符串
+    fn apply_mapping(&self, _id: PadId, _mapping: &[(MappingTarget, MappedInput)], _name: &str) -> Result<String, String> {
+        Err("当前输入方式不支持 SDL 映射".to_string())
+    }
+
+    /// 当前连接的手柄列表
+    fn devices(&self) -> Vec<DeviceInfo> {
+        self.registry().devices()
+    }
+
+    /// 查找已连接的手柄
+    fn device(&self, id: PadId) -> Option<DeviceInfo> {
+        self.devices().into_iter().find(|device| device.id == id)
+    }
+
+    /// 手柄的最新状态，未知的手柄返回 None
+    fn state(&self, id: PadId) -> Option<PadState> {
+        self.registry().state(id)
+    }
+
+    /// 订阅设备事件，接收端被丢弃后自动取消订阅
+    fn subscribe(&self) -> Receiver<DeviceEvent> {
+        self.registry().subscribe()
+    }
+
+    /// 标记手柄被控制器占用，已被占用时返回 false
+    fn claim(&self, id: PadId) -> bool {
+        self.registry().claim(id)
+    }
+
+    /// 释放控制器占用的手柄
+    fn release(&self, id: PadId) {
+        self.registry().release(id)
+    }
+
+    fn is_claimed(&self, id: PadId) -> bool {
+        self.registry().is_claimed(id)
+    }
+
+    /// 按稳定标识查找未被占用的手柄
+    ///
+    /// 两个同型号手柄的 UUID 和名称可能相同，跳过已被占用的手柄可以让它们各自找回一个。
+    fn find_unclaimed(&self, key: &DeviceKey) -> Option<DeviceInfo> {
+        self.unclaimed_devices().into_iter().find(|device| device.key() == *key)
+    }
+
+    /// 未被任何控制器占用的手柄
+    fn unclaimed_devices(&self) -> Vec<DeviceInfo> {
+        self.devices().into_iter().filter(|device| !self.is_claimed(device.id)).collect()
+    }
+}
+
+/// 脚本中的一个输入动作
+#[derive(Clone, Debug)]
+pub enum ScriptAction {
+    Connect(DeviceInfo),
+    Disconnect(PadId),
+    Press(PadId, Button),
+    Release(PadId, Button),
+    Axis(PadId, Axis, f32),
+}
+
+/// 在 `at` 时刻（从开始播放算起）执行的动作
+#[derive(Clone, Debug)]
+pub struct ScriptStep {
+    pub at: Duration,
+    pub action: ScriptAction,
+}
+
+/// 按脚本产生输入的输入源
+///
+/// 在后台线程中按时间执行脚本，效果与真实手柄相同：更新登记表中的状态并广播事件。
+/// 脚本结束后手柄保持最后的状态，直到调用 `stop`。
+pub struct ScriptedSource {
+    registry: DeviceRegistry,
+    running: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    thread_handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ScriptedSource {
+    /// 开始播放脚本，步骤需要按时间排序
+    pub fn start(steps: Vec<ScriptStep>) -> Arc<Self> {
+        let registry = DeviceRegistry::default();
+        let running = Arc::new(AtomicBool::new(true));
+        let finished = Arc::new(AtomicBool::new(false));
+
+        let registry_thread = registry.clone();
+        let running_thread = running.clone();
+        let finished_thread = finished.clone();
+        let thread_handle = thread::spawn(move || {
+            info!("开始播放输入脚本，共 {} 步", steps.len());
+            let start = Instant::now();
+            for step in steps {
+                // 分段等待，以便及时响应停止
+                loop {
+                    if !running_thread.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let elapsed = start.elapsed();
+                    if elapsed >= step.at {
+                        break;
+                    }
+                    thread::sleep((step.at - elapsed).min(Duration::from_millis(20)));
+                }
+                apply(&registry_thread, step.action);
+            }
+            finished_thread.store(true, Ordering::Relaxed);
+            info!("输入脚本播放完毕");
+        });
+
+        Arc::new(Self {
+            registry,
+            running,
+            finished,
+            thread_handle: Mutex::new(Some(thread_handle)),
+        })
+    }
+
+}
+
+impl InputSource for ScriptedSource {
+    fn registry(&self) -> &DeviceRegistry {
+        &self.registry
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+
+    fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.thread_handle.lock().ok().and_then(|mut handle| handle.take())
+            && let Err(e) = handle.join()
+        {
+            error!("停止输入脚本时发生错误: {:?}", e);
+        }
+    }
+}
+
+impl Drop for ScriptedSource {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 创建脚本中使用的手柄，型号按名称识别
+pub fn scripted_device(id: usize, name: &str, uuid: [u8; 16]) -> DeviceInfo {
+    let model = ControllerModel::classify(name, None, None);
+    DeviceInfo {
+        id: PadId(id),
+        name: name.to_string(),
+        uuid,
+        detected: DetectedController {
+            model,
+            mapping_source: MappingSource::None,
+            swap_face_buttons: false,
+        },
+    }
+}
+
+/// 执行一个脚本动作
+fn apply(registry: &DeviceRegistry, action: ScriptAction) {
+    match action {
+        ScriptAction::Connect(device) => registry.add_device(device),
+        ScriptAction::Disconnect(id) => registry.remove_device(id),
+        ScriptAction::Press(id, button) => registry.set_button(id, button, true),
+        ScriptAction::Release(id, button) => registry.set_button(id, button, false),
+        ScriptAction::Axis(id, axis, value) => registry.set_axis(id, axis, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::event_bus::{ControllerEvent, EventBus, MouseAction};
+    use crate::gamepad_controller::GamepadController;
+    use crate::output::{OutputAction, RecordingSink};
+
+    fn step(millis: u64, action: ScriptAction) -> ScriptStep {
+        ScriptStep { at: Duration::from_millis(millis), action }
+    }
+
+    /// 脚本输入经过控制器产生输出，手柄断开后以新的编号重新连接时控制器自动找回
+    #[test]
+    fn scripted_input_drives_controller_end_to_end() {
+        let first = scripted_device(0, "Xbox Wireless Controller", [9; 16]);
+        let mut second = first.clone();
+        second.id = PadId(1);
+        let source = ScriptedSource::start(vec![
+            step(0, ScriptAction::Connect(first.clone())),
+            step(50, ScriptAction::Press(PadId(0), Button::South)),
+            step(80, ScriptAction::Release(PadId(0), Button::South)),
+            step(100, ScriptAction::Axis(PadId(0), Axis::LeftStickX, 1.0)),
+            step(200, ScriptAction::Axis(PadId(0), Axis::LeftStickX, 0.0)),
+            step(300, ScriptAction::Disconnect(PadId(0))),
+            step(400, ScriptAction::Connect(second)),
+            step(450, ScriptAction::Axis(PadId(1), Axis::LeftStickX, 1.0)),
+        ]);
+        let bus = EventBus::new();
+        let events = bus.subscribe();
+        let sink = RecordingSink::new();
+        let log = sink.log();
+        let mut controller = GamepadController::with_output(source.clone(), bus.clone(), first, Config::default(), sink.factory());
+
+        // 等待控制器找回手柄后继续输出
+        let mut seen = Vec::new();
+        let mut output_at_reconnect = None;
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            seen.extend(events.try_iter().map(|message| message.event));
+            let outputs = log.lock().unwrap().len();
+            match output_at_reconnect {
+                None if seen.contains(&ControllerEvent::Connected) => output_at_reconnect = Some(outputs),
+                Some(at) if outputs > at => break,
+                _ => {}
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        controller.stop();
+        source.stop();
+
+        let actions: Vec<OutputAction> = log.lock().unwrap().iter().map(|output| output.action).collect();
+        let at = output_at_reconnect.expect("控制器没有找回重新连接的手柄");
+        assert_eq!(actions[..2], [OutputAction::ButtonDown(MouseAction::Left), OutputAction::ButtonUp(MouseAction::Left)]);
+        assert!(actions[2..at].iter().any(|action| matches!(action, OutputAction::MoveRelative { dx, .. } if *dx > 0)));
+        assert!(
+            actions[at..].iter().any(|action| matches!(action, OutputAction::MoveRelative { dx, .. } if *dx > 0)),
+            "重新连接后没有移动光标: {:?}",
+            &actions[at..]
+        );
+        let disconnected = seen.iter().position(|event| *event == ControllerEvent::Disconnected);
+        let connected = seen.iter().position(|event| *event == ControllerEvent::Connected);
+        assert!(matches!((disconnected, connected), (Some(lost), Some(found)) if lost < found), "{:?}", seen);
+        assert_eq!(controller.device().id, PadId(1));
+    }
+}
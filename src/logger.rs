@@ -21,10 +21,12 @@ pub struct EnhancedLogger {
 }
 
 impl EnhancedLogger {
-    /// 创建新的增强型日志器
-    pub fn new(log_level: LevelFilter) -> Result<Self, Error> {
+    /// 创建新的增强型日志器，`log_directory` 为空时使用本地数据目录下的 logs 文件夹
+    pub fn new(log_level: LevelFilter, log_directory: Option<PathBuf>) -> Result<Self, Error> {
         // 确定日志目录
-        let log_directory = if let Some(app_dir) = dirs::data_local_dir() {
+        let log_directory = if let Some(dir) = log_directory {
+            dir
+        } else if let Some(app_dir) = dirs::data_local_dir() {
             let mut dir = app_dir;
             dir.push("GamepadMouseControl");
             dir.push("logs");
@@ -69,17 +71,24 @@ impl EnhancedLogger {
     
     /// 初始化日志系统
     pub fn init(self) -> Result<(), log::SetLoggerError> {
-        // 使用 simple_logger 处理控制台输出
-        SimpleLogger::new()
-            .with_level(self.log_level)
-            .init()?;
+        let log_level = self.log_level;
         
-        // 注册我们自己的日志处理器来处理文件输出
-        log::set_max_level(self.log_level);
+        // 使用 simple_logger 处理控制台输出，我们自己的日志处理器处理文件输出
+        let console = SimpleLogger::new().with_level(log_level);
+        log::set_boxed_logger(Box::new(CombinedLogger {
+            console,
+            file: FileLogger::new(self),
+        }))?;
+        log::set_max_level(log_level);
         
         Ok(())
     }
     
+    /// 当前日志文件的路径
+    pub fn log_path(&self) -> &Path {
+        &self.current_log_path
+    }
+    
     /// 检查日志文件大小并在必要时进行轮转
     fn rotate_log_if_needed(&self) -> Result<(), Error> {
         let file_lock = self.log_file.lock().unwrap();
@@ -187,16 +196,39 @@ impl log::Log for FileLogger {
     }
 }
 
-/// 实现增强日志初始化功能
-pub fn initialize_enhanced_logging(level: Option<LevelFilter>) -> Result<(), String> {
+/// 同时输出到控制台和日志文件
+struct CombinedLogger {
+    console: SimpleLogger,
+    file: FileLogger,
+}
+
+impl log::Log for CombinedLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.console.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.console.log(record);
+        self.file.log(record);
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+        self.file.flush();
+    }
+}
+
+/// 实现增强日志初始化功能，`log_dir` 为空时使用默认日志目录
+pub fn initialize_enhanced_logging(level: Option<LevelFilter>, log_dir: Option<PathBuf>) -> Result<(), String> {
     let log_level = level.unwrap_or(LevelFilter::Info);
     
     // 创建增强型日志器
-    match EnhancedLogger::new(log_level) {
+    match EnhancedLogger::new(log_level, log_dir) {
         Ok(logger) => {
+            let log_path = logger.log_path().to_path_buf();
             match logger.init() {
                 Ok(_) => {
-                    log::info!("增强型日志系统已初始化（级别：{:?}）", log_level);
+                    log::info!("增强型日志系统已初始化（级别：{:?}），日志文件: {:?}", log_level, log_path);
                     Ok(())
                 },
                 Err(e) => Err(format!("无法初始化日志系统: {}", e))
@@ -1,20 +1,67 @@
+mod app_focus;
+mod axis_remap;
+mod battery;
+mod cli;
 mod config;
+mod config_backup;
+mod config_format;
+mod config_watch;
+mod connection;
+mod controller_model;
+mod device_manager;
+mod event_bus;
+#[cfg(target_os = "linux")]
+mod evdev;
 mod gamepad_controller;
 mod gui;
+mod haptics;
+mod headless;
+mod input;
 mod logger;
+mod migration;
+mod multi_pad;
+mod output;
+mod presets;
+mod profile;
+mod sdl_mapping;
+mod session;
+#[cfg(target_os = "linux")]
+mod uinput;
+mod validation;
 mod embedded_font;
 
 use eframe::egui;
 use gui::GamepadMouseApp;
+use cli::{CliArgs, CliCommand, USAGE};
+use config::Config;
 use log::{info, error, LevelFilter};
 use logger::initialize_enhanced_logging;
+use profile::ProfileStore;
 
 fn main() -> Result<(), eframe::Error> {
+    // 解析命令行参数
+    let cli = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(CliCommand::Run(cli)) => cli,
+        Ok(CliCommand::Help) => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if cli.no_gui && cli.session.record.is_some() {
+        eprintln!("--record 需要在窗口中结束录制，不能与 --no-gui 同时使用");
+        std::process::exit(2);
+    }
+    
     // 初始化增强型日志系统
-    if let Err(e) = initialize_enhanced_logging(Some(LevelFilter::Info)) {
+    let log_level = cli.log_level.unwrap_or(LevelFilter::Info);
+    if let Err(e) = initialize_enhanced_logging(Some(log_level), cli.log_dir.clone()) {
         eprintln!("警告：无法初始化增强型日志系统：{}，尝试使用简单日志系统", e);
         // 回退到简单日志系统
-        if let Err(e) = logger::initialize_simple_logging(Some(LevelFilter::Info)) {
+        if let Err(e) = logger::initialize_simple_logging(Some(log_level)) {
             eprintln!("警告：无法初始化日志系统：{}", e);
         }
     }
@@ -42,6 +89,28 @@ fn main() -> Result<(), eframe::Error> {
         eprintln!("程序遇到了一个错误。错误信息已记录到日志文件中。请重新启动应用程序。");
     }));
     
+    // 命令行指定的配置文件，转为绝对路径以免工作目录影响备份位置
+    if let Some(path) = &cli.config {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.clone());
+        info!("使用命令行指定的配置文件: {:?}", path);
+        Config::set_config_path(path);
+    }
+    
+    // 加载配置档案，命令行指定的档案不存在时列出可用的档案并退出
+    let (mut profiles, config_issues) = ProfileStore::load();
+    if let Some(name) = &cli.profile
+        && let Err(e) = profiles.set_active_override(name)
+    {
+        let names: Vec<&str> = profiles.profiles.iter().map(|profile| profile.name.as_str()).collect();
+        eprintln!("{}，可用的档案: {}", e, names.join("、"));
+        std::process::exit(2);
+    }
+    
+    if cli.no_gui {
+        headless::run(cli, profiles, config_issues);
+        return Ok(());
+    }
+    
     // 设置环境选项
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -58,6 +127,6 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "游戏手柄鼠标控制器",
         options,
-        Box::new(|cc| Box::new(GamepadMouseApp::new(cc))),
+        Box::new(move |cc| Box::new(GamepadMouseApp::new(cc, cli, profiles, config_issues))),
     )
 }
@@ -0,0 +1,203 @@
+// 配置文件版本和迁移
+//
+// 配置文件先解析为 JSON 值，按版本逐步迁移到当前格式，再用默认配置补齐缺少的字段，
+// 最后才反序列化为 `ProfileStore`。新增字段因此不会让旧配置解析失败，类型不对的值也只恢复该项的默认值。
+use log::{info, warn};
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+use crate::profile::{ProfileStore, DEFAULT_PROFILE_NAME};
+use crate::validation::{self, ValidationIssue};
+
+/// 当前的配置文件版本
+///
+/// - 0：只包含单个 `Config` 的旧版配置
+/// - 1：配置档案库，没有 `version` 字段
+/// - 2：带 `version` 字段的配置档案库
+pub const CONFIG_VERSION: u32 = 2;
+
+/// 把某个版本的配置迁移到下一个版本
+type Migration = fn(Value) -> Result<Value, String>;
+
+// 下标为迁移前的版本
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+];
+
+/// 迁移后的配置
+pub struct Migrated {
+    pub store: ProfileStore,
+    /// 文件原来的版本
+    pub from_version: u32,
+    /// 类型错误、已恢复为默认值的项
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// 把解析得到的配置迁移到当前版本并补齐默认值
+pub fn load_store(value: Value) -> Result<Migrated, String> {
+    let from_version = detect_version(&value)?;
+    if from_version > CONFIG_VERSION {
+        warn!("配置文件版本 {} 比程序支持的版本 {} 新，未知的设置会被忽略", from_version, CONFIG_VERSION);
+    }
+
+    let mut value = value;
+    for version in from_version..CONFIG_VERSION {
+        value = MIGRATIONS[version as usize](value)?;
+        info!("配置文件已从版本 {} 迁移到版本 {}", version, version + 1);
+    }
+
+    fill_store_defaults(&mut value)?;
+    let issues = validation::repair_value(&mut value)?;
+    let mut store: ProfileStore = serde_json::from_value(value).map_err(|e| format!("配置文件格式错误: {}", e))?;
+    store.version = CONFIG_VERSION;
+    Ok(Migrated { store, from_version, issues })
+}
+
+/// 判断配置的版本，没有 `version` 字段时按内容推断
+fn detect_version(value: &Value) -> Result<u32, String> {
+    let Some(object) = value.as_object() else {
+        return Err("配置文件顶层不是对象".to_string());
+    };
+    match object.get("version") {
+        Some(version) => version.as_u64()
+            .map(|version| version as u32)
+            .ok_or_else(|| format!("配置文件版本无效: {}", version)),
+        None if object.contains_key("profiles") => Ok(1),
+        None => Ok(0),
+    }
+}
+
+/// 旧版单个配置导入为默认档案
+fn migrate_v0_to_v1(value: Value) -> Result<Value, String> {
+    let mut store = Map::new();
+    store.insert("active_profile".to_string(), Value::from(DEFAULT_PROFILE_NAME));
+    store.insert("profiles".to_string(), Value::Array(vec![serde_json::json!({
+        "name": DEFAULT_PROFILE_NAME,
+        "config": value,
+    })]));
+    Ok(Value::Object(store))
+}
+
+/// 增加版本字段
+fn migrate_v1_to_v2(mut value: Value) -> Result<Value, String> {
+    if let Some(object) = value.as_object_mut() {
+        object.insert("version".to_string(), Value::from(2));
+    }
+    Ok(value)
+}
+
+/// 用默认档案库和默认配置补齐缺少的字段
+fn fill_store_defaults(value: &mut Value) -> Result<(), String> {
+    let store_defaults = serde_json::to_value(ProfileStore::default()).map_err(|e| format!("默认配置序列化失败: {}", e))?;
+    let config_defaults = serde_json::to_value(Config::default()).map_err(|e| format!("默认配置序列化失败: {}", e))?;
+
+    // 档案列表是数组，不参与整体合并，逐个档案补齐配置
+    if let Some(profiles) = value.get_mut("profiles").and_then(Value::as_array_mut) {
+        for profile in profiles {
+            if let Some(config) = profile.get_mut("config") {
+                merge_defaults(config, &config_defaults);
+            }
+        }
+    }
+    merge_defaults(value, &store_defaults);
+    Ok(())
+}
+
+/// 把 `defaults` 中缺少的键递归补到 `value` 中，已有的值保持不变
+fn merge_defaults(value: &mut Value, defaults: &Value) {
+    let (Some(object), Some(defaults)) = (value.as_object_mut(), defaults.as_object()) else {
+        return;
+    };
+    for (key, default) in defaults {
+        match object.get_mut(key) {
+            Some(existing) => merge_defaults(existing, default),
+            None => {
+                object.insert(key.clone(), default.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn detects_version_from_field_or_content() {
+        assert_eq!(detect_version(&json!({"mouse_sensitivity": 3.0})), Ok(0));
+        assert_eq!(detect_version(&json!({"active_profile": "默认", "profiles": []})), Ok(1));
+        assert_eq!(detect_version(&json!({"version": 2, "profiles": []})), Ok(2));
+        assert_eq!(detect_version(&json!({"version": 7})), Ok(7));
+        assert!(detect_version(&json!({"version": "2"})).is_err());
+        assert!(detect_version(&json!({"version": -1})).is_err());
+        assert!(detect_version(&json!([1, 2])).is_err());
+        assert!(load_store(json!("config")).is_err());
+    }
+
+    #[test]
+    fn migrates_single_config_to_default_profile() {
+        let migrated = load_store(json!({"mouse_sensitivity": 3.5, "dead_zone": 0.2})).unwrap();
+        assert_eq!(migrated.from_version, 0);
+        let store = migrated.store;
+        assert_eq!(store.version, CONFIG_VERSION);
+        assert_eq!(store.active_profile, DEFAULT_PROFILE_NAME);
+        assert_eq!(store.profiles.len(), 1);
+        assert_eq!(store.profiles[0].name, DEFAULT_PROFILE_NAME);
+        assert_eq!(store.profiles[0].config.mouse_sensitivity, 3.5);
+        assert_eq!(store.profiles[0].config.dead_zone, 0.2);
+        // 旧配置中没有的字段使用默认值
+        assert_eq!(store.profiles[0].config.left_click_button, Config::default().left_click_button);
+    }
+
+    #[test]
+    fn loads_store_with_and_without_version() {
+        let v1 = json!({
+            "active_profile": "游戏",
+            "profiles": [{"name": "游戏", "config": {"mouse_sensitivity": 8.0}}],
+            "auto_switch_enabled": true,
+        });
+        let migrated = load_store(v1.clone()).unwrap();
+        assert_eq!(migrated.from_version, 1);
+        assert_eq!(migrated.store.active_profile, "游戏");
+        assert!(migrated.store.auto_switch_enabled);
+        assert_eq!(migrated.store.profiles[0].config.mouse_sensitivity, 8.0);
+
+        let mut v2 = v1;
+        v2["version"] = json!(2);
+        let migrated = load_store(v2).unwrap();
+        assert_eq!(migrated.from_version, 2);
+        assert_eq!(migrated.store.active_profile, "游戏");
+
+        // 较新的版本照常读取，保留原版本号供调用方判断
+        let migrated = load_store(json!({"version": 9, "profiles": [], "future_setting": 1})).unwrap();
+        assert_eq!(migrated.from_version, 9);
+        assert_eq!(migrated.store.version, CONFIG_VERSION);
+    }
+
+    #[test]
+    fn fills_missing_nested_fields() {
+        let store = load_store(json!({
+            "version": 2,
+            "profiles": [{"name": "默认", "config": {}}],
+            "battery": {"threshold": 10},
+        })).unwrap().store;
+        assert_eq!(store.battery.threshold, 10);
+        assert_eq!(store.battery.warn_enabled, crate::battery::BatterySettings::default().warn_enabled);
+        assert_eq!(store.profiles[0].config.mouse_sensitivity, Config::default().mouse_sensitivity);
+        assert_eq!(store.backup_count, ProfileStore::default().backup_count);
+    }
+
+    #[test]
+    fn merge_defaults_keeps_existing_values() {
+        let mut value = json!({"a": 1, "nested": {"b": 2}, "list": [1]});
+        merge_defaults(&mut value, &json!({"a": 0, "c": 3, "nested": {"b": 0, "d": 4}, "list": [0, 0]}));
+        assert_eq!(value, json!({"a": 1, "c": 3, "nested": {"b": 2, "d": 4}, "list": [1]}));
+
+        // 不是对象时保持原样
+        let mut value = json!(5);
+        merge_defaults(&mut value, &json!({"a": 1}));
+        assert_eq!(value, json!(5));
+    }
+}
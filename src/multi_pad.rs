@@ -0,0 +1,153 @@
+// 多手柄协作设置
+use enigo::Key;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// 多个手柄同时连接时的协作方式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultiPadPolicy {
+    SharedCursor,   // 所有手柄共同控制同一个光标
+    SeparateRoles,  // 每个手柄有自己的角色（鼠标或键盘）
+    Handover,       // 最近操作的手柄获得控制权
+}
+
+impl MultiPadPolicy {
+    pub const ALL: [MultiPadPolicy; 3] = [
+        MultiPadPolicy::SharedCursor,
+        MultiPadPolicy::SeparateRoles,
+        MultiPadPolicy::Handover,
+    ];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            MultiPadPolicy::SharedCursor => "共享光标",
+            MultiPadPolicy::SeparateRoles => "分配角色",
+            MultiPadPolicy::Handover => "交接控制",
+        }
+    }
+}
+
+/// 手柄角色
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PadRole {
+    Pointer,   // 控制鼠标
+    Keyboard,  // 模拟方向键和常用按键
+}
+
+impl PadRole {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            PadRole::Pointer => "鼠标",
+            PadRole::Keyboard => "键盘",
+        }
+    }
+}
+
+// 尚无手柄取得控制权
+const NO_OWNER: usize = usize::MAX;
+
+/// 交接模式下分配控制权令牌
+pub struct HandoverArbiter {
+    owner: Arc<AtomicUsize>,
+    next_id: usize,
+}
+
+impl HandoverArbiter {
+    pub fn new() -> Self {
+        Self {
+            owner: Arc::new(AtomicUsize::new(NO_OWNER)),
+            next_id: 0,
+        }
+    }
+
+    /// 为新手柄分配令牌
+    pub fn token(&mut self) -> HandoverToken {
+        let id = self.next_id;
+        self.next_id += 1;
+        HandoverToken {
+            owner: self.owner.clone(),
+            id,
+        }
+    }
+}
+
+/// 手柄持有的控制权令牌
+#[derive(Clone)]
+pub struct HandoverToken {
+    owner: Arc<AtomicUsize>,
+    id: usize,
+}
+
+impl HandoverToken {
+    /// 手柄有输入时取得控制权
+    pub fn claim(&self) {
+        self.owner.store(self.id, Ordering::Relaxed);
+    }
+
+    pub fn has_control(&self) -> bool {
+        let owner = self.owner.load(Ordering::Relaxed);
+        owner == self.id || owner == NO_OWNER
+    }
+}
+
+/// 单个手柄在多手柄模式下的协作设置
+#[derive(Clone)]
+pub struct PadCoordination {
+    pub role: PadRole,
+    pub handover: Option<HandoverToken>,
+}
+
+impl Default for PadCoordination {
+    fn default() -> Self {
+        Self {
+            role: PadRole::Pointer,
+            handover: None,
+        }
+    }
+}
+
+impl PadCoordination {
+    /// 手柄有输入时调用，交接模式下取得控制权
+    pub fn claim(&self) {
+        if let Some(token) = &self.handover {
+            token.claim();
+        }
+    }
+
+    /// 当前是否允许该手柄输出
+    pub fn has_control(&self) -> bool {
+        self.handover.as_ref().is_none_or(|token| token.has_control())
+    }
+}
+
+/// 键盘角色下按钮对应的按键
+pub fn keyboard_key_for_button(button_name: &str) -> Option<Key> {
+    match button_name {
+        "South" => Some(Key::Return),
+        "East" => Some(Key::Escape),
+        "West" => Some(Key::Backspace),
+        "North" => Some(Key::Space),
+        "RightTrigger" => Some(Key::Tab),
+        "DPadUp" => Some(Key::UpArrow),
+        "DPadDown" => Some(Key::DownArrow),
+        "DPadLeft" => Some(Key::LeftArrow),
+        "DPadRight" => Some(Key::RightArrow),
+        _ => None,
+    }
+}
+
+/// 键盘角色下摇杆方向对应的方向键，摇杆超过阈值时返回
+pub fn keyboard_key_for_stick(x: f32, y: f32, threshold: f32) -> Option<Key> {
+    if x.abs() < threshold && y.abs() < threshold {
+        return None;
+    }
+    // 取偏移较大的方向，gilrs 中 Y 轴向上为正
+    Some(if x.abs() > y.abs() {
+        if x > 0.0 { Key::RightArrow } else { Key::LeftArrow }
+    } else if y > 0.0 {
+        Key::UpArrow
+    } else {
+        Key::DownArrow
+    })
+}
@@ -0,0 +1,283 @@
+// 鼠标和键盘输出后端
+use enigo::{Enigo, Key, KeyboardControllable, MouseButton, MouseControllable};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::event_bus::MouseAction;
+
+/// 鼠标和键盘的输出端
+///
+/// 控制线程只通过这个接口产生输出，真实的光标由 `EnigoSink` 驱动，
+/// 试运行和测试使用 `RecordingSink` 记录输出而不移动光标。
+pub trait OutputSink {
+    fn move_relative(&mut self, dx: i32, dy: i32);
+    fn move_to(&mut self, x: i32, y: i32);
+    /// 当前光标位置
+    fn location(&self) -> (i32, i32);
+    fn button_down(&mut self, button: MouseAction);
+    fn button_up(&mut self, button: MouseAction);
+    /// 滚动，`horizontal` 为 true 时水平滚动
+    fn scroll(&mut self, horizontal: bool, amount: i32);
+    fn key_down(&mut self, key: Key);
+    fn key_up(&mut self, key: Key);
+
+    fn click(&mut self, button: MouseAction) {
+        self.button_down(button);
+        self.button_up(button);
+    }
+
+    fn key_tap(&mut self, key: Key) {
+        self.key_down(key);
+        self.key_up(key);
+    }
+
+    /// 是否支持鼠标侧键
+    fn supports_side_buttons(&self) -> bool {
+        true
+    }
+}
+
+/// 在控制线程中创建输出端
+///
+/// enigo 的上下文不能跨线程传递，因此传入的是创建函数而不是输出端本身。
+pub type SinkFactory = Box<dyn FnOnce() -> Box<dyn OutputSink> + Send>;
+
+/// 默认的输出端创建函数
+pub fn enigo_factory() -> SinkFactory {
+    Box::new(|| Box::new(EnigoSink::new()) as Box<dyn OutputSink>)
+}
+
+/// 配置中选择的输出后端
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputBackend {
+    Enigo,   // X11、Windows 和 macOS
+    Uinput,  // Linux 虚拟输入设备，支持 Wayland 和终端
+}
+
+impl OutputBackend {
+    /// 当前平台可用的后端
+    #[cfg(target_os = "linux")]
+    pub const AVAILABLE: &'static [OutputBackend] = &[OutputBackend::Enigo, OutputBackend::Uinput];
+    #[cfg(not(target_os = "linux"))]
+    pub const AVAILABLE: &'static [OutputBackend] = &[OutputBackend::Enigo];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            OutputBackend::Enigo => "enigo（系统默认）",
+            OutputBackend::Uinput => "uinput（Linux 虚拟设备）",
+        }
+    }
+
+    /// 创建该后端的输出端，uinput 不可用时退回 enigo
+    pub fn factory(self) -> SinkFactory {
+        match self {
+            OutputBackend::Enigo => enigo_factory(),
+            #[cfg(target_os = "linux")]
+            OutputBackend::Uinput => Box::new(|| match crate::uinput::UinputSink::open() {
+                Ok(sink) => Box::new(sink) as Box<dyn OutputSink>,
+                Err(e) => {
+                    error!("{}，改用 enigo 输出", e);
+                    Box::new(EnigoSink::new())
+                }
+            }),
+            #[cfg(not(target_os = "linux"))]
+            OutputBackend::Uinput => {
+                error!("uinput 只在 Linux 上可用，改用 enigo 输出");
+                enigo_factory()
+            }
+        }
+    }
+}
+
+/// 通过 enigo 控制真实的光标和键盘
+pub struct EnigoSink {
+    enigo: Enigo,
+}
+
+impl EnigoSink {
+    pub fn new() -> Self {
+        Self { enigo: Enigo::new() }
+    }
+}
+
+impl OutputSink for EnigoSink {
+    fn move_relative(&mut self, dx: i32, dy: i32) {
+        self.enigo.mouse_move_relative(dx, dy);
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) {
+        self.enigo.mouse_move_to(x, y);
+    }
+
+    fn location(&self) -> (i32, i32) {
+        self.enigo.mouse_location()
+    }
+
+    fn button_down(&mut self, button: MouseAction) {
+        if let Some(button) = enigo_button(button) {
+            self.enigo.mouse_down(button);
+        }
+    }
+
+    fn button_up(&mut self, button: MouseAction) {
+        if let Some(button) = enigo_button(button) {
+            self.enigo.mouse_up(button);
+        }
+    }
+
+    fn scroll(&mut self, horizontal: bool, amount: i32) {
+        if horizontal {
+            self.enigo.mouse_scroll_x(amount);
+        } else {
+            self.enigo.mouse_scroll_y(amount);
+        }
+    }
+
+    fn key_down(&mut self, key: Key) {
+        self.enigo.key_down(key);
+    }
+
+    fn key_up(&mut self, key: Key) {
+        self.enigo.key_up(key);
+    }
+
+    fn key_tap(&mut self, key: Key) {
+        self.enigo.key_click(key);
+    }
+
+    fn supports_side_buttons(&self) -> bool {
+        enigo_button(MouseAction::Back).is_some()
+    }
+}
+
+/// 转换为 enigo 的鼠标按钮，macOS 上 enigo 不提供侧键，返回 None
+fn enigo_button(button: MouseAction) -> Option<MouseButton> {
+    match button {
+        MouseAction::Left => Some(MouseButton::Left),
+        MouseAction::Right => Some(MouseButton::Right),
+        MouseAction::Middle => Some(MouseButton::Middle),
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        MouseAction::Back => Some(MouseButton::Back),
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        MouseAction::Forward => Some(MouseButton::Forward),
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        MouseAction::Back | MouseAction::Forward => None,
+    }
+}
+
+/// 记录下来的一次输出
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputAction {
+    MoveRelative { dx: i32, dy: i32 },
+    MoveTo { x: i32, y: i32 },
+    ButtonDown(MouseAction),
+    ButtonUp(MouseAction),
+    Scroll { horizontal: bool, amount: i32 },
+    KeyDown(Key),
+    KeyUp(Key),
+}
+
+/// 带时间戳的输出记录，`elapsed` 为距离输出端创建的时间
+#[derive(Clone, Copy, Debug)]
+pub struct RecordedOutput {
+    pub elapsed: Duration,
+    pub action: OutputAction,
+}
+
+/// 只记录输出、不操作真实光标的输出端
+///
+/// 记录保存在共享的日志中，把输出端交给控制线程后仍可以通过 `log()` 读取。
+/// 光标位置按记录的移动计算，从原点开始。
+#[derive(Clone)]
+pub struct RecordingSink {
+    start: Instant,
+    position: (i32, i32),
+    log: Arc<Mutex<VecDeque<RecordedOutput>>>,
+    // 日志最多保留的条数，0 表示不限
+    limit: usize,
+}
+
+impl RecordingSink {
+    pub fn new() -> Self {
+        Self::with_limit(0)
+    }
+
+    /// 只保留最近 `limit` 条记录，用于长时间的试运行
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            start: Instant::now(),
+            position: (0, 0),
+            log: Arc::new(Mutex::new(VecDeque::new())),
+            limit,
+        }
+    }
+
+    /// 共享的输出日志
+    pub fn log(&self) -> Arc<Mutex<VecDeque<RecordedOutput>>> {
+        self.log.clone()
+    }
+
+    /// 创建在控制线程中使用的输出端，日志与当前实例共享
+    pub fn factory(&self) -> SinkFactory {
+        let sink = self.clone();
+        Box::new(move || Box::new(sink) as Box<dyn OutputSink>)
+    }
+
+    fn record(&mut self, action: OutputAction) {
+        let Ok(mut log) = self.log.lock() else {
+            return;
+        };
+        if self.limit > 0 && log.len() >= self.limit {
+            log.pop_front();
+        }
+        log.push_back(RecordedOutput {
+            elapsed: self.start.elapsed(),
+            action,
+        });
+    }
+}
+
+impl Default for RecordingSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputSink for RecordingSink {
+    fn move_relative(&mut self, dx: i32, dy: i32) {
+        self.position = (self.position.0 + dx, self.position.1 + dy);
+        self.record(OutputAction::MoveRelative { dx, dy });
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) {
+        self.position = (x, y);
+        self.record(OutputAction::MoveTo { x, y });
+    }
+
+    fn location(&self) -> (i32, i32) {
+        self.position
+    }
+
+    fn button_down(&mut self, button: MouseAction) {
+        self.record(OutputAction::ButtonDown(button));
+    }
+
+    fn button_up(&mut self, button: MouseAction) {
+        self.record(OutputAction::ButtonUp(button));
+    }
+
+    fn scroll(&mut self, horizontal: bool, amount: i32) {
+        self.record(OutputAction::Scroll { horizontal, amount });
+    }
+
+    fn key_down(&mut self, key: Key) {
+        self.record(OutputAction::KeyDown(key));
+    }
+
+    fn key_up(&mut self, key: Key) {
+        self.record(OutputAction::KeyUp(key));
+    }
+}
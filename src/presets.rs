@@ -0,0 +1,99 @@
+// 内置预设库
+// 预设与用户配置使用相同的序列化格式，文件位于 presets/ 目录，编译时嵌入程序
+use std::fs;
+use std::path::PathBuf;
+use log::info;
+
+use crate::config::Config;
+
+/// 内置预设
+pub struct Preset {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    json: &'static str,
+}
+
+impl Preset {
+    /// 解析预设得到完整配置
+    pub fn config(&self) -> Result<Config, String> {
+        serde_json::from_str(self.json).map_err(|e| format!("预设 {} 解析失败: {}", self.id, e))
+    }
+}
+
+/// 所有内置预设
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        id: "browsing",
+        name: "网页浏览",
+        description: "侧键后退/前进，十字键水平滚动，较快的滚轮",
+        json: include_str!("../presets/browsing.json"),
+    },
+    Preset {
+        id: "media",
+        name: "影音播放",
+        description: "较慢的光标，Y键双击切换全屏，十字键左右后退/前进",
+        json: include_str!("../presets/media.json"),
+    },
+    Preset {
+        id: "presentation",
+        name: "演示",
+        description: "平稳的光标，十字键左右翻页，可记录位置一键点击",
+        json: include_str!("../presets/presentation.json"),
+    },
+    Preset {
+        id: "drawing",
+        name: "绘图",
+        description: "更陡的加速曲线便于精细操作，十字键上下记录并点击工具位置",
+        json: include_str!("../presets/drawing.json"),
+    },
+    Preset {
+        id: "accessibility",
+        name: "无障碍",
+        description: "较大死区、线性曲线，关闭双击和加速模式以避免误触",
+        json: include_str!("../presets/accessibility.json"),
+    },
+];
+
+/// 导出配置到配置目录下的 exports 文件夹，返回导出文件路径
+pub fn export_config(name: &str, config: &Config) -> Result<PathBuf, String> {
+    let export_dir = Config::get_config_dir().join("exports");
+    fs::create_dir_all(&export_dir).map_err(|e| format!("无法创建导出目录: {}", e))?;
+
+    // 去掉文件名中不允许的字符
+    let file_name: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let path = export_dir.join(format!("{}.json", file_name));
+
+    let json = serde_json::to_string_pretty(config).map_err(|e| format!("配置序列化失败: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("无法写入导出文件: {}", e))?;
+    info!("配置已导出到 {:?}", path);
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::ProfileStore;
+    use crate::validation;
+    use std::collections::HashSet;
+
+    #[test]
+    fn every_preset_parses_and_passes_validation() {
+        for preset in PRESETS {
+            let config = preset.config().unwrap_or_else(|e| panic!("{}", e));
+            let mut store = ProfileStore::default();
+            *store.active_config_mut() = config;
+            let errors: Vec<_> = validation::validate_store(&store).into_iter().filter(|issue| issue.is_error()).collect();
+            assert!(errors.is_empty(), "预设 {} 有错误: {:?}", preset.id, errors);
+        }
+    }
+
+    #[test]
+    fn preset_ids_are_unique() {
+        let ids: HashSet<_> = PRESETS.iter().map(|preset| preset.id).collect();
+        assert_eq!(ids.len(), PRESETS.len());
+    }
+}
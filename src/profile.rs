@@ -0,0 +1,616 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use log::{info, error, warn};
+
+use crate::app_focus::AppProfileRule;
+use crate::axis_remap::AxisRemap;
+use crate::battery::BatterySettings;
+use crate::config::Config;
+use crate::connection::Backoff;
+use crate::controller_model::ControllerModel;
+use crate::device_manager::{DeviceKey, ReconnectPolicy};
+use crate::config_backup::{self, DEFAULT_BACKUP_COUNT};
+use crate::config_format;
+use crate::migration::{Migrated, CONFIG_VERSION};
+use crate::multi_pad::{MultiPadPolicy, PadRole};
+use crate::haptics::HapticSettings;
+use crate::input::InputBackend;
+use crate::output::OutputBackend;
+use crate::validation::{self, ValidationIssue};
+
+/// 默认档案名称
+pub const DEFAULT_PROFILE_NAME: &str = "默认";
+
+/// 命名的配置档案
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub config: Config,
+}
+
+/// 配置档案库，保存多个命名档案以及当前激活的档案
+///
+/// 配置文件中缺少的字段使用默认值，新增设置后旧的配置文件仍可读取。
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ProfileStore {
+    // 配置文件版本，见 `migration::CONFIG_VERSION`
+    pub version: u32,
+    pub active_profile: String,
+    pub profiles: Vec<Profile>,
+    // 按前台应用自动切换档案
+    pub auto_switch_enabled: bool,
+    pub app_rules: Vec<AppProfileRule>,
+    // 各手柄型号连接时使用的默认档案
+    pub model_profiles: BTreeMap<ControllerModel, String>,
+    // 多手柄同时使用
+    pub multi_pad_enabled: bool,
+    pub multi_pad_policy: MultiPadPolicy,
+    // 按手柄指定的档案和角色，键见 `DeviceKey::storage_key`；旧版本以手柄名称为键，对没有单独设置的同名手柄仍然有效。
+    // 档案为空表示该手柄跟随激活档案
+    pub pad_profiles: BTreeMap<String, String>,
+    pub pad_roles: BTreeMap<String, PadRole>,
+    // 按手柄设置的轴重映射，键与 `pad_profiles` 相同
+    pub axis_remaps: BTreeMap<String, AxisRemap>,
+    // 手柄断开后等待原手柄还是切换到其他手柄
+    pub reconnect_policy: ReconnectPolicy,
+    pub reconnect_timeout_secs: u64,
+    // 连接失败和重新连接的退避间隔
+    pub connection_backoff: Backoff,
+    // 鼠标和键盘的输出后端
+    pub output_backend: OutputBackend,
+    // 手柄输入后端
+    pub input_backend: InputBackend,
+    // 震动提示
+    pub haptics: HapticSettings,
+    // 低电量警告
+    pub battery: BatterySettings,
+    // 保存时保留的自动备份数量
+    pub backup_count: usize,
+    // 命令行 `--profile` 临时激活档案时，配置文件中原来的激活档案，保存时写回该值
+    #[serde(skip)]
+    saved_active_profile: Option<String>,
+}
+
+impl Default for ProfileStore {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            active_profile: DEFAULT_PROFILE_NAME.to_string(),
+            profiles: vec![Profile {
+                name: DEFAULT_PROFILE_NAME.to_string(),
+                config: Config::default(),
+            }],
+            auto_switch_enabled: false,
+            app_rules: Vec::new(),
+            model_profiles: BTreeMap::new(),
+            multi_pad_enabled: false,
+            multi_pad_policy: MultiPadPolicy::SharedCursor,
+            pad_profiles: BTreeMap::new(),
+            pad_roles: BTreeMap::new(),
+            axis_remaps: BTreeMap::new(),
+            reconnect_policy: ReconnectPolicy::WaitForOriginal,
+            reconnect_timeout_secs: 30,
+            connection_backoff: Backoff::default(),
+            output_backend: OutputBackend::Enigo,
+            input_backend: InputBackend::Gilrs,
+            haptics: HapticSettings::default(),
+            battery: BatterySettings::default(),
+            backup_count: DEFAULT_BACKUP_COUNT,
+            saved_active_profile: None,
+        }
+    }
+}
+
+impl ProfileStore {
+    /// 从配置文件加载档案库
+    ///
+    /// 旧版本的配置文件会逐步迁移到当前版本，迁移前先备份原文件；较新版本的文件只读取，用户保存时才写回。
+    /// 无法解析时同样备份原文件再使用默认档案，避免保存时覆盖用户的设置。
+    /// 有错误的值会恢复为默认值，文件其余部分照常使用；发现的问题与档案库一起返回。
+    pub fn load() -> (Self, Vec<ValidationIssue>) {
+        let config_path = Config::get_config_path();
+
+        if Path::new(&config_path).exists() {
+            match config_format::read_store(Path::new(&config_path)) {
+                Ok(migrated) => {
+                    let from_version = migrated.from_version;
+                    let (store, mut issues) = Self::from_migrated(migrated);
+                    // 较新版本的文件不自动写回，以免丢失本程序不认识的设置
+                    if from_version > CONFIG_VERSION {
+                        issues.push(ValidationIssue::warning("version", format!(
+                            "配置文件版本 {} 比程序支持的版本 {} 新，文件保持不变，保存设置时才会按当前版本写回",
+                            from_version, CONFIG_VERSION,
+                        )));
+                    } else if from_version < CONFIG_VERSION {
+                        let reason = format!("v{}", from_version);
+                        match config_backup::backup_file(Path::new(&config_path), &reason) {
+                            Ok(_) => {
+                                if let Err(e) = store.save() {
+                                    warn!("保存迁移后的配置失败: {}", e);
+                                }
+                            }
+                            Err(e) => warn!("{}，迁移后的配置暂不写回", e),
+                        }
+                    }
+                    info!("配置档案已从 {:?} 成功加载，共 {} 个档案", config_path, store.profiles.len());
+                    return (store, issues);
+                }
+                Err(e) => {
+                    error!("解析配置文件失败: {}", e);
+                    let message = match config_backup::backup_file(Path::new(&config_path), "broken") {
+                        Ok(backup) => format!("{}，原文件已备份到 {:?}，已使用默认档案", e, backup),
+                        Err(backup_err) => {
+                            error!("{}", backup_err);
+                            format!("{}，已使用默认档案", e)
+                        }
+                    };
+                    info!("使用默认配置档案");
+                    return (Self::default(), vec![ValidationIssue::error("", message)]);
+                }
+            }
+        }
+
+        // 如果加载失败，则使用默认档案
+        info!("使用默认配置档案");
+        (Self::default(), Vec::new())
+    }
+
+    /// 从 JSON 或 TOML 文件导入档案库，格式按扩展名判断
+    pub fn import(path: &Path) -> Result<(Self, Vec<ValidationIssue>), String> {
+        let migrated = config_format::read_store(path)?;
+        info!("已从 {:?} 导入配置档案", path);
+        Ok(Self::from_migrated(migrated))
+    }
+
+    /// 检查迁移后的档案库，把有错误的值恢复为默认值
+    pub fn from_migrated(migrated: Migrated) -> (Self, Vec<ValidationIssue>) {
+        let mut store = migrated.store;
+        let mut issues = migrated.issues;
+        issues.extend(validation::repair_store(&mut store));
+        for issue in &issues {
+            warn!("配置检查 {}", issue);
+        }
+        store.ensure_valid();
+        (store, issues)
+    }
+
+    /// 保存档案库到配置文件
+    pub fn save(&self) -> Result<(), String> {
+        let config_path = Config::get_config_path();
+
+        // 确保存在父目录
+        if let Some(parent) = Path::new(&config_path).parent().filter(|parent| !parent.exists()) {
+            fs::create_dir_all(parent).map_err(|e| format!("无法创建配置目录: {}", e))?;
+        }
+
+        // 按扩展名序列化为 JSON 或 TOML 并写入文件
+        config_format::write_store(Path::new(&config_path), &self.persisted())?;
+        info!("配置档案已保存到 {:?}", config_path);
+        Ok(())
+    }
+
+    /// 修正空档案库或失效的激活档案指针
+    fn ensure_valid(&mut self) {
+        if self.profiles.is_empty() {
+            // 只补上档案，其他设置照常使用
+            warn!("配置档案库为空，已创建默认档案");
+            self.profiles.push(Profile {
+                name: DEFAULT_PROFILE_NAME.to_string(),
+                config: Config::default(),
+            });
+            self.active_profile = DEFAULT_PROFILE_NAME.to_string();
+        } else if self.find(&self.active_profile).is_none() {
+            warn!("激活档案 {} 不存在，改用 {}", self.active_profile, self.profiles[0].name);
+            self.active_profile = self.profiles[0].name.clone();
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.profiles.iter().position(|p| p.name == name)
+    }
+
+    /// 所有档案名称
+    pub fn names(&self) -> Vec<String> {
+        self.profiles.iter().map(|p| p.name.clone()).collect()
+    }
+
+    /// 指定档案的配置
+    pub fn config_of(&self, name: &str) -> Option<&Config> {
+        self.find(name).map(|index| &self.profiles[index].config)
+    }
+
+    /// 当前激活档案的配置
+    pub fn active_config(&self) -> &Config {
+        let index = self.find(&self.active_profile).unwrap_or(0);
+        &self.profiles[index].config
+    }
+
+    /// 当前激活档案的配置（可修改）
+    pub fn active_config_mut(&mut self) -> &mut Config {
+        let index = self.find(&self.active_profile).unwrap_or(0);
+        &mut self.profiles[index].config
+    }
+
+    /// 手柄单独指定的档案
+    pub fn pad_profile(&self, key: &DeviceKey) -> Option<&String> {
+        pad_setting(&self.pad_profiles, key).filter(|profile| !profile.is_empty())
+    }
+
+    /// 为手柄指定档案，None 表示跟随当前激活档案
+    ///
+    /// 按 UUID 和名称保存，按名称保存的旧设置保留给其他同名手柄。
+    pub fn set_pad_profile(&mut self, key: &DeviceKey, profile: Option<String>) {
+        match profile {
+            Some(profile) => {
+                self.pad_profiles.insert(key.storage_key(), profile);
+            }
+            // 有同名的旧设置时需要记下空档案，否则会退回旧设置
+            None if self.pad_profiles.contains_key(&key.name) => {
+                self.pad_profiles.insert(key.storage_key(), String::new());
+            }
+            None => {
+                self.pad_profiles.remove(&key.storage_key());
+            }
+        }
+    }
+
+    /// 手柄在分配角色策略下的角色，未设置时为鼠标
+    pub fn pad_role(&self, key: &DeviceKey) -> PadRole {
+        pad_setting(&self.pad_roles, key).copied().unwrap_or(PadRole::Pointer)
+    }
+
+    /// 设置手柄的角色，按名称保存的旧设置保留给其他同名手柄
+    pub fn set_pad_role(&mut self, key: &DeviceKey, role: PadRole) {
+        self.pad_roles.insert(key.storage_key(), role);
+    }
+
+    /// 手柄的轴重映射，没有设置时不改变任何轴
+    pub fn axis_remap(&self, key: &DeviceKey) -> AxisRemap {
+        pad_setting(&self.axis_remaps, key).cloned().unwrap_or_default()
+    }
+
+    /// 设置手柄的轴重映射，按名称保存的旧设置保留给其他同名手柄
+    pub fn set_axis_remap(&mut self, key: &DeviceKey, remap: AxisRemap) {
+        // 有同名的旧设置时需要记下不改变任何轴的映射，否则会退回旧设置
+        if remap.is_identity() && !self.axis_remaps.contains_key(&key.name) {
+            self.axis_remaps.remove(&key.storage_key());
+        } else {
+            self.axis_remaps.insert(key.storage_key(), remap);
+        }
+    }
+
+    /// 切换激活档案，之前临时激活的档案随之失效
+    pub fn set_active(&mut self, name: &str) -> Result<(), String> {
+        if self.find(name).is_none() {
+            return Err(format!("配置档案不存在: {}", name));
+        }
+        self.active_profile = name.to_string();
+        self.saved_active_profile = None;
+        info!("已切换到配置档案: {}", name);
+        Ok(())
+    }
+
+    /// 临时激活档案，用于命令行 `--profile`
+    ///
+    /// 配置文件中仍保存原来的激活档案，直到用户手动切换档案。
+    pub fn set_active_override(&mut self, name: &str) -> Result<(), String> {
+        if self.find(name).is_none() {
+            return Err(format!("配置档案不存在: {}", name));
+        }
+        if self.saved_active_profile.is_none() {
+            self.saved_active_profile = Some(self.active_profile.clone());
+        }
+        self.active_profile = name.to_string();
+        info!("本次运行使用配置档案: {}", name);
+        Ok(())
+    }
+
+    /// 当前激活档案是否为临时激活
+    pub fn has_active_override(&self) -> bool {
+        self.saved_active_profile.is_some()
+    }
+
+    /// 写入配置文件的档案库，临时激活的档案换回原来的激活档案
+    pub fn persisted(&self) -> Cow<'_, ProfileStore> {
+        match &self.saved_active_profile {
+            Some(saved) => Cow::Owned(ProfileStore {
+                active_profile: saved.clone(),
+                saved_active_profile: None,
+                ..self.clone()
+            }),
+            None => Cow::Borrowed(self),
+        }
+    }
+
+    /// 按顺序切换到下一个档案，返回新的激活档案名称
+    pub fn cycle_next(&mut self) -> String {
+        let index = self.find(&self.active_profile).unwrap_or(0);
+        let next = (index + 1) % self.profiles.len();
+        self.active_profile = self.profiles[next].name.clone();
+        info!("已循环切换到配置档案: {}", self.active_profile);
+        self.active_profile.clone()
+    }
+
+    /// 检查新档案名称是否可用
+    fn check_new_name(&self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("档案名称不能为空".to_string());
+        }
+        if self.find(name).is_some() {
+            return Err(format!("配置档案已存在: {}", name));
+        }
+        Ok(())
+    }
+
+    /// 基于给定名称生成不重复的档案名称
+    pub fn unique_name(&self, base: &str) -> String {
+        if self.find(base).is_none() {
+            return base.to_string();
+        }
+        (2..)
+            .map(|i| format!("{} {}", base, i))
+            .find(|name| self.find(name).is_none())
+            .unwrap()
+    }
+
+    /// 以指定配置创建新档案
+    pub fn add(&mut self, name: &str, config: Config) -> Result<(), String> {
+        self.check_new_name(name)?;
+        self.profiles.push(Profile {
+            name: name.trim().to_string(),
+            config,
+        });
+        info!("已创建配置档案: {}", name.trim());
+        Ok(())
+    }
+
+    /// 以默认设置创建新档案
+    pub fn create(&mut self, name: &str) -> Result<(), String> {
+        self.add(name, Config::default())
+    }
+
+    /// 复制已有档案
+    pub fn clone_profile(&mut self, source: &str, name: &str) -> Result<(), String> {
+        let index = self.find(source).ok_or_else(|| format!("配置档案不存在: {}", source))?;
+        let config = self.profiles[index].config.clone();
+        self.add(name, config)
+    }
+
+    /// 重命名档案
+    pub fn rename(&mut self, old_name: &str, new_name: &str) -> Result<(), String> {
+        let index = self.find(old_name).ok_or_else(|| format!("配置档案不存在: {}", old_name))?;
+        self.check_new_name(new_name)?;
+        let new_name = new_name.trim().to_string();
+        if self.active_profile == old_name {
+            self.active_profile = new_name.clone();
+        }
+        if self.saved_active_profile.as_deref() == Some(old_name) {
+            self.saved_active_profile = Some(new_name.clone());
+        }
+        for rule in self.app_rules.iter_mut().filter(|rule| rule.profile == old_name) {
+            rule.profile = new_name.clone();
+        }
+        for profile in self.model_profiles.values_mut().filter(|profile| *profile == old_name) {
+            *profile = new_name.clone();
+        }
+        for profile in self.pad_profiles.values_mut().filter(|profile| *profile == old_name) {
+            *profile = new_name.clone();
+        }
+        info!("配置档案 {} 已重命名为 {}", old_name, new_name);
+        self.profiles[index].name = new_name;
+        Ok(())
+    }
+
+    /// 删除档案，至少保留一个档案
+    pub fn delete(&mut self, name: &str) -> Result<(), String> {
+        let index = self.find(name).ok_or_else(|| format!("配置档案不存在: {}", name))?;
+        if self.profiles.len() <= 1 {
+            return Err("至少需要保留一个配置档案".to_string());
+        }
+        self.profiles.remove(index);
+        self.app_rules.retain(|rule| rule.profile != name);
+        self.model_profiles.retain(|_, profile| profile != name);
+        self.pad_profiles.retain(|_, profile| profile != name);
+        let fallback = &self.profiles[index.min(self.profiles.len() - 1)].name;
+        if self.active_profile == name {
+            self.active_profile = fallback.clone();
+        }
+        if self.saved_active_profile.as_deref() == Some(name) {
+            self.saved_active_profile = Some(fallback.clone());
+        }
+        info!("已删除配置档案: {}", name);
+        Ok(())
+    }
+}
+
+/// 按手柄查找设置，先按 UUID 和名称查找，再按旧版本使用的名称查找
+fn pad_setting<'a, T>(settings: &'a BTreeMap<String, T>, key: &DeviceKey) -> Option<&'a T> {
+    settings.get(&key.storage_key()).or_else(|| settings.get(&key.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration;
+    use serde_json::json;
+
+    fn reload(store: &ProfileStore) -> ProfileStore {
+        let value = serde_json::to_value(store).unwrap();
+        ProfileStore::from_migrated(migration::load_store(value).unwrap()).0
+    }
+
+    #[test]
+    fn store_round_trips_through_json() {
+        let mut store = ProfileStore::default();
+        let fast = Config {
+            mouse_sensitivity: 90.0,
+            back_button: "LeftTrigger".to_string(),
+            ..Config::default()
+        };
+        store.add("快速", fast).unwrap();
+        store.set_active("快速").unwrap();
+        store.auto_switch_enabled = true;
+        store.app_rules.push(AppProfileRule { app: "firefox".to_string(), profile: "快速".to_string() });
+        store.reconnect_timeout_secs = 12;
+
+        let loaded = reload(&store);
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&store).unwrap());
+        assert_eq!(loaded.active_config().mouse_sensitivity, 90.0);
+    }
+
+    #[test]
+    fn legacy_single_config_becomes_default_profile() {
+        // 档案功能之前的 config.json，只有单个配置
+        let legacy = json!({
+            "mouse_sensitivity": 42.0,
+            "dead_zone": 0.1,
+            "scroll_sensitivity": 3.0,
+            "mouse_acceleration": 1.4,
+            "left_click_button": "South",
+            "right_click_button": "East",
+            "middle_click_button": "West",
+            "double_click_button": "North",
+            "invert_x_axis": true,
+            "invert_y_axis": false,
+            "use_left_stick_for_mouse": true,
+            "precision_mode_button": "LeftTrigger2",
+            "turbo_mode_button": "RightTrigger2",
+            "start_minimized": false,
+            "start_with_system": false,
+            "show_notification": true
+        });
+        let (store, issues) = ProfileStore::from_migrated(migration::load_store(legacy).unwrap());
+        assert!(issues.is_empty(), "{:?}", issues);
+        assert_eq!(store.names(), vec![DEFAULT_PROFILE_NAME.to_string()]);
+        let config = store.active_config();
+        assert_eq!(config.mouse_sensitivity, 42.0);
+        assert!(config.invert_x_axis);
+        assert_eq!(config.back_button, "");
+        assert_eq!(config.scroll_left_button, "");
+    }
+
+    #[test]
+    fn missing_store_fields_use_defaults() {
+        let store: ProfileStore = serde_json::from_value(json!({
+            "active_profile": "默认",
+            "profiles": [{ "name": "默认", "config": serde_json::to_value(Config::default()).unwrap() }]
+        }))
+        .unwrap();
+        assert!(!store.multi_pad_enabled);
+        assert_eq!(store.reconnect_timeout_secs, ProfileStore::default().reconnect_timeout_secs);
+    }
+
+    #[test]
+    fn empty_profile_list_keeps_other_settings() {
+        let mut store = ProfileStore::default();
+        store.profiles.clear();
+        store.active_profile = "已删除".to_string();
+        store.auto_switch_enabled = true;
+        store.app_rules.push(AppProfileRule { app: "gimp".to_string(), profile: DEFAULT_PROFILE_NAME.to_string() });
+        store.reconnect_timeout_secs = 7;
+        store.backup_count = 3;
+
+        let loaded = reload(&store);
+        assert_eq!(loaded.names(), vec![DEFAULT_PROFILE_NAME.to_string()]);
+        assert_eq!(loaded.active_profile, DEFAULT_PROFILE_NAME);
+        assert!(loaded.auto_switch_enabled);
+        assert_eq!(loaded.app_rules.len(), 1);
+        assert_eq!(loaded.reconnect_timeout_secs, 7);
+        assert_eq!(loaded.backup_count, 3);
+    }
+
+    #[test]
+    fn cycle_wraps_to_first_profile() {
+        let mut store = ProfileStore::default();
+        store.add("第二", Config::default()).unwrap();
+        assert_eq!(store.cycle_next(), "第二");
+        assert_eq!(store.cycle_next(), DEFAULT_PROFILE_NAME);
+    }
+
+    #[test]
+    fn pad_settings_are_keyed_by_uuid_and_name() {
+        let mut store = ProfileStore::default();
+        store.add("第二", Config::default()).unwrap();
+        let usb = DeviceKey { uuid: [1; 16], name: "Pad".to_string() };
+        let bluetooth = DeviceKey { uuid: [2; 16], name: "Pad".to_string() };
+
+        // 旧版本按名称保存的设置对同名手柄都有效
+        store.pad_roles.insert("Pad".to_string(), PadRole::Keyboard);
+        store.pad_profiles.insert("Pad".to_string(), "第二".to_string());
+        assert_eq!(store.pad_role(&bluetooth), PadRole::Keyboard);
+
+        // 修改一个手柄不影响其他同名手柄
+        store.set_pad_role(&usb, PadRole::Pointer);
+        store.set_pad_profile(&usb, None);
+        assert_eq!(store.pad_role(&usb), PadRole::Pointer);
+        assert_eq!(store.pad_role(&bluetooth), PadRole::Keyboard);
+        assert_eq!(store.pad_profile(&usb), None);
+        assert_eq!(store.pad_profile(&bluetooth).map(String::as_str), Some("第二"));
+
+        store.set_pad_profile(&bluetooth, Some(DEFAULT_PROFILE_NAME.to_string()));
+        assert_eq!(store.pad_profile(&bluetooth).map(String::as_str), Some(DEFAULT_PROFILE_NAME));
+        assert_eq!(store.pad_profile(&usb), None);
+
+        // 跟随激活档案的设置在重新加载后保留，且不被当作失效的档案引用
+        let (loaded, issues) = ProfileStore::from_migrated(
+            migration::load_store(serde_json::to_value(&store).unwrap()).unwrap(),
+        );
+        assert!(issues.is_empty(), "{:?}", issues);
+        assert_eq!(loaded.pad_profile(&usb), None);
+        assert_eq!(loaded.pad_role(&bluetooth), PadRole::Keyboard);
+    }
+
+    #[test]
+    fn active_override_is_not_persisted() {
+        let mut store = ProfileStore::default();
+        store.add("游戏", Config::default()).unwrap();
+        store.add("办公", Config::default()).unwrap();
+        assert!(store.set_active_override("不存在").is_err());
+        assert!(!store.has_active_override());
+
+        store.set_active_override("游戏").unwrap();
+        store.set_active_override("办公").unwrap();
+        assert_eq!(store.active_profile, "办公");
+        assert!(store.has_active_override());
+        assert_eq!(store.persisted().active_profile, DEFAULT_PROFILE_NAME);
+        assert_eq!(reload(&store).active_profile, "办公");
+        assert_eq!(reload(&store.persisted()).active_profile, DEFAULT_PROFILE_NAME);
+
+        // 原来的激活档案改名或删除时跟随变化
+        store.rename(DEFAULT_PROFILE_NAME, "日常").unwrap();
+        assert_eq!(store.persisted().active_profile, "日常");
+        store.delete("日常").unwrap();
+        assert_eq!(store.persisted().active_profile, "游戏");
+
+        // 手动切换后不再是临时档案
+        store.set_active("办公").unwrap();
+        assert!(!store.has_active_override());
+        assert_eq!(store.persisted().active_profile, "办公");
+    }
+
+    #[test]
+    fn axis_remaps_are_keyed_by_uuid_and_name() {
+        let mut store = ProfileStore::default();
+        let usb = DeviceKey { uuid: [1; 16], name: "Pad".to_string() };
+        let bluetooth = DeviceKey { uuid: [2; 16], name: "Pad".to_string() };
+        let swapped = AxisRemap { swap_sticks: true, ..AxisRemap::default() };
+
+        store.set_axis_remap(&usb, swapped.clone());
+        assert_eq!(store.axis_remap(&usb), swapped);
+        assert!(store.axis_remap(&bluetooth).is_identity());
+        store.set_axis_remap(&usb, AxisRemap::default());
+        assert!(store.axis_remaps.is_empty());
+
+        // 旧版本按名称保存的映射对同名手柄都有效，恢复一个手柄不影响其他手柄
+        store.axis_remaps.insert("Pad".to_string(), swapped.clone());
+        store.set_axis_remap(&usb, AxisRemap::default());
+        assert!(store.axis_remap(&usb).is_identity());
+        assert_eq!(store.axis_remap(&bluetooth), swapped);
+        assert_eq!(reload(&store).axis_remap(&bluetooth), swapped);
+    }
+}
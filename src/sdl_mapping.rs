@@ -0,0 +1,493 @@
+// SDL 手柄映射：用户映射文件和映射向导
+use gilrs::ev::Code;
+use gilrs::{Axis, Button, Mapping};
+use log::{info, warn};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::config_backup;
+use crate::device_manager::DeviceInfo;
+
+/// 映射文件中使用的平台名称，与 gilrs 读取时的过滤一致
+#[cfg(target_os = "linux")]
+const SDL_PLATFORM: &str = "Linux";
+#[cfg(target_os = "windows")]
+const SDL_PLATFORM: &str = "Windows";
+#[cfg(target_os = "macos")]
+const SDL_PLATFORM: &str = "Mac OS X";
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+const SDL_PLATFORM: &str = "";
+
+/// 用户映射文件路径，格式与 SDL_GameControllerDB 的 gamecontrollerdb.txt 相同
+pub fn user_mappings_path() -> PathBuf {
+    Config::get_config_dir().join("gamecontrollerdb.txt")
+}
+
+/// 读取用户映射文件，文件不存在时返回空字符串
+pub fn load_user_mappings() -> String {
+    let path = user_mappings_path();
+    match fs::read_to_string(&path) {
+        Ok(text) => {
+            info!("已加载用户手柄映射 {} 条: {:?}", count_mappings(&text), path);
+            text
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            warn!("无法读取用户手柄映射文件 {:?}: {}", path, e);
+            String::new()
+        }
+    }
+}
+
+/// 映射文本中的有效映射条数（跳过空行和注释）
+pub fn count_mappings(text: &str) -> usize {
+    text.lines().filter(|line| is_mapping_line(line)).count()
+}
+
+fn is_mapping_line(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with('#')
+}
+
+/// 把一条映射写入用户映射文件，替换同一 UUID 的旧映射
+pub fn save_user_mapping(mapping: &str) -> Result<(), String> {
+    let path = user_mappings_path();
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    let updated = replace_mapping(&existing, &with_platform(mapping));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("无法创建配置目录: {}", e))?;
+    }
+    config_backup::write_atomic(&path, &updated)?;
+    info!("已保存手柄映射到 {:?}", path);
+    Ok(())
+}
+
+/// 补上平台字段，gilrs 生成的映射字符串不包含平台
+fn with_platform(mapping: &str) -> String {
+    let mapping = mapping.trim();
+    if mapping.contains("platform:") || SDL_PLATFORM.is_empty() {
+        return mapping.to_string();
+    }
+    let separator = if mapping.ends_with(',') { "" } else { "," };
+    format!("{}{}platform:{},", mapping, separator, SDL_PLATFORM)
+}
+
+/// 在映射文本中替换或追加一条映射，按第一个字段（UUID）匹配
+fn replace_mapping(text: &str, mapping: &str) -> String {
+    let uuid = mapping.split(',').next().unwrap_or_default();
+    let mut lines: Vec<&str> = text.lines()
+        .filter(|line| !is_mapping_line(line) || line.trim().split(',').next() != Some(uuid))
+        .collect();
+    lines.push(mapping);
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    updated
+}
+
+/// 映射的目标：一个逻辑按钮或摇杆轴
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MappingTarget {
+    Button(Button),
+    Axis(Axis),
+}
+
+impl MappingTarget {
+    /// 向导中提示用户的操作
+    pub fn prompt(self) -> &'static str {
+        match self {
+            MappingTarget::Button(Button::South) => "按下 A 键（下方的面键）",
+            MappingTarget::Button(Button::East) => "按下 B 键（右侧的面键）",
+            MappingTarget::Button(Button::West) => "按下 X 键（左侧的面键）",
+            MappingTarget::Button(Button::North) => "按下 Y 键（上方的面键）",
+            MappingTarget::Button(Button::LeftTrigger) => "按下左肩键 LB",
+            MappingTarget::Button(Button::RightTrigger) => "按下右肩键 RB",
+            MappingTarget::Button(Button::LeftTrigger2) => "按下左扳机 LT",
+            MappingTarget::Button(Button::RightTrigger2) => "按下右扳机 RT",
+            MappingTarget::Button(Button::Select) => "按下 Back/Select 键",
+            MappingTarget::Button(Button::Start) => "按下 Start 键",
+            MappingTarget::Button(Button::Mode) => "按下 Home/Guide 键",
+            MappingTarget::Button(Button::LeftThumb) => "按下左摇杆",
+            MappingTarget::Button(Button::RightThumb) => "按下右摇杆",
+            MappingTarget::Button(Button::DPadUp) => "按下十字键上",
+            MappingTarget::Button(Button::DPadDown) => "按下十字键下",
+            MappingTarget::Button(Button::DPadLeft) => "按下十字键左",
+            MappingTarget::Button(Button::DPadRight) => "按下十字键右",
+            MappingTarget::Axis(Axis::LeftStickX) => "左右移动左摇杆",
+            MappingTarget::Axis(Axis::LeftStickY) => "上下移动左摇杆",
+            MappingTarget::Axis(Axis::RightStickX) => "左右移动右摇杆",
+            MappingTarget::Axis(Axis::RightStickY) => "上下移动右摇杆",
+            MappingTarget::Button(_) => "按下对应的按钮",
+            MappingTarget::Axis(_) => "移动对应的轴",
+        }
+    }
+
+    /// SDL 映射中的字段名
+    fn sdl_field(self) -> Option<&'static str> {
+        let field = match self {
+            MappingTarget::Button(Button::South) => "a",
+            MappingTarget::Button(Button::East) => "b",
+            MappingTarget::Button(Button::West) => "x",
+            MappingTarget::Button(Button::North) => "y",
+            MappingTarget::Button(Button::LeftTrigger) => "leftshoulder",
+            MappingTarget::Button(Button::RightTrigger) => "rightshoulder",
+            MappingTarget::Button(Button::LeftTrigger2) => "lefttrigger",
+            MappingTarget::Button(Button::RightTrigger2) => "righttrigger",
+            MappingTarget::Button(Button::Select) => "back",
+            MappingTarget::Button(Button::Start) => "start",
+            MappingTarget::Button(Button::Mode) => "guide",
+            MappingTarget::Button(Button::LeftThumb) => "leftstick",
+            MappingTarget::Button(Button::RightThumb) => "rightstick",
+            MappingTarget::Button(Button::DPadUp) => "dpup",
+            MappingTarget::Button(Button::DPadDown) => "dpdown",
+            MappingTarget::Button(Button::DPadLeft) => "dpleft",
+            MappingTarget::Button(Button::DPadRight) => "dpright",
+            MappingTarget::Axis(Axis::LeftStickX) => "leftx",
+            MappingTarget::Axis(Axis::LeftStickY) => "lefty",
+            MappingTarget::Axis(Axis::RightStickX) => "rightx",
+            MappingTarget::Axis(Axis::RightStickY) => "righty",
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    /// 十字键方向在 SDL hat 字段中的值（上 1、右 2、下 4、左 8）
+    fn hat_direction(self) -> Option<u8> {
+        match self {
+            MappingTarget::Button(Button::DPadUp) => Some(1),
+            MappingTarget::Button(Button::DPadRight) => Some(2),
+            MappingTarget::Button(Button::DPadDown) => Some(4),
+            MappingTarget::Button(Button::DPadLeft) => Some(8),
+            _ => None,
+        }
+    }
+
+    fn is_trigger(self) -> bool {
+        matches!(self, MappingTarget::Button(Button::LeftTrigger2 | Button::RightTrigger2))
+    }
+}
+
+/// 向导依次询问的输入，顺序与 SDL 映射字段一致
+pub const WIZARD_STEPS: [MappingTarget; 21] = [
+    MappingTarget::Button(Button::South),
+    MappingTarget::Button(Button::East),
+    MappingTarget::Button(Button::West),
+    MappingTarget::Button(Button::North),
+    MappingTarget::Button(Button::LeftTrigger),
+    MappingTarget::Button(Button::RightTrigger),
+    MappingTarget::Button(Button::LeftTrigger2),
+    MappingTarget::Button(Button::RightTrigger2),
+    MappingTarget::Button(Button::Select),
+    MappingTarget::Button(Button::Start),
+    MappingTarget::Button(Button::Mode),
+    MappingTarget::Button(Button::LeftThumb),
+    MappingTarget::Button(Button::RightThumb),
+    MappingTarget::Button(Button::DPadUp),
+    MappingTarget::Button(Button::DPadDown),
+    MappingTarget::Button(Button::DPadLeft),
+    MappingTarget::Button(Button::DPadRight),
+    MappingTarget::Axis(Axis::LeftStickX),
+    MappingTarget::Axis(Axis::LeftStickY),
+    MappingTarget::Axis(Axis::RightStickX),
+    MappingTarget::Axis(Axis::RightStickY),
+];
+
+// 轴偏离中心超过该值才算作输入
+const AXIS_THRESHOLD: f32 = 0.6;
+
+/// 手柄的一个原始输入，`code` 为驱动报告的键码
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RawInput<C = Code> {
+    Button(C),
+    Axis(C, f32),
+}
+
+/// 记录下来的映射来源
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MappedInput<C = Code> {
+    Button(C),
+    /// 整个轴：摇杆轴或模拟扳机
+    Axis(C),
+    /// 轴的一个方向：以 hat 轴报告的十字键，`true` 为正方向
+    Hat(C, bool),
+}
+
+impl<C: Copy + PartialEq> MappedInput<C> {
+    fn code(self) -> C {
+        match self {
+            MappedInput::Button(code) | MappedInput::Axis(code) | MappedInput::Hat(code, _) => code,
+        }
+    }
+
+    /// 两个来源是否使用同一个输入，hat 轴的两个方向可以分别映射
+    fn conflicts(self, other: Self) -> bool {
+        match (self, other) {
+            (MappedInput::Hat(..), MappedInput::Hat(..)) => self == other,
+            _ => self.code() == other.code(),
+        }
+    }
+}
+
+/// 映射向导
+///
+/// 依次提示用户按下每个逻辑按钮、移动每个摇杆轴，把收到的原始键码记录为映射。
+/// 扳机也接受模拟轴，十字键也接受 hat 轴的一个方向。
+/// 用户可以跳过手柄上没有的按钮；已经记录过的输入不会被重复使用。
+pub struct MappingWizard<C = Code> {
+    pub device: DeviceInfo,
+    step: usize,
+    captured: Vec<(MappingTarget, MappedInput<C>)>,
+}
+
+impl<C: Copy + PartialEq> MappingWizard<C> {
+    pub fn new(device: DeviceInfo) -> Self {
+        Self {
+            device,
+            step: 0,
+            captured: Vec::new(),
+        }
+    }
+
+    /// 当前等待的输入，全部完成后返回 None
+    pub fn current(&self) -> Option<MappingTarget> {
+        WIZARD_STEPS.get(self.step).copied()
+    }
+
+    /// 已完成的步骤数和总步骤数
+    pub fn progress(&self) -> (usize, usize) {
+        (self.step.min(WIZARD_STEPS.len()), WIZARD_STEPS.len())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.current().is_none()
+    }
+
+    /// 处理一个原始输入，被记录时返回 true
+    pub fn feed(&mut self, input: RawInput<C>) -> bool {
+        let Some(target) = self.current() else {
+            return false;
+        };
+        let source = match (target, input) {
+            (MappingTarget::Button(_), RawInput::Button(code)) => MappedInput::Button(code),
+            (MappingTarget::Axis(_), RawInput::Axis(code, value)) if value.abs() >= AXIS_THRESHOLD => MappedInput::Axis(code),
+            // 扳机静止时可能报告 -1，只接受按下的方向
+            (_, RawInput::Axis(code, value)) if target.is_trigger() && value >= AXIS_THRESHOLD => MappedInput::Axis(code),
+            (_, RawInput::Axis(code, value)) if target.hat_direction().is_some() && value.abs() >= AXIS_THRESHOLD => {
+                MappedInput::Hat(code, value > 0.0)
+            }
+            _ => return false,
+        };
+        if self.captured.iter().any(|(_, used)| used.conflicts(source)) {
+            return false;
+        }
+        self.captured.push((target, source));
+        self.step += 1;
+        true
+    }
+
+    /// 跳过当前输入
+    pub fn skip(&mut self) {
+        if !self.is_done() {
+            self.step += 1;
+        }
+    }
+
+    /// 回到上一步并丢弃它的记录
+    pub fn back(&mut self) {
+        if self.step == 0 {
+            return;
+        }
+        self.step -= 1;
+        let target = WIZARD_STEPS[self.step];
+        self.captured.retain(|(captured, _)| *captured != target);
+    }
+
+    /// 已记录的映射
+    pub fn captured(&self) -> &[(MappingTarget, MappedInput<C>)] {
+        &self.captured
+    }
+}
+
+/// 把记录的映射转换为 gilrs 的映射数据
+///
+/// gilrs 只能把按钮映射到按钮、轴映射到轴：模拟扳机暂时映射为 LeftZ/RightZ 轴，
+/// 由 `complete_mapping` 改写为扳机字段；hat 轴无法表示，只写入映射字符串，重新启动后生效。
+pub fn mapping_data(captured: &[(MappingTarget, MappedInput)]) -> Mapping {
+    let mut data = Mapping::new();
+    for (target, source) in captured {
+        match (*target, *source) {
+            (MappingTarget::Button(button), MappedInput::Button(code)) => data.insert_btn(code, button),
+            (MappingTarget::Axis(axis), MappedInput::Axis(code)) => data.insert_axis(code, axis),
+            (MappingTarget::Button(Button::LeftTrigger2), MappedInput::Axis(code)) => data.insert_axis(code, Axis::LeftZ),
+            (MappingTarget::Button(Button::RightTrigger2), MappedInput::Axis(code)) => data.insert_axis(code, Axis::RightZ),
+            _ => None,
+        };
+    }
+    data
+}
+
+/// 补全 gilrs 生成的映射字符串：模拟扳机改为 `lefttrigger:a2` 形式，追加 `dpup:h0.1` 形式的 hat 字段
+pub fn complete_mapping<C>(mapping: &str, captured: &[(MappingTarget, MappedInput<C>)]) -> String {
+    let mut fields: Vec<String> = mapping.trim().trim_end_matches(',').split(',')
+        .map(|field| {
+            if let Some(axis) = field.strip_prefix("leftz:") {
+                format!("lefttrigger:{}", axis)
+            } else if let Some(axis) = field.strip_prefix("rightz:") {
+                format!("righttrigger:{}", axis)
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    for (target, source) in captured {
+        if let (MappedInput::Hat(..), Some(field), Some(direction)) = (source, target.sdl_field(), target.hat_direction()) {
+            fields.push(format!("{}:h0.{}", field, direction));
+        }
+    }
+    let mut mapping = fields.join(",");
+    mapping.push(',');
+    mapping
+}
+
+/// 记录中是否有重新启动后才生效的 hat 映射
+pub fn has_hat_mapping<C>(captured: &[(MappingTarget, MappedInput<C>)]) -> bool {
+    captured.iter().any(|(_, source)| matches!(source, MappedInput::Hat(..)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::scripted_device;
+
+    const UUID: &str = "030000005e0400008e02000014010000";
+
+    fn wizard() -> MappingWizard<u32> {
+        MappingWizard::new(scripted_device(0, "Test Pad", [0; 16]))
+    }
+
+    /// 从第一个十字键步骤开始的向导
+    fn wizard_at_dpad() -> MappingWizard<u32> {
+        let mut wizard = wizard();
+        while wizard.current() != Some(MappingTarget::Button(Button::DPadUp)) {
+            wizard.skip();
+        }
+        wizard
+    }
+
+    #[test]
+    fn replace_mapping_replaces_same_uuid_and_keeps_others() {
+        let text = format!("# 注释\n{},Old Pad,a:b0,\n03000000aaaa,Other,a:b1,\n", UUID);
+        let updated = replace_mapping(&text, &format!("{},New Pad,a:b2,", UUID));
+        assert_eq!(updated, format!("# 注释\n03000000aaaa,Other,a:b1,\n{},New Pad,a:b2,\n", UUID));
+        assert_eq!(count_mappings(&updated), 2);
+        assert_eq!(replace_mapping("", "abc,Pad,a:b0,"), "abc,Pad,a:b0,\n");
+    }
+
+    #[test]
+    fn with_platform_appends_platform_once() {
+        let mapping = format!("{},Pad,a:b0,", UUID);
+        let expected = if SDL_PLATFORM.is_empty() { mapping.clone() } else { format!("{}platform:{},", mapping, SDL_PLATFORM) };
+        assert_eq!(with_platform(&mapping), expected);
+        assert_eq!(with_platform(&format!("{},Pad,a:b0", UUID)), expected);
+        assert_eq!(with_platform(&expected), expected);
+    }
+
+    #[test]
+    fn feed_skip_and_back_follow_steps() {
+        let mut wizard = wizard();
+        assert_eq!(wizard.current(), Some(MappingTarget::Button(Button::South)));
+        assert!(!wizard.feed(RawInput::Axis(10, 1.0)));
+        assert!(wizard.feed(RawInput::Button(1)));
+        // 已使用的键码不会再被记录
+        assert!(!wizard.feed(RawInput::Button(1)));
+        assert_eq!(wizard.current(), Some(MappingTarget::Button(Button::East)));
+        wizard.skip();
+        assert!(wizard.feed(RawInput::Button(2)));
+        assert_eq!(wizard.progress(), (3, WIZARD_STEPS.len()));
+        assert_eq!(wizard.captured(), &[
+            (MappingTarget::Button(Button::South), MappedInput::Button(1)),
+            (MappingTarget::Button(Button::West), MappedInput::Button(2)),
+        ]);
+
+        wizard.back();
+        assert_eq!(wizard.current(), Some(MappingTarget::Button(Button::West)));
+        assert_eq!(wizard.captured().len(), 1);
+        // 回到跳过的步骤，没有记录可丢弃
+        wizard.back();
+        assert_eq!(wizard.current(), Some(MappingTarget::Button(Button::East)));
+        assert!(wizard.feed(RawInput::Button(2)));
+
+        while !wizard.is_done() {
+            wizard.skip();
+        }
+        assert!(!wizard.feed(RawInput::Button(3)));
+        assert_eq!(wizard.progress(), (WIZARD_STEPS.len(), WIZARD_STEPS.len()));
+    }
+
+    #[test]
+    fn sticks_need_axis_past_threshold() {
+        let mut wizard = wizard();
+        while wizard.current() != Some(MappingTarget::Axis(Axis::LeftStickX)) {
+            wizard.skip();
+        }
+        assert!(!wizard.feed(RawInput::Button(1)));
+        assert!(!wizard.feed(RawInput::Axis(0, 0.3)));
+        assert!(wizard.feed(RawInput::Axis(0, -0.9)));
+        assert!(!wizard.feed(RawInput::Axis(0, 0.9)));
+        assert!(wizard.feed(RawInput::Axis(1, 0.9)));
+        assert_eq!(wizard.captured()[1], (MappingTarget::Axis(Axis::LeftStickY), MappedInput::Axis(1)));
+    }
+
+    #[test]
+    fn triggers_accept_pressed_analog_axis() {
+        let mut wizard = wizard();
+        while wizard.current() != Some(MappingTarget::Button(Button::LeftTrigger2)) {
+            wizard.skip();
+        }
+        // 扳机静止时的 -1 不算按下
+        assert!(!wizard.feed(RawInput::Axis(2, -1.0)));
+        assert!(wizard.feed(RawInput::Axis(2, 1.0)));
+        assert!(wizard.feed(RawInput::Button(7)));
+        assert_eq!(wizard.captured(), &[
+            (MappingTarget::Button(Button::LeftTrigger2), MappedInput::Axis(2)),
+            (MappingTarget::Button(Button::RightTrigger2), MappedInput::Button(7)),
+        ]);
+    }
+
+    #[test]
+    fn dpad_accepts_each_direction_of_hat_axis() {
+        let mut wizard = wizard_at_dpad();
+        assert!(wizard.feed(RawInput::Axis(17, -1.0)));
+        // 同一方向不能重复使用，另一个方向可以
+        assert!(!wizard.feed(RawInput::Axis(17, -1.0)));
+        assert!(wizard.feed(RawInput::Axis(17, 1.0)));
+        assert!(wizard.feed(RawInput::Axis(16, -1.0)));
+        assert!(wizard.feed(RawInput::Axis(16, 1.0)));
+        assert_eq!(wizard.captured(), &[
+            (MappingTarget::Button(Button::DPadUp), MappedInput::Hat(17, false)),
+            (MappingTarget::Button(Button::DPadDown), MappedInput::Hat(17, true)),
+            (MappingTarget::Button(Button::DPadLeft), MappedInput::Hat(16, false)),
+            (MappingTarget::Button(Button::DPadRight), MappedInput::Hat(16, true)),
+        ]);
+        // hat 轴已被十字键使用，不能再作为摇杆轴
+        assert!(!wizard.feed(RawInput::Axis(16, 1.0)));
+        assert!(has_hat_mapping(wizard.captured()));
+    }
+
+    #[test]
+    fn complete_mapping_emits_trigger_axes_and_hats() {
+        let captured = [
+            (MappingTarget::Button(Button::South), MappedInput::Button(1)),
+            (MappingTarget::Button(Button::LeftTrigger2), MappedInput::Axis(2)),
+            (MappingTarget::Button(Button::RightTrigger2), MappedInput::Axis(5)),
+            (MappingTarget::Button(Button::DPadUp), MappedInput::Hat(17, false)),
+            (MappingTarget::Button(Button::DPadRight), MappedInput::Hat(16, true)),
+        ];
+        let gilrs_mapping = format!("{},Pad,a:b0,leftz:a2,rightz:a5,leftx:a0,", UUID);
+        assert_eq!(
+            complete_mapping(&gilrs_mapping, &captured),
+            format!("{},Pad,a:b0,lefttrigger:a2,righttrigger:a5,leftx:a0,dpup:h0.1,dpright:h0.2,", UUID)
+        );
+        assert!(!has_hat_mapping(&captured[..3]));
+    }
+}
@@ -0,0 +1,415 @@
+// 手柄输入的录制与回放
+use gilrs::{Axis, Button};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::device_manager::{axis_from_name, button_from_name, DeviceEvent, PadId, TRACKED_AXES, TRACKED_BUTTONS};
+use crate::input::{scripted_device, InputSource, ScriptAction, ScriptStep, ScriptedSource};
+
+// 文件第一行，标识格式和版本
+const HEADER: &str = "GMS1";
+// 录制时采样摇杆的间隔
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(5);
+// 摇杆变化小于该值时不记录
+const AXIS_EPSILON: f32 = 0.0005;
+pub const SESSION_EXTENSION: &str = "gms";
+/// 原始 evdev 事件转储（`cat /dev/input/eventN > 名称.evdump`），Linux 上按 evdev 映射回放
+pub const DUMP_EXTENSION: &str = "evdump";
+
+/// 录制的输入会话
+///
+/// 文件为按行排列的文本，每行一个动作，时间为距离开始录制的毫秒数：
+///
+/// ```text
+/// GMS1
+/// C <毫秒> <编号> <UUID> <名称>
+/// D <毫秒> <编号>
+/// P <毫秒> <编号> <按钮>
+/// R <毫秒> <编号> <按钮>
+/// A <毫秒> <编号> <轴> <值>
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    pub steps: Vec<ScriptStep>,
+}
+
+impl Session {
+    /// 会话时长
+    pub fn duration(&self) -> Duration {
+        self.steps.last().map(|step| step.at).unwrap_or_default()
+    }
+
+    pub fn write_to(&self, mut writer: impl Write) -> std::io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        for step in &self.steps {
+            let ms = step.at.as_millis();
+            match &step.action {
+                ScriptAction::Connect(device) => {
+                    let uuid: String = device.uuid.iter().map(|byte| format!("{:02x}", byte)).collect();
+                    writeln!(writer, "C {} {} {} {}", ms, device.id, uuid, device.name)?
+                }
+                ScriptAction::Disconnect(id) => writeln!(writer, "D {} {}", ms, id)?,
+                ScriptAction::Press(id, button) => writeln!(writer, "P {} {} {:?}", ms, id, button)?,
+                ScriptAction::Release(id, button) => writeln!(writer, "R {} {} {:?}", ms, id, button)?,
+                ScriptAction::Axis(id, axis, value) => writeln!(writer, "A {} {} {:?} {:.4}", ms, id, axis, value)?,
+            }
+        }
+        writer.flush()
+    }
+
+    pub fn read_from(reader: impl BufRead) -> Result<Self, String> {
+        let mut lines = reader.lines().enumerate();
+        match lines.next() {
+            Some((_, Ok(line))) if line.trim() == HEADER => {}
+            _ => return Err("不是有效的录制文件".to_string()),
+        }
+
+        let mut steps = Vec::new();
+        for (index, line) in lines {
+            let line = line.map_err(|e| format!("读取录制文件失败: {}", e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let step = parse_line(&line).map_err(|e| format!("第 {} 行: {}", index + 1, e))?;
+            steps.push(step);
+        }
+        // 按时间排序，手工编辑的文件也能正常回放
+        steps.sort_by_key(|step| step.at);
+        Ok(Self { steps })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("无法创建目录: {}", e))?;
+        }
+        let file = File::create(path).map_err(|e| format!("无法创建录制文件: {}", e))?;
+        self.write_to(BufWriter::new(file)).map_err(|e| format!("无法写入录制文件: {}", e))?;
+        info!("已保存录制文件: {:?}，共 {} 个动作", path, self.steps.len());
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("无法打开录制文件: {}", e))?;
+        let session = Self::read_from(BufReader::new(file))?;
+        info!("已加载录制文件: {:?}，共 {} 个动作", path, session.steps.len());
+        Ok(session)
+    }
+}
+
+/// 解析录制文件中的一行
+fn parse_line(line: &str) -> Result<ScriptStep, String> {
+    let mut fields = line.splitn(5, ' ');
+    let kind = fields.next().unwrap_or_default();
+    let ms: u64 = fields.next()
+        .and_then(|value| value.parse().ok())
+        .ok_or("时间无效")?;
+    let id = fields.next()
+        .and_then(|value| value.parse().ok())
+        .map(PadId)
+        .ok_or("手柄编号无效")?;
+
+    let action = match kind {
+        "C" => {
+            let uuid = fields.next().and_then(parse_uuid).ok_or("UUID 无效")?;
+            let name = fields.next().unwrap_or("未知手柄");
+            ScriptAction::Connect(scripted_device(id.0, name, uuid))
+        }
+        "D" => ScriptAction::Disconnect(id),
+        "P" | "R" => {
+            let name = fields.next().unwrap_or_default();
+            let button = button_from_name(name).ok_or_else(|| format!("未知按钮 {}", name))?;
+            if kind == "P" {
+                ScriptAction::Press(id, button)
+            } else {
+                ScriptAction::Release(id, button)
+            }
+        }
+        "A" => {
+            let name = fields.next().unwrap_or_default();
+            let axis = axis_from_name(name).ok_or_else(|| format!("未知摇杆轴 {}", name))?;
+            let value: f32 = fields.next()
+                .and_then(|value| value.trim().parse().ok())
+                .ok_or("摇杆值无效")?;
+            ScriptAction::Axis(id, axis, value.clamp(-1.0, 1.0))
+        }
+        _ => return Err(format!("未知的动作类型 {}", kind)),
+    };
+
+    Ok(ScriptStep {
+        at: Duration::from_millis(ms),
+        action,
+    })
+}
+
+fn parse_uuid(text: &str) -> Option<[u8; 16]> {
+    if text.len() != 32 {
+        return None;
+    }
+    let mut uuid = [0u8; 16];
+    for (index, byte) in uuid.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(text.get(index * 2..index * 2 + 2)?, 16).ok()?;
+    }
+    Some(uuid)
+}
+
+/// 从输入源录制会话
+///
+/// 后台线程记录手柄的连接、断开和按钮事件，并定期采样摇杆，只记录变化的值。
+pub struct SessionRecorder {
+    running: Arc<AtomicBool>,
+    started: Instant,
+    thread_handle: Option<JoinHandle<Session>>,
+}
+
+impl SessionRecorder {
+    pub fn start(source: Arc<dyn InputSource>) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let running_thread = running.clone();
+        let started = Instant::now();
+        // 在线程启动前订阅，避免错过事件
+        let events = source.subscribe();
+
+        let thread_handle = thread::spawn(move || {
+            let mut steps = Vec::new();
+            let mut axes: HashMap<(PadId, Axis), f32> = HashMap::new();
+            let mut known = Vec::new();
+
+            // 录制开始时已连接的手柄
+            for device in source.devices() {
+                known.push(device.id);
+                let state = source.state(device.id).unwrap_or_default();
+                let pressed: Vec<Button> = TRACKED_BUTTONS.into_iter().filter(|button| state.is_pressed(*button)).collect();
+                steps.push(ScriptStep { at: Duration::ZERO, action: ScriptAction::Connect(device.clone()) });
+                for button in pressed {
+                    steps.push(ScriptStep { at: Duration::ZERO, action: ScriptAction::Press(device.id, button) });
+                }
+            }
+            info!("开始录制手柄输入，已连接 {} 个手柄", known.len());
+
+            while running_thread.load(Ordering::Relaxed) {
+                while let Ok(event) = events.try_recv() {
+                    let at = started.elapsed();
+                    let action = match event {
+                        DeviceEvent::ButtonPressed(id, button) => ScriptAction::Press(id, button),
+                        DeviceEvent::ButtonReleased(id, button) => ScriptAction::Release(id, button),
+                        // 连接和断开按设备列表的变化记录，此时设备信息已经可用
+                        DeviceEvent::DevicesChanged(list) => {
+                            for id in known.iter().filter(|id| !list.iter().any(|device| device.id == **id)) {
+                                steps.push(ScriptStep { at, action: ScriptAction::Disconnect(*id) });
+                                axes.retain(|(pad, _), _| pad != id);
+                            }
+                            for device in list.iter().filter(|device| !known.contains(&device.id)) {
+                                steps.push(ScriptStep { at, action: ScriptAction::Connect(device.clone()) });
+                            }
+                            known = list.iter().map(|device| device.id).collect();
+                            continue;
+                        }
+                        DeviceEvent::Connected(_) | DeviceEvent::Disconnected(_) | DeviceEvent::RawInput(..) => continue,
+                    };
+                    steps.push(ScriptStep { at, action });
+                }
+
+                let at = started.elapsed();
+                for id in &known {
+                    let Some(state) = source.state(*id) else {
+                        continue;
+                    };
+                    for axis in TRACKED_AXES {
+                        let value = state.value(axis);
+                        let last = axes.entry((*id, axis)).or_insert(0.0);
+                        if (value - *last).abs() > AXIS_EPSILON {
+                            *last = value;
+                            steps.push(ScriptStep { at, action: ScriptAction::Axis(*id, axis, value) });
+                        }
+                    }
+                }
+
+                thread::sleep(SAMPLE_INTERVAL);
+            }
+
+            Session { steps }
+        });
+
+        Self {
+            running,
+            started,
+            thread_handle: Some(thread_handle),
+        }
+    }
+
+    /// 已录制的时长
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// 停止录制并返回录制的会话
+    pub fn stop(mut self) -> Session {
+        self.running.store(false, Ordering::Relaxed);
+        let session = self.thread_handle.take()
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default();
+        info!("录制结束，共 {} 个动作", session.steps.len());
+        session
+    }
+}
+
+impl Drop for SessionRecorder {
+    fn drop(&mut self) {
+        if self.thread_handle.is_some() {
+            warn!("录制未保存即被丢弃");
+            self.running.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 保存录制文件的目录
+pub fn sessions_dir() -> PathBuf {
+    Config::get_config_dir().join("sessions")
+}
+
+/// 新录制文件的默认路径
+pub fn new_session_path() -> PathBuf {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    sessions_dir().join(format!("session-{}.{}", stamp, SESSION_EXTENSION))
+}
+
+/// 录制目录中的录制文件，最新的在前
+pub fn list_sessions() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(sessions_dir()) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == SESSION_EXTENSION || ext == DUMP_EXTENSION))
+        .collect();
+    files.sort();
+    files.reverse();
+    files
+}
+
+/// 可回放的文件
+pub enum Replay {
+    Session(Session),
+    #[cfg(target_os = "linux")]
+    Dump(Arc<crate::evdev::EvdevSource>),
+}
+
+impl Replay {
+    /// 开始回放，返回回放的输入源和描述，`name` 为文件名
+    pub fn start(self, name: &str) -> (Arc<dyn InputSource>, String) {
+        match self {
+            Replay::Session(recorded) => {
+                let message = format!("正在回放: {}，时长 {:.1} 秒", name, recorded.duration().as_secs_f32());
+                (ScriptedSource::start(recorded.steps) as Arc<dyn InputSource>, message)
+            }
+            #[cfg(target_os = "linux")]
+            Replay::Dump(source) => (source as Arc<dyn InputSource>, format!("正在回放 evdev 转储: {}", name)),
+        }
+    }
+}
+
+/// 按扩展名加载录制文件或 evdev 转储
+pub fn load_replay(path: &Path) -> Result<Replay, String> {
+    if path.extension().is_some_and(|ext| ext == DUMP_EXTENSION) {
+        #[cfg(target_os = "linux")]
+        return crate::evdev::EvdevSource::load_dump(path).map(Replay::Dump);
+        #[cfg(not(target_os = "linux"))]
+        return Err("evdev 转储只能在 Linux 上回放".to_string());
+    }
+    Session::load(path).map(Replay::Session)
+}
+
+/// 命令行中与录制和回放相关的参数
+#[derive(Clone, Debug, Default)]
+pub struct SessionArgs {
+    /// 启动后录制输入，退出时保存到该文件
+    pub record: Option<PathBuf>,
+    /// 启动后回放该录制文件
+    pub replay: Option<PathBuf>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(millis: u64, action: ScriptAction) -> ScriptStep {
+        ScriptStep { at: Duration::from_millis(millis), action }
+    }
+
+    fn to_text(session: &Session) -> String {
+        let mut out = Vec::new();
+        session.write_to(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn session_round_trips_through_text() {
+        let mut uuid = [0u8; 16];
+        uuid[0] = 0x03;
+        uuid[15] = 0xfe;
+        let session = Session {
+            steps: vec![
+                step(0, ScriptAction::Connect(scripted_device(2, "Xbox Wireless Controller", uuid))),
+                step(15, ScriptAction::Press(PadId(2), Button::South)),
+                step(40, ScriptAction::Axis(PadId(2), Axis::LeftStickX, -0.5)),
+                step(90, ScriptAction::Release(PadId(2), Button::South)),
+                step(1200, ScriptAction::Disconnect(PadId(2))),
+            ],
+        };
+        let text = to_text(&session);
+        assert!(text.starts_with("GMS1\nC 0 2 030000000000000000000000000000fe Xbox Wireless Controller\n"));
+
+        let loaded = Session::read_from(text.as_bytes()).unwrap();
+        assert_eq!(to_text(&loaded), text);
+        assert_eq!(loaded.duration(), Duration::from_millis(1200));
+        match &loaded.steps[0].action {
+            ScriptAction::Connect(device) => {
+                assert_eq!(device.id, PadId(2));
+                assert_eq!(device.uuid, uuid);
+                assert_eq!(device.name, "Xbox Wireless Controller");
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(loaded.steps[2].action, ScriptAction::Axis(PadId(2), Axis::LeftStickX, value) if value == -0.5));
+    }
+
+    #[test]
+    fn steps_are_sorted_and_values_clamped() {
+        let text = "GMS1\nR 50 0 South\n\nP 10 0 South\nA 20 0 RightStickY 3.5\n";
+        let session = Session::read_from(text.as_bytes()).unwrap();
+        let times: Vec<u128> = session.steps.iter().map(|step| step.at.as_millis()).collect();
+        assert_eq!(times, vec![10, 20, 50]);
+        assert!(matches!(session.steps[1].action, ScriptAction::Axis(_, Axis::RightStickY, value) if value == 1.0));
+    }
+
+    #[test]
+    fn rejects_missing_header() {
+        for text in ["", "P 10 0 South\n", "GMS2\nP 10 0 South\n"] {
+            assert_eq!(Session::read_from(text.as_bytes()).unwrap_err(), "不是有效的录制文件");
+        }
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let cases = [
+            ("GMS1\nP 10 0 South\nX 20 0\n", "第 3 行: 未知的动作类型 X"),
+            ("GMS1\nP abc 0 South\n", "第 2 行: 时间无效"),
+            ("GMS1\n\nP 10 0 South\nP 20 zero South\n", "第 4 行: 手柄编号无效"),
+            ("GMS1\nP 10 0 Jump\n", "第 2 行: 未知按钮 Jump"),
+            ("GMS1\nA 10 0 Wheel 0.5\n", "第 2 行: 未知摇杆轴 Wheel"),
+            ("GMS1\nA 10 0 LeftStickX fast\n", "第 2 行: 摇杆值无效"),
+            ("GMS1\nC 10 0 1234 Pad\n", "第 2 行: UUID 无效"),
+        ];
+        for (text, error) in cases {
+            assert_eq!(Session::read_from(text.as_bytes()).unwrap_err(), error);
+        }
+    }
+}
@@ -0,0 +1,391 @@
+// Linux uinput 输出后端
+use enigo::Key;
+use log::{info, warn};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::event_bus::MouseAction;
+use crate::output::OutputSink;
+
+// linux/input-event-codes.h
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const SYN_REPORT: u16 = 0;
+const REL_X: u16 = 0x00;
+const REL_Y: u16 = 0x01;
+const REL_HWHEEL: u16 = 0x06;
+const REL_WHEEL: u16 = 0x08;
+const REL_WHEEL_HI_RES: u16 = 0x0b;
+const REL_HWHEEL_HI_RES: u16 = 0x0c;
+const BTN_LEFT: u16 = 0x110;
+const BTN_RIGHT: u16 = 0x111;
+const BTN_MIDDLE: u16 = 0x112;
+const BTN_SIDE: u16 = 0x113;
+const BTN_EXTRA: u16 = 0x114;
+// 注册的键盘按键范围
+const KEY_MAX_REGISTERED: u16 = 248;
+// 高精度滚轮每格的值
+const HI_RES_PER_DETENT: i32 = 120;
+const BUS_VIRTUAL: u16 = 0x06;
+
+// linux/uinput.h
+const UI_DEV_CREATE: libc::c_ulong = 0x5501;
+const UI_DEV_DESTROY: libc::c_ulong = 0x5502;
+const UI_DEV_SETUP: libc::c_ulong = 0x405c_5503;
+const UI_SET_EVBIT: libc::c_ulong = 0x4004_5564;
+const UI_SET_KEYBIT: libc::c_ulong = 0x4004_5565;
+const UI_SET_RELBIT: libc::c_ulong = 0x4004_5566;
+
+const DEVICE_NAME: &str = "Gamepad Mouse Control";
+
+#[repr(C)]
+struct InputId {
+    bustype: u16,
+    vendor: u16,
+    product: u16,
+    version: u16,
+}
+
+#[repr(C)]
+struct UinputSetup {
+    id: InputId,
+    name: [u8; 80],
+    ff_effects_max: u32,
+}
+
+/// 通过 `/dev/uinput` 创建虚拟鼠标和键盘
+///
+/// 不依赖 X 显示服务器，在 Wayland 会话和纯终端下也能工作。
+/// 事件以 `input_event` 结构写入 `writer`，测试时可以传入任意 `Write` 代替真实设备。
+/// uinput 无法读取光标位置，`location` 返回按相对移动累计的位置。
+pub struct UinputSink {
+    writer: Box<dyn Write + Send>,
+    // 真实设备的文件描述符，释放时销毁虚拟设备
+    device_fd: Option<RawFd>,
+    position: (i32, i32),
+}
+
+impl UinputSink {
+    /// 打开 `/dev/uinput` 并创建虚拟设备
+    pub fn open() -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/uinput")
+            .map_err(|e| format!("无法打开 /dev/uinput: {}（需要 uinput 模块和写入权限）", e))?;
+        let fd = file.as_raw_fd();
+        setup_device(fd)?;
+        info!("已创建 uinput 虚拟设备: {}", DEVICE_NAME);
+        let mut sink = Self::with_writer(file);
+        sink.device_fd = Some(fd);
+        Ok(sink)
+    }
+
+    /// 把事件写入指定的输出，不创建真实设备
+    pub fn with_writer(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Box::new(writer),
+            device_fd: None,
+            position: (0, 0),
+        }
+    }
+
+    /// 写入一组事件并同步
+    fn emit(&mut self, events: &[(u16, u16, i32)]) {
+        let mut buffer = Vec::with_capacity((events.len() + 1) * event_size());
+        for (kind, code, value) in events.iter().chain(&[(EV_SYN, SYN_REPORT, 0)]) {
+            encode_event(&mut buffer, *kind, *code, *value);
+        }
+        if let Err(e) = self.writer.write_all(&buffer) {
+            warn!("写入 uinput 事件失败: {}", e);
+        }
+    }
+
+    fn key_event(&mut self, key: Key, value: i32) {
+        match key_code(key) {
+            Some(code) => self.emit(&[(EV_KEY, code, value)]),
+            None => warn!("uinput 不支持按键 {:?}", key),
+        }
+    }
+}
+
+impl OutputSink for UinputSink {
+    fn move_relative(&mut self, dx: i32, dy: i32) {
+        self.position = (self.position.0 + dx, self.position.1 + dy);
+        self.emit(&[(EV_REL, REL_X, dx), (EV_REL, REL_Y, dy)]);
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) {
+        // 没有绝对坐标，按累计位置换算成相对移动
+        let (dx, dy) = (x - self.position.0, y - self.position.1);
+        self.move_relative(dx, dy);
+    }
+
+    fn location(&self) -> (i32, i32) {
+        self.position
+    }
+
+    fn button_down(&mut self, button: MouseAction) {
+        self.emit(&[(EV_KEY, button_code(button), 1)]);
+    }
+
+    fn button_up(&mut self, button: MouseAction) {
+        self.emit(&[(EV_KEY, button_code(button), 0)]);
+    }
+
+    fn scroll(&mut self, horizontal: bool, amount: i32) {
+        // 与 enigo 一致：正值向下/向右滚动，而 REL_WHEEL 正值向上
+        if horizontal {
+            self.emit(&[
+                (EV_REL, REL_HWHEEL, amount),
+                (EV_REL, REL_HWHEEL_HI_RES, amount * HI_RES_PER_DETENT),
+            ]);
+        } else {
+            self.emit(&[
+                (EV_REL, REL_WHEEL, -amount),
+                (EV_REL, REL_WHEEL_HI_RES, -amount * HI_RES_PER_DETENT),
+            ]);
+        }
+    }
+
+    fn key_down(&mut self, key: Key) {
+        self.key_event(key, 1);
+    }
+
+    fn key_up(&mut self, key: Key) {
+        self.key_event(key, 0);
+    }
+}
+
+impl Drop for UinputSink {
+    fn drop(&mut self) {
+        if let Some(fd) = self.device_fd {
+            // SAFETY: fd 由 writer 中的文件持有，此时尚未关闭
+            unsafe {
+                libc::ioctl(fd, UI_DEV_DESTROY);
+            }
+            info!("已销毁 uinput 虚拟设备");
+        }
+    }
+}
+
+/// 注册事件类型并创建虚拟设备
+fn setup_device(fd: RawFd) -> Result<(), String> {
+    let ioctl = |request: libc::c_ulong, value: libc::c_int, what: &str| -> Result<(), String> {
+        // SAFETY: fd 是已打开的 uinput 设备，value 按值传递
+        if unsafe { libc::ioctl(fd, request, value) } < 0 {
+            return Err(format!("uinput {} 失败: {}", what, std::io::Error::last_os_error()));
+        }
+        Ok(())
+    };
+
+    ioctl(UI_SET_EVBIT, EV_KEY as libc::c_int, "注册按键事件")?;
+    ioctl(UI_SET_EVBIT, EV_REL as libc::c_int, "注册相对移动事件")?;
+    for code in [REL_X, REL_Y, REL_WHEEL, REL_HWHEEL, REL_WHEEL_HI_RES, REL_HWHEEL_HI_RES] {
+        ioctl(UI_SET_RELBIT, code as libc::c_int, "注册相对轴")?;
+    }
+    for code in (1..=KEY_MAX_REGISTERED).chain(BTN_LEFT..=BTN_EXTRA) {
+        ioctl(UI_SET_KEYBIT, code as libc::c_int, "注册按键")?;
+    }
+
+    let mut setup = UinputSetup {
+        id: InputId {
+            bustype: BUS_VIRTUAL,
+            vendor: 0x1209,
+            product: 0x0001,
+            version: 1,
+        },
+        name: [0; 80],
+        ff_effects_max: 0,
+    };
+    setup.name[..DEVICE_NAME.len()].copy_from_slice(DEVICE_NAME.as_bytes());
+
+    // SAFETY: setup 为与内核一致的 repr(C) 结构
+    if unsafe { libc::ioctl(fd, UI_DEV_SETUP, &setup as *const UinputSetup) } < 0 {
+        return Err(format!("uinput 设置设备失败: {}", std::io::Error::last_os_error()));
+    }
+    // SAFETY: 无参数的 ioctl
+    if unsafe { libc::ioctl(fd, UI_DEV_CREATE) } < 0 {
+        return Err(format!("uinput 创建设备失败: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+fn event_size() -> usize {
+    std::mem::size_of::<libc::input_event>()
+}
+
+/// 按 `input_event` 的内存布局编码一个事件，时间戳由内核填写
+fn encode_event(buffer: &mut Vec<u8>, kind: u16, code: u16, value: i32) {
+    buffer.extend(std::iter::repeat_n(0u8, std::mem::size_of::<libc::timeval>()));
+    buffer.extend_from_slice(&kind.to_ne_bytes());
+    buffer.extend_from_slice(&code.to_ne_bytes());
+    buffer.extend_from_slice(&value.to_ne_bytes());
+}
+
+fn button_code(button: MouseAction) -> u16 {
+    match button {
+        MouseAction::Left => BTN_LEFT,
+        MouseAction::Right => BTN_RIGHT,
+        MouseAction::Middle => BTN_MIDDLE,
+        MouseAction::Back => BTN_SIDE,
+        MouseAction::Forward => BTN_EXTRA,
+    }
+}
+
+/// enigo 按键对应的 Linux 键码
+fn key_code(key: Key) -> Option<u16> {
+    let code = match key {
+        Key::Escape => 1,
+        Key::Backspace => 14,
+        Key::Tab => 15,
+        Key::Return => 28,
+        Key::Control => 29,
+        Key::Shift => 42,
+        Key::Alt => 56,
+        Key::Space => 57,
+        Key::CapsLock => 58,
+        Key::F1 => 59,
+        Key::F2 => 60,
+        Key::F3 => 61,
+        Key::F4 => 62,
+        Key::F5 => 63,
+        Key::F6 => 64,
+        Key::F7 => 65,
+        Key::F8 => 66,
+        Key::F9 => 67,
+        Key::F10 => 68,
+        Key::F11 => 87,
+        Key::F12 => 88,
+        Key::Home => 102,
+        Key::UpArrow => 103,
+        Key::PageUp => 104,
+        Key::LeftArrow => 105,
+        Key::RightArrow => 106,
+        Key::End => 107,
+        Key::DownArrow => 108,
+        Key::PageDown => 109,
+        Key::Delete => 111,
+        Key::Meta => 125,
+        Key::Layout(c) => return layout_code(c),
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// 字母和数字在美式键盘上的键码
+fn layout_code(c: char) -> Option<u16> {
+    const ROWS: [(&str, u16); 4] = [
+        ("1234567890", 2),
+        ("qwertyuiop", 16),
+        ("asdfghjkl", 30),
+        ("zxcvbnm", 44),
+    ];
+    let c = c.to_ascii_lowercase();
+    ROWS.iter().find_map(|(row, start)| {
+        row.find(c).map(|index| start + index as u16)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// 可以在交给输出端后读取内容的缓冲区
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(data)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// 按 `input_event` 的布局解码写入的事件，返回（类型, 键码, 值）
+    fn decode(buffer: &SharedBuffer) -> Vec<(u16, u16, i32)> {
+        let bytes = std::mem::take(&mut *buffer.0.lock().unwrap());
+        assert_eq!(bytes.len() % event_size(), 0);
+        let header = std::mem::size_of::<libc::timeval>();
+        bytes.chunks(event_size())
+            .map(|event| {
+                assert!(event[..header].iter().all(|byte| *byte == 0), "时间戳应由内核填写");
+                let field = &event[header..];
+                (
+                    u16::from_ne_bytes([field[0], field[1]]),
+                    u16::from_ne_bytes([field[2], field[3]]),
+                    i32::from_ne_bytes([field[4], field[5], field[6], field[7]]),
+                )
+            })
+            .collect()
+    }
+
+    fn sink() -> (UinputSink, SharedBuffer) {
+        let buffer = SharedBuffer::default();
+        (UinputSink::with_writer(buffer.clone()), buffer)
+    }
+
+    #[test]
+    fn encodes_kernel_input_event_layout() {
+        let mut buffer = Vec::new();
+        encode_event(&mut buffer, EV_REL, REL_X, -3);
+        assert_eq!(buffer.len(), event_size());
+        // SAFETY: 缓冲区大小与 input_event 相同，且 input_event 的任意字节都是有效值
+        let event: libc::input_event = unsafe { std::ptr::read_unaligned(buffer.as_ptr().cast()) };
+        assert_eq!((event.type_, event.code, event.value), (EV_REL, REL_X, -3));
+    }
+
+    #[test]
+    fn move_writes_relative_axes() {
+        let (mut sink, buffer) = sink();
+        sink.move_relative(5, -7);
+        assert_eq!(decode(&buffer), vec![(EV_REL, REL_X, 5), (EV_REL, REL_Y, -7), (EV_SYN, SYN_REPORT, 0)]);
+
+        // 绝对移动按累计位置换算成相对移动
+        sink.move_to(10, 10);
+        assert_eq!(sink.location(), (10, 10));
+        assert_eq!(decode(&buffer), vec![(EV_REL, REL_X, 5), (EV_REL, REL_Y, 17), (EV_SYN, SYN_REPORT, 0)]);
+    }
+
+    #[test]
+    fn scroll_writes_wheel_and_hi_res_wheel() {
+        let (mut sink, buffer) = sink();
+        sink.scroll(false, 2);
+        assert_eq!(
+            decode(&buffer),
+            vec![(EV_REL, REL_WHEEL, -2), (EV_REL, REL_WHEEL_HI_RES, -240), (EV_SYN, SYN_REPORT, 0)]
+        );
+        sink.scroll(true, -1);
+        assert_eq!(
+            decode(&buffer),
+            vec![(EV_REL, REL_HWHEEL, -1), (EV_REL, REL_HWHEEL_HI_RES, -120), (EV_SYN, SYN_REPORT, 0)]
+        );
+    }
+
+    #[test]
+    fn buttons_and_keys_write_key_events() {
+        let (mut sink, buffer) = sink();
+        sink.click(MouseAction::Right);
+        assert_eq!(
+            decode(&buffer),
+            vec![(EV_KEY, BTN_RIGHT, 1), (EV_SYN, SYN_REPORT, 0), (EV_KEY, BTN_RIGHT, 0), (EV_SYN, SYN_REPORT, 0)]
+        );
+        sink.key_down(Key::Layout('A'));
+        sink.key_up(Key::Space);
+        assert_eq!(
+            decode(&buffer),
+            vec![(EV_KEY, 30, 1), (EV_SYN, SYN_REPORT, 0), (EV_KEY, 57, 0), (EV_SYN, SYN_REPORT, 0)]
+        );
+
+        // 不支持的按键不写入任何事件
+        sink.key_down(Key::Layout('#'));
+        assert_eq!(decode(&buffer), Vec::new());
+    }
+}
@@ -0,0 +1,497 @@
+// 配置检查
+//
+// 检查配置中的取值范围、按钮名称和档案引用。错误表示该值无法使用，加载时会恢复为默认值；
+// 警告表示该值可以使用但可能不是用户想要的，只提示不修改。
+// 类型不对的值（例如灵敏度写成字符串）在反序列化之前就按 JSON 值逐项修复，不影响文件的其余部分。
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::config::{Config, BUTTON_NAMES};
+use crate::battery::BatterySettings;
+use crate::connection::Backoff;
+use crate::haptics::HapticCue;
+use crate::profile::ProfileStore;
+
+/// 问题的严重程度
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Severity::Error => "错误",
+            Severity::Warning => "警告",
+        }
+    }
+}
+
+/// 一条检查结果，`path` 为出问题的字段，例如 `profiles[0].config.dead_zone`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    pub fn error(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { severity: Severity::Error, path: path.into(), message: message.into() }
+    }
+
+    pub fn warning(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, path: path.into(), message: message.into() }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}: {}", self.severity.display_name(), self.message)
+        } else {
+            write!(f, "{}: {}: {}", self.severity.display_name(), self.path, self.message)
+        }
+    }
+}
+
+/// 检查档案库，不修改任何值
+pub fn validate_store(store: &ProfileStore) -> Vec<ValidationIssue> {
+    let mut checker = Checker { issues: Vec::new(), repair: false };
+    checker.check_store(&mut store.clone());
+    checker.issues
+}
+
+/// 检查档案库并把有错误的值恢复为默认值，返回发现的问题
+pub fn repair_store(store: &mut ProfileStore) -> Vec<ValidationIssue> {
+    let mut checker = Checker { issues: Vec::new(), repair: true };
+    checker.check_store(store);
+    checker.issues
+}
+
+/// 修复无法反序列化为 `ProfileStore` 的值，返回发现的问题
+///
+/// `value` 应已补齐默认值。整个值可以反序列化时不做任何检查；否则逐项检查，
+/// 把无法使用的值恢复为同一位置的默认值，没有默认值的项（例如映射表中的条目）直接删除。
+pub fn repair_value(value: &mut Value) -> Result<Vec<ValidationIssue>, String> {
+    if ProfileStore::deserialize(&*value).is_ok() {
+        return Ok(Vec::new());
+    }
+    let defaults = serde_json::to_value(ProfileStore::default()).map_err(|e| format!("默认配置序列化失败: {}", e))?;
+    let mut repair = ValueRepair { defaults: &defaults, issues: Vec::new() };
+    repair.check(value, Some(&defaults), &mut Vec::new(), "");
+    Ok(repair.issues)
+}
+
+/// 从档案库顶层到某个值的路径，数组元素统一放到默认档案库对应数组的第一个位置检查
+enum Step {
+    Key(String),
+    Element,
+}
+
+struct ValueRepair<'a> {
+    defaults: &'a Value,
+    issues: Vec<ValidationIssue>,
+}
+
+impl ValueRepair<'_> {
+    /// 逐项检查 `value`，`default` 为同一位置的默认值；返回 false 表示该项应被删除
+    fn check(&mut self, value: &mut Value, default: Option<&Value>, steps: &mut Vec<Step>, path: &str) -> bool {
+        match (value, default) {
+            (Value::Object(object), Some(Value::Object(defaults))) => {
+                let keys: Vec<String> = object.keys().cloned().collect();
+                for key in keys {
+                    let Some(child) = object.get_mut(&key) else {
+                        continue;
+                    };
+                    let child_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                    steps.push(Step::Key(key.clone()));
+                    let keep = self.check(child, defaults.get(&key), steps, &child_path);
+                    steps.pop();
+                    if !keep {
+                        object.remove(&key);
+                    }
+                }
+                true
+            }
+            (Value::Array(items), Some(Value::Array(defaults))) => {
+                steps.push(Step::Element);
+                let mut index = 0;
+                items.retain_mut(|item| {
+                    let keep = self.check(item, defaults.first(), steps, &format!("{}[{}]", path, index));
+                    index += 1;
+                    keep
+                });
+                steps.pop();
+                true
+            }
+            (value, default) => {
+                // 只把这一项放进默认档案库，能反序列化就说明这一项没有问题
+                let Some(trial) = with_value(self.defaults, steps, value) else {
+                    return true;
+                };
+                let Err(e) = ProfileStore::deserialize(&trial) else {
+                    return true;
+                };
+                match default {
+                    Some(default) => {
+                        self.issues.push(ValidationIssue::error(path, format!("{}，已恢复为默认值 {}", e, default)));
+                        *value = default.clone();
+                        true
+                    }
+                    None => {
+                        self.issues.push(ValidationIssue::error(path, format!("{}，已删除该项", e)));
+                        false
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 在默认档案库的 `steps` 位置放入 `value`
+fn with_value(defaults: &Value, steps: &[Step], value: &Value) -> Option<Value> {
+    let mut trial = defaults.clone();
+    let mut slot = &mut trial;
+    for step in steps {
+        slot = match step {
+            Step::Key(key) => slot.as_object_mut()?.entry(key.clone()).or_insert(Value::Null),
+            Step::Element => {
+                let items = slot.as_array_mut()?;
+                items.truncate(1);
+                if items.is_empty() {
+                    items.push(Value::Object(Map::new()));
+                }
+                &mut items[0]
+            }
+        };
+    }
+    *slot = value.clone();
+    Some(trial)
+}
+
+/// 常见的按钮简称，用于提示正确的名称
+fn suggest_button(name: &str) -> Option<&'static str> {
+    let suggestion = match name.to_ascii_uppercase().as_str() {
+        "A" => "South",
+        "B" => "East",
+        "X" => "West",
+        "Y" => "North",
+        "LB" | "L1" => "LeftTrigger",
+        "RB" | "R1" => "RightTrigger",
+        "LT" | "L2" => "LeftTrigger2",
+        "RT" | "R2" => "RightTrigger2",
+        "L3" | "LS" => "LeftThumb",
+        "R3" | "RS" => "RightThumb",
+        "BACK" | "VIEW" | "SHARE" => "Select",
+        "MENU" | "OPTIONS" => "Start",
+        "GUIDE" | "HOME" | "PS" => "Mode",
+        _ => return BUTTON_NAMES.iter().find(|valid| valid.eq_ignore_ascii_case(name)).copied(),
+    };
+    Some(suggestion)
+}
+
+struct Checker {
+    issues: Vec<ValidationIssue>,
+    repair: bool,
+}
+
+impl Checker {
+    /// 记录错误，修复模式下把值改为默认值
+    fn reject<T: fmt::Debug>(&mut self, path: String, message: String, value: &mut T, default: T) {
+        let message = if self.repair {
+            format!("{}，已恢复为默认值 {:?}", message, default)
+        } else {
+            message
+        };
+        self.issues.push(ValidationIssue::error(path, message));
+        if self.repair {
+            *value = default;
+        }
+    }
+
+    /// 检查数值是否有限且在范围内
+    fn check_range(&mut self, path: String, value: &mut f32, min: f32, max: f32, default: f32) {
+        if !value.is_finite() || *value < min || *value > max {
+            let message = format!("{} 超出有效范围 {} 到 {}", value, min, max);
+            self.reject(path, message, value, default);
+        }
+    }
+
+    /// 检查数值是否为有限的正数
+    fn check_positive(&mut self, path: String, value: &mut f32, default: f32) {
+        if !value.is_finite() || *value <= 0.0 {
+            let message = format!("{} 必须大于 0", value);
+            self.reject(path, message, value, default);
+        }
+    }
+
+    fn check_store(&mut self, store: &mut ProfileStore) {
+        let mut names = HashSet::new();
+        for (index, profile) in store.profiles.iter_mut().enumerate() {
+            let path = format!("profiles[{}]", index);
+            if profile.name.trim().is_empty() {
+                self.issues.push(ValidationIssue::warning(format!("{}.name", path), "档案名称为空"));
+            } else if !names.insert(profile.name.clone()) {
+                self.issues.push(ValidationIssue::warning(
+                    format!("{}.name", path),
+                    format!("档案名称 {} 重复，只有第一个能被选中", profile.name),
+                ));
+            }
+            self.check_config(&format!("{}.config", path), &mut profile.config);
+        }
+
+        if !names.contains(&store.active_profile) {
+            self.issues.push(ValidationIssue::warning(
+                "active_profile",
+                format!("激活档案 {} 不存在", store.active_profile),
+            ));
+        }
+        for (index, rule) in store.app_rules.iter().enumerate() {
+            if !names.contains(&rule.profile) {
+                self.issues.push(ValidationIssue::warning(
+                    format!("app_rules[{}].profile", index),
+                    format!("应用 {} 对应的档案 {} 不存在", rule.app, rule.profile),
+                ));
+            }
+        }
+        for (model, profile) in &store.model_profiles {
+            if !names.contains(profile) {
+                self.issues.push(ValidationIssue::warning(
+                    format!("model_profiles.{:?}", model),
+                    format!("手柄型号使用的档案 {} 不存在", profile),
+                ));
+            }
+        }
+        for (pad, profile) in &store.pad_profiles {
+            // 空档案表示跟随激活档案
+            if !profile.is_empty() && !names.contains(profile) {
+                self.issues.push(ValidationIssue::warning(
+                    format!("pad_profiles[\"{}\"]", pad),
+                    format!("手柄使用的档案 {} 不存在", profile),
+                ));
+            }
+        }
+
+        if store.reconnect_timeout_secs == 0 {
+            let message = "等待时间必须大于 0".to_string();
+            self.reject("reconnect_timeout_secs".to_string(), message, &mut store.reconnect_timeout_secs, ProfileStore::default().reconnect_timeout_secs);
+        }
+        self.check_backoff(&mut store.connection_backoff);
+
+        for cue in HapticCue::ALL {
+            let Some(setting) = store.haptics.cues.get_mut(&cue) else {
+                continue;
+            };
+            let path = format!("haptics.cues.{:?}.pattern", cue);
+            let default = cue.default_pattern();
+            self.check_range(format!("{}.strong", path), &mut setting.pattern.strong, 0.0, 1.0, default.strong);
+            self.check_range(format!("{}.weak", path), &mut setting.pattern.weak, 0.0, 1.0, default.weak);
+            if setting.pattern.duration_ms == 0 || setting.pattern.duration_ms > 5000 {
+                let message = format!("时长 {} 毫秒超出有效范围 1 到 5000", setting.pattern.duration_ms);
+                self.reject(format!("{}.duration_ms", path), message, &mut setting.pattern.duration_ms, default.duration_ms);
+            }
+        }
+
+        if store.battery.threshold == 0 || store.battery.threshold > 100 {
+            let message = format!("{}% 超出有效范围 1% 到 100%", store.battery.threshold);
+            self.reject("battery.threshold".to_string(), message, &mut store.battery.threshold, BatterySettings::default().threshold);
+        }
+    }
+
+    fn check_backoff(&mut self, backoff: &mut Backoff) {
+        let defaults = Backoff::default();
+        self.check_positive("connection_backoff.initial_secs".to_string(), &mut backoff.initial_secs, defaults.initial_secs);
+        self.check_positive("connection_backoff.max_secs".to_string(), &mut backoff.max_secs, defaults.max_secs);
+        self.check_range("connection_backoff.factor".to_string(), &mut backoff.factor, 1.0, 10.0, defaults.factor);
+        if backoff.max_secs < backoff.initial_secs {
+            self.issues.push(ValidationIssue::warning(
+                "connection_backoff.max_secs",
+                format!("最长间隔 {} 秒小于初始间隔 {} 秒", backoff.max_secs, backoff.initial_secs),
+            ));
+        }
+    }
+
+    fn check_config(&mut self, path: &str, config: &mut Config) {
+        let defaults = Config::default();
+        self.check_positive(format!("{}.mouse_sensitivity", path), &mut config.mouse_sensitivity, defaults.mouse_sensitivity);
+        self.check_range(format!("{}.dead_zone", path), &mut config.dead_zone, 0.0, 0.99, defaults.dead_zone);
+        self.check_positive(format!("{}.scroll_sensitivity", path), &mut config.scroll_sensitivity, defaults.scroll_sensitivity);
+        self.check_positive(format!("{}.mouse_acceleration", path), &mut config.mouse_acceleration, defaults.mouse_acceleration);
+        if config.dead_zone > 0.5 {
+            self.issues.push(ValidationIssue::warning(
+                format!("{}.dead_zone", path),
+                format!("死区 {} 较大，摇杆需要推到很远才会移动光标", config.dead_zone),
+            ));
+        }
+
+        let mut bound: Vec<(&'static str, String)> = Vec::new();
+        for (field, value, default) in button_fields(config, &defaults) {
+            let field_path = format!("{}.{}", path, field);
+            if !value.is_empty() && !BUTTON_NAMES.contains(&value.as_str()) {
+                let message = match suggest_button(value) {
+                    Some(suggestion) => format!("未知按钮名称 \"{}\"，是否应为 \"{}\"", value, suggestion),
+                    None => format!("未知按钮名称 \"{}\"", value),
+                };
+                self.reject(field_path, message, value, default);
+                continue;
+            }
+            if value.is_empty() {
+                continue;
+            }
+            if let Some((other, _)) = bound.iter().find(|(_, button)| button == value) {
+                self.issues.push(ValidationIssue::warning(
+                    field_path,
+                    format!("按钮 {} 同时绑定了 {}", value, other),
+                ));
+            } else {
+                bound.push((field, value.clone()));
+            }
+        }
+    }
+}
+
+/// 配置中所有按钮绑定字段，以及各自的默认值
+fn button_fields<'a>(config: &'a mut Config, defaults: &Config) -> [(&'static str, &'a mut String, String); 14] {
+    [
+        ("left_click_button", &mut config.left_click_button, defaults.left_click_button.clone()),
+        ("right_click_button", &mut config.right_click_button, defaults.right_click_button.clone()),
+        ("middle_click_button", &mut config.middle_click_button, defaults.middle_click_button.clone()),
+        ("double_click_button", &mut config.double_click_button, defaults.double_click_button.clone()),
+        ("triple_click_button", &mut config.triple_click_button, defaults.triple_click_button.clone()),
+        ("back_button", &mut config.back_button, defaults.back_button.clone()),
+        ("forward_button", &mut config.forward_button, defaults.forward_button.clone()),
+        ("scroll_left_button", &mut config.scroll_left_button, defaults.scroll_left_button.clone()),
+        ("scroll_right_button", &mut config.scroll_right_button, defaults.scroll_right_button.clone()),
+        ("save_position_button", &mut config.save_position_button, defaults.save_position_button.clone()),
+        ("saved_position_click_button", &mut config.saved_position_click_button, defaults.saved_position_click_button.clone()),
+        ("profile_cycle_button", &mut config.profile_cycle_button, defaults.profile_cycle_button.clone()),
+        ("precision_mode_button", &mut config.precision_mode_button, defaults.precision_mode_button.clone()),
+        ("turbo_mode_button", &mut config.turbo_mode_button, defaults.turbo_mode_button.clone()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_focus::AppProfileRule;
+    use crate::controller_model::ControllerModel;
+    use crate::migration;
+    use serde_json::json;
+
+    fn paths(issues: &[ValidationIssue]) -> Vec<&str> {
+        issues.iter().map(|issue| issue.path.as_str()).collect()
+    }
+
+    #[test]
+    fn suggests_button_names() {
+        let mut store = ProfileStore::default();
+        store.profiles[0].config.left_click_button = "LB".to_string();
+        store.profiles[0].config.right_click_button = "start".to_string();
+        let issues = validate_store(&store);
+        assert_eq!(paths(&issues), ["profiles[0].config.left_click_button", "profiles[0].config.right_click_button"]);
+        assert!(issues[0].is_error());
+        assert_eq!(issues[0].message, "未知按钮名称 \"LB\"，是否应为 \"LeftTrigger\"");
+        assert_eq!(issues[1].message, "未知按钮名称 \"start\"，是否应为 \"Start\"");
+        // 只检查时不修改
+        assert_eq!(store.profiles[0].config.left_click_button, "LB");
+
+        let issues = repair_store(&mut store);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(store.profiles[0].config.left_click_button, Config::default().left_click_button);
+        assert!(validate_store(&store).is_empty());
+    }
+
+    #[test]
+    fn repairs_out_of_range_values() {
+        let defaults = Config::default();
+        let mut store = ProfileStore::default();
+        store.profiles[0].config.dead_zone = 1.0;
+        store.profiles[0].config.mouse_sensitivity = -5.0;
+        store.profiles[0].config.scroll_sensitivity = f32::NAN;
+        let issues = repair_store(&mut store);
+        assert_eq!(paths(&issues), [
+            "profiles[0].config.mouse_sensitivity",
+            "profiles[0].config.dead_zone",
+            "profiles[0].config.scroll_sensitivity",
+        ]);
+        assert!(issues.iter().all(ValidationIssue::is_error));
+        assert_eq!(store.profiles[0].config.dead_zone, defaults.dead_zone);
+        assert_eq!(store.profiles[0].config.mouse_sensitivity, defaults.mouse_sensitivity);
+        assert_eq!(store.profiles[0].config.scroll_sensitivity, defaults.scroll_sensitivity);
+        assert_eq!(issues[0].to_string(), format!(
+            "错误: profiles[0].config.mouse_sensitivity: -5 必须大于 0，已恢复为默认值 {:?}",
+            defaults.mouse_sensitivity,
+        ));
+    }
+
+    #[test]
+    fn large_dead_zone_is_only_a_warning() {
+        let mut store = ProfileStore::default();
+        store.profiles[0].config.dead_zone = 0.6;
+        let issues = repair_store(&mut store);
+        assert_eq!(paths(&issues), ["profiles[0].config.dead_zone"]);
+        assert!(!issues[0].is_error());
+        assert_eq!(store.profiles[0].config.dead_zone, 0.6);
+    }
+
+    #[test]
+    fn reports_missing_profile_references() {
+        let mut store = ProfileStore::default();
+        store.app_rules.push(AppProfileRule { app: "firefox".to_string(), profile: "浏览".to_string() });
+        store.active_profile = "不存在".to_string();
+        store.model_profiles.insert(ControllerModel::Xbox, "游戏".to_string());
+        store.pad_profiles.insert("Pad".to_string(), "游戏".to_string());
+        let issues = validate_store(&store);
+        assert_eq!(paths(&issues), ["active_profile", "app_rules[0].profile", "model_profiles.Xbox", "pad_profiles[\"Pad\"]"]);
+        assert!(issues.iter().all(|issue| !issue.is_error()));
+    }
+
+    #[test]
+    fn mistyped_values_are_reset_without_failing_the_file() {
+        let migrated = migration::load_store(json!({
+            "version": 2,
+            "active_profile": "默认",
+            "profiles": [
+                {"name": "默认", "config": {"mouse_sensitivity": "fast", "dead_zone": 0.2}},
+                {"name": "第二", "config": {"invert_y_axis": 1, "mouse_acceleration": 2.0}},
+            ],
+            "multi_pad_policy": "Unknown",
+            "battery": {"threshold": 300},
+            "model_profiles": {"Xbox": "第二", "Gamecube": "默认"},
+        })).unwrap();
+        let defaults = ProfileStore::default();
+        assert_eq!(paths(&migrated.issues), [
+            "battery.threshold",
+            "model_profiles.Gamecube",
+            "multi_pad_policy",
+            "profiles[0].config.mouse_sensitivity",
+            "profiles[1].config.invert_y_axis",
+        ]);
+        assert!(migrated.issues.iter().all(ValidationIssue::is_error));
+        assert!(migrated.issues[3].message.starts_with("invalid type: string \"fast\", expected f32"), "{}", migrated.issues[3].message);
+
+        let store = migrated.store;
+        assert_eq!(store.profiles[0].config.mouse_sensitivity, Config::default().mouse_sensitivity);
+        assert_eq!(store.profiles[0].config.dead_zone, 0.2);
+        assert!(!store.profiles[1].config.invert_y_axis);
+        assert_eq!(store.profiles[1].config.mouse_acceleration, 2.0);
+        assert_eq!(store.battery.threshold, defaults.battery.threshold);
+        assert_eq!(store.multi_pad_policy, defaults.multi_pad_policy);
+        assert_eq!(store.model_profiles.get(&ControllerModel::Xbox).map(String::as_str), Some("第二"));
+        assert_eq!(store.model_profiles.len(), 1);
+    }
+
+    #[test]
+    fn valid_values_are_not_reported() {
+        let mut value = serde_json::to_value(ProfileStore::default()).unwrap();
+        assert!(repair_value(&mut value).unwrap().is_empty());
+    }
+}